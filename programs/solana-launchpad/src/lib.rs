@@ -1,12 +1,37 @@
 #![allow(unexpected_cfgs)]
 #![allow(deprecated)]
+#![allow(clippy::too_many_arguments)]
 
-use anchor_lang::{prelude::*, AnchorDeserialize, AnchorSerialize};
-use anchor_spl::token::{self, Mint, MintTo, Token, TokenAccount, Transfer};
+use anchor_lang::{
+    prelude::*, solana_program::keccak, system_program, AnchorDeserialize, AnchorSerialize,
+};
+use anchor_spl::{
+    token::{self, Mint, Token, TokenAccount, Transfer},
+    token_2022::spl_token_2022::{
+        extension::{
+            default_account_state::DefaultAccountState, non_transferable::NonTransferable,
+            permanent_delegate::PermanentDelegate, transfer_fee::TransferFeeConfig,
+            transfer_hook::TransferHook, BaseStateWithExtensions, StateWithExtensions,
+        },
+        state::{AccountState, Mint as MintState},
+    },
+    token_interface::{self, TokenInterface, TransferChecked},
+};
+use light_ctoken_sdk::compressed_token::{
+    transfer::{
+        account_metas::TokenAccountsMetaConfig,
+        instruction::{create_transfer_instruction_raw, TransferConfig},
+    },
+    CTokenAccount, TokenAccountMeta,
+};
+use light_ctoken_types::{constants::MINT_TO, instruction::mint_to::MintToParams};
 use light_sdk::{
     account::LightAccount,
-    address::v1::derive_address,
-    cpi::{v1::CpiAccounts, CpiSigner},
+    address::{v1::derive_address, PackedNewAddressParams},
+    cpi::{
+        v1::{CpiAccounts, LightSystemProgramCpi},
+        CpiSigner, InvokeLightSystemProgram, LightCpiInstruction,
+    },
     derive_light_cpi_signer,
     instruction::{account_meta::CompressedAccountMeta, PackedAddressTreeInfo, ValidityProof},
     LightDiscriminator,
@@ -20,7 +45,18 @@ pub const LIGHT_CPI_SIGNER: CpiSigner =
 
 /// Compressed Token Program ID (Light Protocol)
 /// cTokenmWW8bLPjZEBAUgYy3zKxQZW6VKi7bqNFEVv3m
-pub const COMPRESSED_TOKEN_PROGRAM_ID: &str = "cTokenmWW8bLPjZEBAUgYy3zKxQZW6VKi7bqNFEVv3m";
+pub const COMPRESSED_TOKEN_PROGRAM_ID: Pubkey =
+    pubkey!("cTokenmWW8bLPjZEBAUgYy3zKxQZW6VKi7bqNFEVv3m");
+
+/// Address tree holding every compressed TokenSale address (Light v1 address tree)
+/// amt1Ayt45jfbdw5YSo7iz6WZxUmnZsQTYXy82hVwyC2
+pub const COMPRESSED_SALE_ADDRESS_TREE: Pubkey =
+    pubkey!("amt1Ayt45jfbdw5YSo7iz6WZxUmnZsQTYXy82hVwyC2");
+
+/// CPI authority PDA of the Compressed Token Program
+/// Derived from [b"cpi_authority"] under COMPRESSED_TOKEN_PROGRAM_ID
+pub const COMPRESSED_TOKEN_CPI_AUTHORITY: Pubkey =
+    pubkey!("GXtd2izAiMJPwMEjfgTRH3d7k9mjn4Jq3JrWFv9gySYy");
 
 // ==========================
 // Account Structs
@@ -39,6 +75,17 @@ pub struct Initialize<'info> {
     )]
     pub app_state: Account<'info, AppState>,
 
+    pub usdc_mint: Account<'info, Mint>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + QuoteMintEntry::INIT_SPACE,
+        seeds = [b"quote_mint", usdc_mint.key().as_ref()],
+        bump
+    )]
+    pub usdc_mint_entry: Account<'info, QuoteMintEntry>,
+
     pub system_program: Program<'info, System>,
 }
 
@@ -49,6 +96,88 @@ pub struct UpdateFee<'info> {
     pub owner: Signer<'info>,
 }
 
+#[derive(Accounts)]
+pub struct UpdateFeeRecipient<'info> {
+    #[account(mut, has_one = owner)]
+    pub app_state: Account<'info, AppState>,
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetPaused<'info> {
+    #[account(mut, has_one = owner)]
+    pub app_state: Account<'info, AppState>,
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeOwner<'info> {
+    #[account(mut, has_one = owner)]
+    pub app_state: Account<'info, AppState>,
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptOwner<'info> {
+    #[account(
+        mut,
+        constraint = app_state.pending_owner == Some(new_owner.key()) @ ErrorCode::Unauthorized,
+    )]
+    pub app_state: Account<'info, AppState>,
+    pub new_owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AddQuoteMint<'info> {
+    #[account(has_one = owner)]
+    pub app_state: Account<'info, AppState>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub quote_mint: Account<'info, Mint>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + QuoteMintEntry::INIT_SPACE,
+        seeds = [b"quote_mint", quote_mint.key().as_ref()],
+        bump
+    )]
+    pub quote_mint_entry: Account<'info, QuoteMintEntry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateQuoteMint<'info> {
+    #[account(has_one = owner)]
+    pub app_state: Account<'info, AppState>,
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"quote_mint", quote_mint_entry.mint.as_ref()],
+        bump = quote_mint_entry.bump,
+    )]
+    pub quote_mint_entry: Account<'info, QuoteMintEntry>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveQuoteMint<'info> {
+    #[account(has_one = owner)]
+    pub app_state: Account<'info, AppState>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"quote_mint", quote_mint_entry.mint.as_ref()],
+        bump = quote_mint_entry.bump,
+    )]
+    pub quote_mint_entry: Account<'info, QuoteMintEntry>,
+}
+
 #[derive(Accounts)]
 pub struct LaunchToken<'info> {
     #[account(mut)]
@@ -66,17 +195,67 @@ pub struct LaunchToken<'info> {
     #[account(
         mut,
         mint::authority = token_sale,
+        mint::token_program = token_program,
     )]
-    pub token_mint: Account<'info, Mint>,
+    pub token_mint: InterfaceAccount<'info, token_interface::Mint>,
 
     #[account(
         mut,
         constraint = sale_token_account.mint == token_mint.key() @ ErrorCode::InvalidMint,
         constraint = sale_token_account.owner == token_sale.key() @ ErrorCode::InvalidTokenAccountOwner,
     )]
-    pub sale_token_account: Account<'info, TokenAccount>,
+    pub sale_token_account: InterfaceAccount<'info, token_interface::TokenAccount>,
 
-    pub token_program: Program<'info, Token>,
+    /// Holds purchases until they vest (required by vesting sales)
+    #[account(
+        init,
+        payer = creator,
+        seeds = [b"vesting_vault", token_mint.key().as_ref()],
+        bump,
+        token::mint = token_mint,
+        token::authority = token_sale,
+        token::token_program = token_program,
+    )]
+    pub vesting_vault: Option<InterfaceAccount<'info, token_interface::TokenAccount>>,
+
+    /// Registry entry of the sale's quote mint (required unless paying in SOL)
+    #[account(
+        seeds = [b"quote_mint", quote_mint_entry.mint.as_ref()],
+        bump = quote_mint_entry.bump,
+    )]
+    pub quote_mint_entry: Option<Account<'info, QuoteMintEntry>>,
+
+    /// Quote mint of the sale (required unless paying in SOL)
+    pub quote_mint: Option<Account<'info, Mint>>,
+
+    /// Holds the sale's quote tokens: payments in transit, curve and auction
+    /// reserves, soft-cap escrow and escrowed proceeds (required unless paying in SOL)
+    #[account(
+        init,
+        payer = creator,
+        seeds = [b"escrow", token_mint.key().as_ref()],
+        bump,
+        token::mint = quote_mint,
+        token::authority = program_authority,
+        token::token_program = quote_token_program,
+    )]
+    pub sale_escrow: Option<Account<'info, TokenAccount>>,
+
+    #[account(
+        seeds = [b"app_state"],
+        bump,
+        constraint = !app_state.paused @ ErrorCode::ProgramPaused,
+    )]
+    pub app_state: Account<'info, AppState>,
+
+    /// CHECK: Program authority PDA
+    #[account(seeds = [b"authority"], bump)]
+    pub program_authority: AccountInfo<'info>,
+
+    /// Token program of the sale mint (SPL Token or Token-2022)
+    pub token_program: Interface<'info, TokenInterface>,
+    /// Token program of the quote mint
+    pub quote_token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
 }
 
@@ -86,7 +265,7 @@ pub struct LaunchToken<'info> {
 /// ARCHITECTURE FOR COMPRESSED TOKENS:
 /// 1. This instruction creates a compressed TokenSale state (rent-free via Light Protocol)
 /// 2. The token mint should have a token pool registered (done client-side via createTokenPool)
-/// 3. Compressed tokens are minted client-side via mintTo from @lightprotocol/compressed-token
+/// 3. The full supply is minted on-chain as compressed tokens to the sale_authority PDA
 /// 4. The sale_authority PDA is used as the mint authority for the compressed token mint
 /// 5. Buyers receive compressed tokens (not standard SPL tokens) - ~5000x cheaper
 #[derive(Accounts)]
@@ -95,8 +274,11 @@ pub struct LaunchTokenCompressed<'info> {
     pub creator: Signer<'info>,
 
     /// Token mint - must have a token pool registered for compression
-    /// The sale_authority PDA should be the mint authority
-    #[account(mut)]
+    /// The sale_authority PDA must be the mint authority
+    #[account(
+        mut,
+        mint::authority = sale_authority,
+    )]
     pub token_mint: Account<'info, Mint>,
 
     /// CHECK: Sale authority PDA - used as mint authority for compressed tokens
@@ -107,6 +289,38 @@ pub struct LaunchTokenCompressed<'info> {
     )]
     pub sale_authority: AccountInfo<'info>,
 
+    /// CHECK: Token pool PDA of the mint - validated by the Compressed Token Program
+    #[account(
+        mut,
+        seeds = [b"pool", token_mint.key().as_ref()],
+        bump,
+        seeds::program = COMPRESSED_TOKEN_PROGRAM_ID,
+    )]
+    pub token_pool: AccountInfo<'info>,
+
+    /// CHECK: Light Compressed Token Program
+    #[account(address = COMPRESSED_TOKEN_PROGRAM_ID)]
+    pub compressed_token_program: AccountInfo<'info>,
+
+    /// CHECK: Compressed Token Program CPI authority PDA
+    #[account(address = COMPRESSED_TOKEN_CPI_AUTHORITY)]
+    pub compressed_token_cpi_authority: AccountInfo<'info>,
+
+    /// Registry entry of the sale's quote mint (required unless paying in SOL)
+    #[account(
+        seeds = [b"quote_mint", quote_mint_entry.mint.as_ref()],
+        bump = quote_mint_entry.bump,
+    )]
+    pub quote_mint_entry: Option<Account<'info, QuoteMintEntry>>,
+
+    #[account(
+        seeds = [b"app_state"],
+        bump,
+        constraint = !app_state.paused @ ErrorCode::ProgramPaused,
+    )]
+    pub app_state: Account<'info, AppState>,
+
+    pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
 }
 
@@ -115,40 +329,64 @@ pub struct BuyTokens<'info> {
     #[account(mut)]
     pub buyer: Signer<'info>,
 
+    /// Required by paid quote-mint sales
     #[account(
         mut,
         constraint = buyer_usdc_account.owner == buyer.key() @ ErrorCode::InvalidTokenAccountOwner,
-        constraint = buyer_usdc_account.mint == app_state.usdc_mint @ ErrorCode::InvalidMint,
+        constraint = buyer_usdc_account.mint == token_sale.quote_mint @ ErrorCode::InvalidMint,
     )]
-    pub buyer_usdc_account: Account<'info, TokenAccount>,
+    pub buyer_usdc_account: Option<Account<'info, TokenAccount>>,
 
     #[account(
         mut,
         constraint = buyer_token_account.owner == buyer.key() @ ErrorCode::InvalidTokenAccountOwner,
         constraint = buyer_token_account.mint == token_mint.key() @ ErrorCode::InvalidMint,
     )]
-    pub buyer_token_account: Account<'info, TokenAccount>,
+    pub buyer_token_account: InterfaceAccount<'info, token_interface::TokenAccount>,
 
+    /// Required by paid quote-mint sales
     #[account(
         mut,
         constraint = creator_usdc_account.owner == token_sale.creator @ ErrorCode::InvalidTokenAccountOwner,
-        constraint = creator_usdc_account.mint == app_state.usdc_mint @ ErrorCode::InvalidMint,
+        constraint = creator_usdc_account.mint == token_sale.quote_mint @ ErrorCode::InvalidMint,
     )]
-    pub creator_usdc_account: Account<'info, TokenAccount>,
+    pub creator_usdc_account: Option<Account<'info, TokenAccount>>,
+
+    /// Creator wallet, paid in lamports by SOL sales
+    #[account(mut, address = token_sale.creator @ ErrorCode::InvalidPaymentAccount)]
+    pub creator: Option<SystemAccount<'info>>,
+
+    /// Fee recipient wallet, paid in lamports by SOL sales
+    #[account(mut, address = app_state.fee_recipient @ ErrorCode::InvalidPaymentAccount)]
+    pub fee_recipient: Option<SystemAccount<'info>>,
 
+    /// Required by paid quote-mint sales
     #[account(
         mut,
-        constraint = owner_usdc_account.owner == app_state.owner @ ErrorCode::InvalidTokenAccountOwner,
-        constraint = owner_usdc_account.mint == app_state.usdc_mint @ ErrorCode::InvalidMint,
+        constraint = fee_recipient_usdc_account.owner == app_state.fee_recipient @ ErrorCode::InvalidTokenAccountOwner,
+        constraint = fee_recipient_usdc_account.mint == token_sale.quote_mint @ ErrorCode::InvalidMint,
     )]
-    pub owner_usdc_account: Account<'info, TokenAccount>,
+    pub fee_recipient_usdc_account: Option<Account<'info, TokenAccount>>,
 
+    /// Referrer wallet, credited with the referral share of the platform fee
+    /// (paid in lamports by SOL sales)
+    #[account(mut, constraint = referrer.key() != buyer.key() @ ErrorCode::SelfReferral)]
+    pub referrer: Option<SystemAccount<'info>>,
+
+    /// Referrer's quote token account (referred quote-mint sales)
     #[account(
         mut,
-        constraint = program_usdc_account.owner == program_authority.key() @ ErrorCode::InvalidTokenAccountOwner,
-        constraint = program_usdc_account.mint == app_state.usdc_mint @ ErrorCode::InvalidMint,
+        constraint = referrer_usdc_account.mint == token_sale.quote_mint @ ErrorCode::InvalidMint,
+    )]
+    pub referrer_usdc_account: Option<Account<'info, TokenAccount>>,
+
+    /// Per-sale escrow of the quote mint (required by paid quote-mint sales)
+    #[account(
+        mut,
+        seeds = [b"escrow", token_mint.key().as_ref()],
+        bump,
     )]
-    pub program_usdc_account: Account<'info, TokenAccount>,
+    pub sale_escrow: Option<Account<'info, TokenAccount>>,
 
     #[account(
         mut,
@@ -156,60 +394,119 @@ pub struct BuyTokens<'info> {
     )]
     pub token_sale: Account<'info, TokenSale>,
 
-    #[account(mut)]
-    pub token_mint: Account<'info, Mint>,
+    #[account(mut, mint::token_program = sale_token_program)]
+    pub token_mint: InterfaceAccount<'info, token_interface::Mint>,
 
     #[account(
         mut,
         constraint = sale_token_account.mint == token_mint.key() @ ErrorCode::InvalidMint,
         constraint = sale_token_account.owner == token_sale.key() @ ErrorCode::InvalidTokenAccountOwner,
     )]
-    pub sale_token_account: Account<'info, TokenAccount>,
+    pub sale_token_account: InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    /// Receives the purchase in place of buyer_token_account on vesting sales
+    #[account(
+        mut,
+        seeds = [b"vesting_vault", token_mint.key().as_ref()],
+        bump,
+    )]
+    pub vesting_vault: Option<InterfaceAccount<'info, token_interface::TokenAccount>>,
 
     #[account(
         mut,
         seeds = [b"app_state"],
         bump,
+        constraint = !app_state.paused @ ErrorCode::ProgramPaused,
     )]
     pub app_state: Account<'info, AppState>,
 
+    /// Registry entry of the sale's quote mint (required by paid quote-mint sales)
+    #[account(
+        seeds = [b"quote_mint", token_sale.quote_mint.as_ref()],
+        bump = quote_mint_entry.bump,
+    )]
+    pub quote_mint_entry: Option<Account<'info, QuoteMintEntry>>,
+
     /// CHECK: Program authority PDA
     #[account(seeds = [b"authority"], bump)]
     pub program_authority: AccountInfo<'info>,
 
     pub token_program: Program<'info, Token>,
+    /// Token program of the sale mint (SPL Token or Token-2022)
+    pub sale_token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
 }
 
 /// Buy tokens using compressed TokenSale state
 ///
 /// ARCHITECTURE FOR COMPRESSED TOKEN PURCHASES:
 /// 1. This instruction handles USDC payment and updates compressed sale state
-/// 2. Compressed tokens are transferred client-side via transfer() from @lightprotocol/compressed-token
+/// 2. Compressed tokens are transferred on-chain via CPI to the Compressed Token Program
 /// 3. The sale_authority PDA holds the compressed tokens and signs transfers
 /// 4. Buyers receive compressed tokens directly to their wallet (no token account rent needed)
 #[derive(Accounts)]
+#[instruction(proof: ValidityProof, current_sale: CompressedTokenSale)]
 pub struct BuyTokensCompressed<'info> {
     #[account(mut)]
     pub buyer: Signer<'info>,
 
-    /// Buyer's USDC account for payment
-    #[account(mut)]
-    pub buyer_usdc_account: Account<'info, TokenAccount>,
+    /// Buyer's quote token account for payment (paid quote-mint sales)
+    #[account(
+        mut,
+        constraint = buyer_usdc_account.owner == buyer.key() @ ErrorCode::InvalidTokenAccountOwner,
+        constraint = buyer_usdc_account.mint == current_sale.quote_mint @ ErrorCode::InvalidMint,
+    )]
+    pub buyer_usdc_account: Option<Account<'info, TokenAccount>>,
 
-    /// Creator's USDC account to receive payment
-    #[account(mut)]
-    pub creator_usdc_account: Account<'info, TokenAccount>,
+    /// Creator's quote token account to receive payment (paid quote-mint sales)
+    #[account(
+        mut,
+        constraint = creator_usdc_account.owner == current_sale.creator @ ErrorCode::InvalidTokenAccountOwner,
+        constraint = creator_usdc_account.mint == current_sale.quote_mint @ ErrorCode::InvalidMint,
+    )]
+    pub creator_usdc_account: Option<Account<'info, TokenAccount>>,
 
-    /// Platform owner's USDC account for fees
-    #[account(mut)]
-    pub owner_usdc_account: Account<'info, TokenAccount>,
+    /// Creator wallet, paid in lamports by SOL sales
+    #[account(mut, address = current_sale.creator @ ErrorCode::InvalidPaymentAccount)]
+    pub creator: Option<SystemAccount<'info>>,
 
-    /// Program's USDC escrow account
-    #[account(mut)]
-    pub program_usdc_account: Account<'info, TokenAccount>,
+    /// Fee recipient wallet, paid in lamports by SOL sales
+    #[account(mut, address = app_state.fee_recipient @ ErrorCode::InvalidPaymentAccount)]
+    pub fee_recipient: Option<SystemAccount<'info>>,
+
+    /// Fee recipient's quote token account for platform fees (paid quote-mint sales)
+    #[account(
+        mut,
+        constraint = fee_recipient_usdc_account.owner == app_state.fee_recipient @ ErrorCode::InvalidTokenAccountOwner,
+        constraint = fee_recipient_usdc_account.mint == current_sale.quote_mint @ ErrorCode::InvalidMint,
+    )]
+    pub fee_recipient_usdc_account: Option<Account<'info, TokenAccount>>,
+
+    /// Referrer wallet, credited with the referral share of the platform fee
+    /// (paid in lamports by SOL sales)
+    #[account(mut, constraint = referrer.key() != buyer.key() @ ErrorCode::SelfReferral)]
+    pub referrer: Option<SystemAccount<'info>>,
+
+    /// Referrer's quote token account (referred quote-mint sales)
+    #[account(
+        mut,
+        constraint = referrer_usdc_account.mint == current_sale.quote_mint @ ErrorCode::InvalidMint,
+    )]
+    pub referrer_usdc_account: Option<Account<'info, TokenAccount>>,
+
+    /// Program's quote token escrow account (paid quote-mint sales)
+    #[account(
+        mut,
+        constraint = program_usdc_account.owner == program_authority.key() @ ErrorCode::InvalidTokenAccountOwner,
+        constraint = program_usdc_account.mint == current_sale.quote_mint @ ErrorCode::InvalidMint,
+    )]
+    pub program_usdc_account: Option<Account<'info, TokenAccount>>,
 
     /// Token mint with compression enabled
-    #[account(mut)]
+    #[account(
+        mut,
+        constraint = current_sale.token_mint == token_mint.key() @ ErrorCode::InvalidMint,
+    )]
     pub token_mint: Account<'info, Mint>,
 
     /// App state for fee configuration
@@ -217,9 +514,17 @@ pub struct BuyTokensCompressed<'info> {
         mut,
         seeds = [b"app_state"],
         bump,
+        constraint = !app_state.paused @ ErrorCode::ProgramPaused,
     )]
     pub app_state: Account<'info, AppState>,
 
+    /// Registry entry of the sale's quote mint (required by paid quote-mint sales)
+    #[account(
+        seeds = [b"quote_mint", current_sale.quote_mint.as_ref()],
+        bump = quote_mint_entry.bump,
+    )]
+    pub quote_mint_entry: Option<Account<'info, QuoteMintEntry>>,
+
     /// CHECK: Program authority PDA for USDC transfers
     #[account(seeds = [b"authority"], bump)]
     pub program_authority: AccountInfo<'info>,
@@ -231,7 +536,23 @@ pub struct BuyTokensCompressed<'info> {
     )]
     pub sale_authority: AccountInfo<'info>,
 
+    /// CHECK: Vesting authority PDA - holds purchases of vesting sales until claimed
+    #[account(
+        seeds = [b"vesting_authority", token_mint.key().as_ref()],
+        bump,
+    )]
+    pub vesting_authority: AccountInfo<'info>,
+
+    /// CHECK: Light Compressed Token Program
+    #[account(address = COMPRESSED_TOKEN_PROGRAM_ID)]
+    pub compressed_token_program: AccountInfo<'info>,
+
+    /// CHECK: Compressed Token Program CPI authority PDA
+    #[account(address = COMPRESSED_TOKEN_CPI_AUTHORITY)]
+    pub compressed_token_cpi_authority: AccountInfo<'info>,
+
     pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
@@ -246,79 +567,572 @@ pub struct CloseSale<'info> {
     )]
     pub token_sale: Account<'info, TokenSale>,
 
-    #[account(mut)]
-    pub token_mint: Account<'info, Mint>,
+    #[account(mut, mint::token_program = token_program)]
+    pub token_mint: InterfaceAccount<'info, token_interface::Mint>,
 
     #[account(
         mut,
         constraint = sale_token_account.mint == token_mint.key() @ ErrorCode::InvalidMint,
         constraint = sale_token_account.owner == token_sale.key() @ ErrorCode::InvalidTokenAccountOwner,
     )]
-    pub sale_token_account: Account<'info, TokenAccount>,
+    pub sale_token_account: InterfaceAccount<'info, token_interface::TokenAccount>,
 
     #[account(
         mut,
         constraint = creator_token_account.mint == token_mint.key() @ ErrorCode::InvalidMint,
         constraint = creator_token_account.owner == creator.key() @ ErrorCode::InvalidTokenAccountOwner,
     )]
-    pub creator_token_account: Account<'info, TokenAccount>,
+    pub creator_token_account: InterfaceAccount<'info, token_interface::TokenAccount>,
 
-    pub token_program: Program<'info, Token>,
+    /// Token program of the sale mint (SPL Token or Token-2022)
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
-/// Close sale using compressed TokenSale state
-///
-/// ARCHITECTURE FOR CLOSING COMPRESSED TOKEN SALES:
-/// 1. This instruction updates the compressed sale state to inactive
-/// 2. Remaining compressed tokens are transferred client-side back to creator
-/// 3. The sale_authority PDA signs the compressed token transfer
+/// Permissionless close of a standard sale once its end_ts has passed.
+/// Releases the escrow of a sale that met its soft cap.
 #[derive(Accounts)]
-pub struct CloseSaleCompressed<'info> {
-    #[account(mut)]
-    pub creator: Signer<'info>,
+pub struct FinalizeSale<'info> {
+    #[account(
+        mut,
+        constraint = token_sale.token_mint == token_mint.key() @ ErrorCode::InvalidMint,
+    )]
+    pub token_sale: Account<'info, TokenSale>,
 
-    /// Token mint with compression enabled
-    #[account(mut)]
-    pub token_mint: Account<'info, Mint>,
+    #[account(mut, mint::token_program = sale_token_program)]
+    pub token_mint: InterfaceAccount<'info, token_interface::Mint>,
 
-    /// CHECK: Sale authority PDA - holds compressed tokens
     #[account(
-        seeds = [b"sale_authority", token_mint.key().as_ref()],
+        mut,
+        constraint = sale_token_account.mint == token_mint.key() @ ErrorCode::InvalidMint,
+        constraint = sale_token_account.owner == token_sale.key() @ ErrorCode::InvalidTokenAccountOwner,
+    )]
+    pub sale_token_account: InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = creator_token_account.mint == token_mint.key() @ ErrorCode::InvalidMint,
+        constraint = creator_token_account.owner == token_sale.creator @ ErrorCode::InvalidTokenAccountOwner,
+    )]
+    pub creator_token_account: InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    /// Quote token accounts paid out when a soft cap was met
+    #[account(
+        mut,
+        constraint = creator_usdc_account.owner == token_sale.creator @ ErrorCode::InvalidTokenAccountOwner,
+        constraint = creator_usdc_account.mint == token_sale.quote_mint @ ErrorCode::InvalidMint,
+    )]
+    pub creator_usdc_account: Option<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = fee_recipient_usdc_account.owner == app_state.fee_recipient @ ErrorCode::InvalidTokenAccountOwner,
+        constraint = fee_recipient_usdc_account.mint == token_sale.quote_mint @ ErrorCode::InvalidMint,
+    )]
+    pub fee_recipient_usdc_account: Option<Account<'info, TokenAccount>>,
+
+    /// Per-sale escrow paid out when a soft cap was met
+    #[account(
+        mut,
+        seeds = [b"escrow", token_mint.key().as_ref()],
         bump,
     )]
-    pub sale_authority: AccountInfo<'info>,
-}
+    pub sale_escrow: Option<Account<'info, TokenAccount>>,
 
-// ==========================
-// Program
-// ==========================
-#[program]
-pub mod gasless_launchpad {
-    use super::*;
-    use light_sdk::cpi::{
-        v1::LightSystemProgramCpi, InvokeLightSystemProgram, LightCpiInstruction,
-    };
+    #[account(seeds = [b"app_state"], bump)]
+    pub app_state: Account<'info, AppState>,
 
-    pub fn initialize(
-        ctx: Context<Initialize>,
-        usdc_mint: Pubkey,
-        platform_fee_bps: u16,
-    ) -> Result<()> {
-        require!(platform_fee_bps <= 1000, ErrorCode::InvalidFee);
-        let state = &mut ctx.accounts.app_state;
-        state.owner = ctx.accounts.owner.key();
-        state.usdc_mint = usdc_mint;
-        state.platform_fee_bps = platform_fee_bps;
-        Ok(())
-    }
+    /// CHECK: Program authority PDA
+    #[account(seeds = [b"authority"], bump)]
+    pub program_authority: AccountInfo<'info>,
 
-    pub fn update_fee(ctx: Context<UpdateFee>, new_fee_bps: u16) -> Result<()> {
-        require!(new_fee_bps <= 1000, ErrorCode::InvalidFee);
-        ctx.accounts.app_state.platform_fee_bps = new_fee_bps;
-        Ok(())
-    }
+    pub token_program: Program<'info, Token>,
+    /// Token program of the sale mint (SPL Token or Token-2022)
+    pub sale_token_program: Interface<'info, TokenInterface>,
+}
 
-    /// Launch a token with standard (non-compressed) TokenSale PDA
+/// Sell tokens back to a bonding-curve sale, paid from its USDC reserve
+#[derive(Accounts)]
+pub struct SellTokens<'info> {
+    #[account(mut)]
+    pub seller: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = seller_usdc_account.owner == seller.key() @ ErrorCode::InvalidTokenAccountOwner,
+        constraint = seller_usdc_account.mint == token_sale.quote_mint @ ErrorCode::InvalidMint,
+    )]
+    pub seller_usdc_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = seller_token_account.owner == seller.key() @ ErrorCode::InvalidTokenAccountOwner,
+        constraint = seller_token_account.mint == token_mint.key() @ ErrorCode::InvalidMint,
+    )]
+    pub seller_token_account: InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    /// Per-sale escrow holding the curve reserve
+    #[account(
+        mut,
+        seeds = [b"escrow", token_mint.key().as_ref()],
+        bump,
+    )]
+    pub sale_escrow: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = token_sale.token_mint == token_mint.key() @ ErrorCode::InvalidMint,
+    )]
+    pub token_sale: Account<'info, TokenSale>,
+
+    #[account(mint::token_program = sale_token_program)]
+    pub token_mint: InterfaceAccount<'info, token_interface::Mint>,
+
+    #[account(
+        mut,
+        constraint = sale_token_account.mint == token_mint.key() @ ErrorCode::InvalidMint,
+        constraint = sale_token_account.owner == token_sale.key() @ ErrorCode::InvalidTokenAccountOwner,
+    )]
+    pub sale_token_account: InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    #[account(
+        seeds = [b"app_state"],
+        bump,
+        constraint = !app_state.paused @ ErrorCode::ProgramPaused,
+    )]
+    pub app_state: Account<'info, AppState>,
+
+    /// CHECK: Program authority PDA
+    #[account(seeds = [b"authority"], bump)]
+    pub program_authority: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+    /// Token program of the sale mint (SPL Token or Token-2022)
+    pub sale_token_program: Interface<'info, TokenInterface>,
+}
+
+/// Release a closed curve sale's USDC reserve to its creator
+#[derive(Accounts)]
+pub struct ClaimReserve<'info> {
+    pub creator: Signer<'info>,
+
+    #[account(mut, has_one = creator)]
+    pub token_sale: Account<'info, TokenSale>,
+
+    #[account(
+        mut,
+        constraint = creator_usdc_account.owner == creator.key() @ ErrorCode::InvalidTokenAccountOwner,
+        constraint = creator_usdc_account.mint == token_sale.quote_mint @ ErrorCode::InvalidMint,
+    )]
+    pub creator_usdc_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = fee_recipient_usdc_account.owner == app_state.fee_recipient @ ErrorCode::InvalidTokenAccountOwner,
+        constraint = fee_recipient_usdc_account.mint == token_sale.quote_mint @ ErrorCode::InvalidMint,
+    )]
+    pub fee_recipient_usdc_account: Account<'info, TokenAccount>,
+
+    /// Per-sale escrow holding the reserve
+    #[account(
+        mut,
+        seeds = [b"escrow", token_sale.token_mint.as_ref()],
+        bump,
+    )]
+    pub sale_escrow: Account<'info, TokenAccount>,
+
+    #[account(seeds = [b"app_state"], bump)]
+    pub app_state: Account<'info, AppState>,
+
+    /// CHECK: Program authority PDA
+    #[account(seeds = [b"authority"], bump)]
+    pub program_authority: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+/// Withdraw the creator's vested proceeds from the escrow
+#[derive(Accounts)]
+pub struct WithdrawProceeds<'info> {
+    pub creator: Signer<'info>,
+
+    #[account(mut, has_one = creator)]
+    pub token_sale: Account<'info, TokenSale>,
+
+    #[account(
+        mut,
+        constraint = creator_usdc_account.owner == creator.key() @ ErrorCode::InvalidTokenAccountOwner,
+        constraint = creator_usdc_account.mint == token_sale.quote_mint @ ErrorCode::InvalidMint,
+    )]
+    pub creator_usdc_account: Account<'info, TokenAccount>,
+
+    /// Per-sale escrow holding the creator share
+    #[account(
+        mut,
+        seeds = [b"escrow", token_sale.token_mint.as_ref()],
+        bump,
+    )]
+    pub sale_escrow: Account<'info, TokenAccount>,
+
+    /// CHECK: Program authority PDA
+    #[account(seeds = [b"authority"], bump)]
+    pub program_authority: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+/// Refund a uniform-clearing auction buyer from the sale reserve
+#[derive(Accounts)]
+pub struct ClaimAuctionRefund<'info> {
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = buyer_usdc_account.owner == buyer.key() @ ErrorCode::InvalidTokenAccountOwner,
+        constraint = buyer_usdc_account.mint == token_sale.quote_mint @ ErrorCode::InvalidMint,
+    )]
+    pub buyer_usdc_account: Account<'info, TokenAccount>,
+
+    /// Per-sale escrow holding the auction reserve
+    #[account(
+        mut,
+        seeds = [b"escrow", token_sale.token_mint.as_ref()],
+        bump,
+    )]
+    pub sale_escrow: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub token_sale: Account<'info, TokenSale>,
+
+    #[account(seeds = [b"app_state"], bump)]
+    pub app_state: Account<'info, AppState>,
+
+    /// CHECK: Program authority PDA
+    #[account(seeds = [b"authority"], bump)]
+    pub program_authority: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+/// Refund a buyer of a sale that closed below its soft cap
+#[derive(Accounts)]
+pub struct ClaimRefund<'info> {
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = buyer_usdc_account.owner == buyer.key() @ ErrorCode::InvalidTokenAccountOwner,
+        constraint = buyer_usdc_account.mint == token_sale.quote_mint @ ErrorCode::InvalidMint,
+    )]
+    pub buyer_usdc_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = buyer_token_account.owner == buyer.key() @ ErrorCode::InvalidTokenAccountOwner,
+        constraint = buyer_token_account.mint == token_mint.key() @ ErrorCode::InvalidMint,
+    )]
+    pub buyer_token_account: InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    /// Per-sale escrow holding the soft-cap payments
+    #[account(
+        mut,
+        seeds = [b"escrow", token_mint.key().as_ref()],
+        bump,
+    )]
+    pub sale_escrow: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = token_sale.token_mint == token_mint.key() @ ErrorCode::InvalidMint,
+    )]
+    pub token_sale: Account<'info, TokenSale>,
+
+    #[account(mut, mint::token_program = sale_token_program)]
+    pub token_mint: InterfaceAccount<'info, token_interface::Mint>,
+
+    #[account(seeds = [b"app_state"], bump)]
+    pub app_state: Account<'info, AppState>,
+
+    /// CHECK: Program authority PDA
+    #[account(seeds = [b"authority"], bump)]
+    pub program_authority: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+    /// Token program of the sale mint (SPL Token or Token-2022)
+    pub sale_token_program: Interface<'info, TokenInterface>,
+}
+
+/// Release a buyer's vested tokens from the vesting vault
+#[derive(Accounts)]
+pub struct ClaimVested<'info> {
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = buyer_token_account.owner == buyer.key() @ ErrorCode::InvalidTokenAccountOwner,
+        constraint = buyer_token_account.mint == token_mint.key() @ ErrorCode::InvalidMint,
+    )]
+    pub buyer_token_account: InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    #[account(
+        constraint = token_sale.token_mint == token_mint.key() @ ErrorCode::InvalidMint,
+    )]
+    pub token_sale: Account<'info, TokenSale>,
+
+    #[account(mint::token_program = sale_token_program)]
+    pub token_mint: InterfaceAccount<'info, token_interface::Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"vesting_vault", token_mint.key().as_ref()],
+        bump,
+    )]
+    pub vesting_vault: InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    /// Token program of the sale mint (SPL Token or Token-2022)
+    pub sale_token_program: Interface<'info, TokenInterface>,
+}
+
+/// Close sale using compressed TokenSale state
+///
+/// ARCHITECTURE FOR CLOSING COMPRESSED TOKEN SALES:
+/// 1. This instruction updates the compressed sale state to inactive
+/// 2. Remaining compressed tokens are transferred on-chain back to creator
+/// 3. The sale_authority PDA signs the compressed token transfer
+#[derive(Accounts)]
+#[instruction(proof: ValidityProof, current_sale: CompressedTokenSale)]
+pub struct CloseSaleCompressed<'info> {
+    #[account(
+        mut,
+        constraint = current_sale.creator == creator.key() @ ErrorCode::Unauthorized,
+    )]
+    pub creator: Signer<'info>,
+
+    /// Token mint with compression enabled
+    #[account(
+        mut,
+        constraint = current_sale.token_mint == token_mint.key() @ ErrorCode::InvalidMint,
+    )]
+    pub token_mint: Account<'info, Mint>,
+
+    /// CHECK: Sale authority PDA - holds compressed tokens
+    #[account(
+        seeds = [b"sale_authority", token_mint.key().as_ref()],
+        bump,
+    )]
+    pub sale_authority: AccountInfo<'info>,
+
+    /// CHECK: Light Compressed Token Program
+    #[account(address = COMPRESSED_TOKEN_PROGRAM_ID)]
+    pub compressed_token_program: AccountInfo<'info>,
+
+    /// CHECK: Compressed Token Program CPI authority PDA
+    #[account(address = COMPRESSED_TOKEN_CPI_AUTHORITY)]
+    pub compressed_token_cpi_authority: AccountInfo<'info>,
+}
+
+/// Permissionless close of a compressed sale once its end_ts has passed
+#[derive(Accounts)]
+#[instruction(proof: ValidityProof, current_sale: CompressedTokenSale)]
+pub struct FinalizeSaleCompressed<'info> {
+    /// Anyone may finalize - pays the Light Protocol fees
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// Token mint with compression enabled
+    #[account(
+        mut,
+        constraint = current_sale.token_mint == token_mint.key() @ ErrorCode::InvalidMint,
+    )]
+    pub token_mint: Account<'info, Mint>,
+
+    /// CHECK: Sale authority PDA - holds compressed tokens
+    #[account(
+        seeds = [b"sale_authority", token_mint.key().as_ref()],
+        bump,
+    )]
+    pub sale_authority: AccountInfo<'info>,
+
+    /// CHECK: Light Compressed Token Program
+    #[account(address = COMPRESSED_TOKEN_PROGRAM_ID)]
+    pub compressed_token_program: AccountInfo<'info>,
+
+    /// CHECK: Compressed Token Program CPI authority PDA
+    #[account(address = COMPRESSED_TOKEN_CPI_AUTHORITY)]
+    pub compressed_token_cpi_authority: AccountInfo<'info>,
+}
+
+/// Release a buyer's vested compressed tokens from the vesting authority
+#[derive(Accounts)]
+#[instruction(proof: ValidityProof, current_sale: CompressedTokenSale)]
+pub struct ClaimVestedCompressed<'info> {
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    /// Token mint with compression enabled
+    #[account(
+        constraint = current_sale.token_mint == token_mint.key() @ ErrorCode::InvalidMint,
+    )]
+    pub token_mint: Account<'info, Mint>,
+
+    /// CHECK: Vesting authority PDA - holds vesting compressed tokens
+    #[account(
+        seeds = [b"vesting_authority", token_mint.key().as_ref()],
+        bump,
+    )]
+    pub vesting_authority: AccountInfo<'info>,
+
+    /// CHECK: Light Compressed Token Program
+    #[account(address = COMPRESSED_TOKEN_PROGRAM_ID)]
+    pub compressed_token_program: AccountInfo<'info>,
+
+    /// CHECK: Compressed Token Program CPI authority PDA
+    #[account(address = COMPRESSED_TOKEN_CPI_AUTHORITY)]
+    pub compressed_token_cpi_authority: AccountInfo<'info>,
+}
+
+// ==========================
+// Program
+// ==========================
+#[program]
+pub mod gasless_launchpad {
+    use super::*;
+
+    /// Set up the launchpad and register `usdc_mint` as a quote mint with
+    /// `usdc_min_price`
+    pub fn initialize(
+        ctx: Context<Initialize>,
+        platform_fee_bps: u16,
+        usdc_min_price: u64,
+    ) -> Result<()> {
+        require!(platform_fee_bps <= 1000, ErrorCode::InvalidFee);
+        require!(usdc_min_price > 0, ErrorCode::InvalidMinPrice);
+        let entry = &mut ctx.accounts.usdc_mint_entry;
+        entry.mint = ctx.accounts.usdc_mint.key();
+        entry.decimals = ctx.accounts.usdc_mint.decimals;
+        entry.min_price = usdc_min_price;
+        entry.bump = ctx.bumps.usdc_mint_entry;
+
+        let state = &mut ctx.accounts.app_state;
+        state.owner = ctx.accounts.owner.key();
+        state.usdc_mint = ctx.accounts.usdc_mint.key();
+        state.platform_fee_bps = platform_fee_bps;
+        state.pending_owner = None;
+        state.fee_recipient = ctx.accounts.owner.key();
+        state.paused = false;
+        state.referral_fee_bps = 0;
+        Ok(())
+    }
+
+    pub fn update_fee(ctx: Context<UpdateFee>, new_fee_bps: u16) -> Result<()> {
+        require!(new_fee_bps <= 1000, ErrorCode::InvalidFee);
+        ctx.accounts.app_state.platform_fee_bps = new_fee_bps;
+        Ok(())
+    }
+
+    /// Set the share of the platform fee, in basis points of the fee, paid to
+    /// the referrer of a buy
+    pub fn update_referral_fee(ctx: Context<UpdateFee>, new_referral_fee_bps: u16) -> Result<()> {
+        require!(
+            new_referral_fee_bps <= 10_000,
+            ErrorCode::InvalidReferralFee
+        );
+        ctx.accounts.app_state.referral_fee_bps = new_referral_fee_bps;
+        Ok(())
+    }
+
+    /// Set the wallet whose USDC account receives platform fees
+    pub fn update_fee_recipient(
+        ctx: Context<UpdateFeeRecipient>,
+        new_fee_recipient: Pubkey,
+    ) -> Result<()> {
+        ctx.accounts.app_state.fee_recipient = new_fee_recipient;
+        Ok(())
+    }
+
+    /// Halt launches and buys. Creators can still close their sales.
+    pub fn pause(ctx: Context<SetPaused>) -> Result<()> {
+        ctx.accounts.app_state.paused = true;
+
+        emit!(LaunchpadPaused {
+            owner: ctx.accounts.owner.key(),
+        });
+
+        Ok(())
+    }
+
+    pub fn unpause(ctx: Context<SetPaused>) -> Result<()> {
+        ctx.accounts.app_state.paused = false;
+
+        emit!(LaunchpadUnpaused {
+            owner: ctx.accounts.owner.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Nominate a new platform owner. Takes effect once they call accept_owner.
+    pub fn propose_owner(ctx: Context<ProposeOwner>, new_owner: Pubkey) -> Result<()> {
+        ctx.accounts.app_state.pending_owner = Some(new_owner);
+
+        emit!(OwnershipProposed {
+            owner: ctx.accounts.owner.key(),
+            pending_owner: new_owner,
+        });
+
+        Ok(())
+    }
+
+    pub fn accept_owner(ctx: Context<AcceptOwner>) -> Result<()> {
+        let state = &mut ctx.accounts.app_state;
+        let previous_owner = state.owner;
+        state.owner = ctx.accounts.new_owner.key();
+        state.pending_owner = None;
+
+        emit!(OwnershipTransferred {
+            previous_owner,
+            new_owner: state.owner,
+        });
+
+        Ok(())
+    }
+
+    /// Accept `quote_mint` as a quote currency, with the lowest price_per_token
+    /// (and auction floor) sales may launch with (owner only)
+    pub fn add_quote_mint(ctx: Context<AddQuoteMint>, min_price: u64) -> Result<()> {
+        require!(min_price > 0, ErrorCode::InvalidMinPrice);
+        let entry = &mut ctx.accounts.quote_mint_entry;
+        entry.mint = ctx.accounts.quote_mint.key();
+        entry.decimals = ctx.accounts.quote_mint.decimals;
+        entry.min_price = min_price;
+        entry.bump = ctx.bumps.quote_mint_entry;
+        Ok(())
+    }
+
+    /// Change a registered quote mint's minimum price (owner only)
+    pub fn update_quote_mint(ctx: Context<UpdateQuoteMint>, min_price: u64) -> Result<()> {
+        require!(min_price > 0, ErrorCode::InvalidMinPrice);
+        ctx.accounts.quote_mint_entry.min_price = min_price;
+        Ok(())
+    }
+
+    /// Stop accepting a quote mint (owner only). Sales already priced in it
+    /// can no longer be bought from, but still settle and refund.
+    pub fn remove_quote_mint(_ctx: Context<RemoveQuoteMint>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Launch a token with standard (non-compressed) TokenSale PDA
+    ///
+    /// The mint may belong to SPL Token or Token-2022. Token-2022 mints with
+    /// extensions the sale cannot honour are rejected with
+    /// `UnsupportedMintExtension`.
+    ///
+    /// With `vesting`, purchases are held in the `vesting_vault` and released
+    /// to buyers by claim_vested. With `proceeds_vesting`, the creator share of
+    /// each purchase is escrowed and released by withdraw_proceeds.
     pub fn launch_token(
         ctx: Context<LaunchToken>,
         name: String,
@@ -327,585 +1141,2936 @@ pub mod gasless_launchpad {
         price_per_token: u64,
         limit_per_mint: u64,
         metadata_id: String,
+        params: SaleParams,
+    ) -> Result<()> {
+        let SaleParams {
+            start_ts,
+            end_ts,
+            merkle_root,
+            max_per_wallet,
+            price_slope,
+            auction,
+            soft_cap,
+            hard_cap_usdc,
+            quote_currency,
+            vesting,
+            proceeds_vesting,
+        } = params;
+        require!(
+            !name.is_empty() && name.len() <= 32,
+            ErrorCode::InvalidNameLength
+        );
+        require!(
+            !symbol.is_empty() && symbol.len() <= 10,
+            ErrorCode::InvalidSymbolLength
+        );
+        require!(metadata_id.len() <= 100, ErrorCode::MetadataIdTooLong);
+        require!(supply > 0, ErrorCode::InvalidSupply);
+
+        let decimals = ctx.accounts.token_mint.decimals;
+        let max_supply = 10u64
+            .pow(decimals as u32)
+            .checked_mul(1_000_000_000)
+            .ok_or(ErrorCode::MathOverflow)?;
+        require!(supply <= max_supply, ErrorCode::SupplyTooLarge);
+
+        let (quote_mint, min_price, quote_decimals) =
+            quote_terms(quote_currency, ctx.accounts.quote_mint_entry.as_deref())?;
+        if price_per_token == 0 {
+            require!(limit_per_mint > 0, ErrorCode::FreeMintRequiresLimit);
+        } else {
+            require!(price_per_token >= min_price, ErrorCode::PriceTooLow);
+            if limit_per_mint > 0 {
+                require!(limit_per_mint <= supply, ErrorCode::LimitExceedsSupply);
+            }
+        }
+        require!(
+            max_per_wallet == 0 || max_per_wallet >= limit_per_mint,
+            ErrorCode::InvalidWalletLimit
+        );
+        require!(
+            price_slope == 0 || price_per_token > 0,
+            ErrorCode::InvalidPriceSlope
+        );
+        validate_sale_window(start_ts, end_ts)?;
+        validate_auction(
+            auction,
+            price_per_token,
+            min_price,
+            price_slope,
+            start_ts,
+            end_ts,
+        )?;
+        require!(
+            soft_cap == 0
+                || (price_per_token > 0
+                    && end_ts.is_some()
+                    && price_slope == 0
+                    && !uniform_clearing(auction)),
+            ErrorCode::InvalidSoftCap
+        );
+        validate_hard_cap(hard_cap_usdc, price_per_token, soft_cap)?;
+        validate_quote_currency(quote_currency, price_slope, auction, soft_cap)?;
+        validate_vesting(vesting, soft_cap)?;
+        validate_proceeds_vesting(
+            proceeds_vesting,
+            quote_currency,
+            price_per_token,
+            price_slope,
+            auction,
+            soft_cap,
+        )?;
+        // close_sale is blocked once a curve sale has holders, so it needs an
+        // end time after which finalize_sale can close it
+        require!(
+            price_slope == 0 || end_ts.is_some(),
+            ErrorCode::CurveRequiresEndTime
+        );
+        require!(
+            vesting.is_none() || ctx.accounts.vesting_vault.is_some(),
+            ErrorCode::VestingVaultRequired
+        );
+        if quote_currency == QuoteCurrency::Token {
+            let sale_escrow = ctx
+                .accounts
+                .sale_escrow
+                .as_ref()
+                .ok_or(ErrorCode::SaleEscrowRequired)?;
+            require!(sale_escrow.mint == quote_mint, ErrorCode::InvalidMint);
+        }
+        validate_sale_mint(
+            &ctx.accounts.token_mint.to_account_info(),
+            &ctx.accounts.token_sale.key(),
+        )?;
+
+        let sale = &mut ctx.accounts.token_sale;
+        sale.creator = ctx.accounts.creator.key();
+        sale.token_mint = ctx.accounts.token_mint.key();
+        sale.price_per_token = price_per_token;
+        sale.supply_for_sale = supply;
+        sale.tokens_sold = 0;
+        sale.active = true;
+        sale.metadata_id = metadata_id.clone();
+        sale.limit_per_mint = limit_per_mint;
+        sale.decimals = decimals;
+        sale.bump = ctx.bumps.token_sale;
+        sale.start_ts = start_ts;
+        sale.end_ts = end_ts;
+        sale.merkle_root = merkle_root;
+        sale.max_per_wallet = max_per_wallet;
+        sale.price_slope = price_slope;
+        sale.usdc_reserve = 0;
+        sale.auction = auction;
+        sale.clearing_price = 0;
+        sale.proceeds_claimed = false;
+        sale.soft_cap = soft_cap;
+        sale.usdc_raised = 0;
+        sale.hard_cap_usdc = hard_cap_usdc;
+        sale.quote_currency = quote_currency;
+        sale.quote_mint = quote_mint;
+        sale.vesting = vesting;
+        sale.proceeds_vesting = proceeds_vesting;
+        sale.proceeds_escrow = 0;
+        sale.proceeds_withdrawn = 0;
+        sale.platform_fee_bps = ctx.accounts.app_state.platform_fee_bps;
+        sale.quote_decimals = quote_decimals;
+
+        let token_mint_key = ctx.accounts.token_mint.key();
+        let seeds = &[b"token_sale", token_mint_key.as_ref(), &[sale.bump]];
+        let signer = &[&seeds[..]];
+
+        token_interface::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token_interface::MintTo {
+                    mint: ctx.accounts.token_mint.to_account_info(),
+                    to: ctx.accounts.sale_token_account.to_account_info(),
+                    authority: ctx.accounts.token_sale.to_account_info(),
+                },
+                signer,
+            ),
+            supply,
+        )?;
+
+        emit!(TokenLaunched {
+            token_mint: ctx.accounts.token_mint.key(),
+            creator: ctx.accounts.creator.key(),
+            symbol,
+            name,
+            price: price_per_token,
+            supply,
+            limit_per_mint,
+            metadata_id,
+            start_ts,
+            end_ts,
+            merkle_root,
+            max_per_wallet,
+            price_slope,
+            auction,
+            soft_cap,
+            hard_cap_usdc,
+            quote_currency,
+            quote_mint,
+            quote_decimals,
+            vesting,
+            proceeds_vesting,
+        });
+
+        Ok(())
+    }
+
+    /// Launch a token with compressed TokenSale state using ZK compression
+    ///
+    /// This creates a compressed TokenSale account (rent-free) to track sale state
+    /// and mints `supply` compressed tokens to the sale_authority PDA in the same
+    /// transaction. The mint must:
+    /// 1. Have a token pool registered via createTokenPool() client-side
+    /// 2. Have the sale_authority PDA as its mint authority
+    ///
+    /// With `vesting`, purchases go to the vesting_authority PDA and are
+    /// released to buyers by claim_vested_compressed.
+    pub fn launch_token_compressed<'info>(
+        ctx: Context<'_, '_, '_, 'info, LaunchTokenCompressed<'info>>,
+        proof: ValidityProof,
+        address_tree_info: PackedAddressTreeInfo,
+        output_state_tree_index: u8,
+        name: String,
+        symbol: String,
+        supply: u64,
+        price_per_token: u64,
+        limit_per_mint: u64,
+        metadata_id: String,
+        params: SaleParams,
+    ) -> Result<()> {
+        let SaleParams {
+            start_ts,
+            end_ts,
+            merkle_root,
+            max_per_wallet,
+            price_slope,
+            auction,
+            soft_cap,
+            hard_cap_usdc,
+            quote_currency,
+            vesting,
+            proceeds_vesting,
+        } = params;
+        require!(
+            !name.is_empty() && name.len() <= 32,
+            ErrorCode::InvalidNameLength
+        );
+        require!(
+            !symbol.is_empty() && symbol.len() <= 10,
+            ErrorCode::InvalidSymbolLength
+        );
+        require!(metadata_id.len() <= 100, ErrorCode::MetadataIdTooLong);
+        require!(supply > 0, ErrorCode::InvalidSupply);
+
+        let decimals = ctx.accounts.token_mint.decimals;
+        let max_supply = 10u64
+            .pow(decimals as u32)
+            .checked_mul(1_000_000_000)
+            .ok_or(ErrorCode::MathOverflow)?;
+        require!(supply <= max_supply, ErrorCode::SupplyTooLarge);
+
+        let (quote_mint, min_price, quote_decimals) =
+            quote_terms(quote_currency, ctx.accounts.quote_mint_entry.as_deref())?;
+        if price_per_token == 0 {
+            require!(limit_per_mint > 0, ErrorCode::FreeMintRequiresLimit);
+        } else {
+            require!(price_per_token >= min_price, ErrorCode::PriceTooLow);
+            if limit_per_mint > 0 {
+                require!(limit_per_mint <= supply, ErrorCode::LimitExceedsSupply);
+            }
+        }
+        require!(
+            max_per_wallet == 0 || max_per_wallet >= limit_per_mint,
+            ErrorCode::InvalidWalletLimit
+        );
+        require!(
+            price_slope == 0 || price_per_token > 0,
+            ErrorCode::InvalidPriceSlope
+        );
+        validate_sale_window(start_ts, end_ts)?;
+        validate_auction(
+            auction,
+            price_per_token,
+            min_price,
+            price_slope,
+            start_ts,
+            end_ts,
+        )?;
+        // Compressed sales have no USDC reserve to hold payments for refunds
+        // or escrowed proceeds
+        require!(
+            !auction.is_some_and(|auction| auction.uniform_clearing),
+            ErrorCode::InvalidAuction
+        );
+        require!(soft_cap == 0, ErrorCode::InvalidSoftCap);
+        require!(
+            proceeds_vesting.is_none(),
+            ErrorCode::InvalidProceedsVesting
+        );
+        validate_hard_cap(hard_cap_usdc, price_per_token, 0)?;
+        validate_quote_currency(quote_currency, price_slope, auction, 0)?;
+        validate_vesting(vesting, 0)?;
+
+        // Setup Light CPI accounts
+        let light_cpi_accounts = CpiAccounts::new(
+            ctx.accounts.creator.as_ref(),
+            ctx.remaining_accounts,
+            crate::LIGHT_CPI_SIGNER,
+        );
+
+        // Sales live in a single address tree so each mint maps to exactly one sale address
+        let address_tree = address_tree_info
+            .get_tree_pubkey(&light_cpi_accounts)
+            .map_err(|_| ErrorCode::InvalidAddressTree)?;
+        require!(
+            address_tree == COMPRESSED_SALE_ADDRESS_TREE,
+            ErrorCode::InvalidAddressTree
+        );
+
+        // Derive compressed account address
+        let (address, address_seed) = derive_address(
+            &[
+                b"compressed_token_sale",
+                ctx.accounts.token_mint.key().as_ref(),
+            ],
+            &address_tree,
+            &crate::ID,
+        );
+
+        // Create compressed TokenSale account (rent-free via Light Protocol)
+        let mut compressed_sale = LightAccount::<CompressedTokenSale>::new_init(
+            &crate::ID,
+            Some(address),
+            output_state_tree_index,
+        );
+
+        compressed_sale.creator = ctx.accounts.creator.key();
+        compressed_sale.token_mint = ctx.accounts.token_mint.key();
+        compressed_sale.price_per_token = price_per_token;
+        compressed_sale.supply_for_sale = supply;
+        compressed_sale.tokens_sold = 0;
+        compressed_sale.active = true;
+        compressed_sale.limit_per_mint = limit_per_mint;
+        compressed_sale.decimals = decimals;
+        // Store the sale authority PDA that holds and signs for the compressed tokens
+        compressed_sale.sale_authority = ctx.accounts.sale_authority.key();
+        compressed_sale.sale_authority_bump = ctx.bumps.sale_authority;
+        compressed_sale.start_ts = start_ts;
+        compressed_sale.end_ts = end_ts;
+        compressed_sale.merkle_root = merkle_root;
+        compressed_sale.max_per_wallet = max_per_wallet;
+        compressed_sale.price_slope = price_slope;
+        compressed_sale.auction = auction;
+        compressed_sale.hard_cap_usdc = hard_cap_usdc;
+        compressed_sale.usdc_raised = 0;
+        compressed_sale.quote_currency = quote_currency;
+        compressed_sale.quote_mint = quote_mint;
+        compressed_sale.vesting = vesting;
+        compressed_sale.quote_decimals = quote_decimals;
+        compressed_sale.platform_fee_bps = ctx.accounts.app_state.platform_fee_bps;
+
+        // Invoke Light System Program to create compressed account
+        LightSystemProgramCpi::new_cpi(LIGHT_CPI_SIGNER, proof)
+            .with_light_account(compressed_sale)?
+            .with_new_addresses(&[address_tree_info.into_new_address_params_packed(address_seed)])
+            .invoke(light_cpi_accounts.clone())?;
+
+        // Mint the sale supply as compressed tokens held by the sale_authority PDA
+        let token_mint_key = ctx.accounts.token_mint.key();
+        let sale_authority_seeds = &[
+            b"sale_authority".as_ref(),
+            token_mint_key.as_ref(),
+            &[ctx.bumps.sale_authority],
+        ];
+        mint_compressed_tokens(
+            &light_cpi_accounts,
+            &ctx.accounts.compressed_token_program,
+            &ctx.accounts.compressed_token_cpi_authority,
+            &ctx.accounts.sale_authority,
+            &[&sale_authority_seeds[..]],
+            &ctx.accounts.token_mint.to_account_info(),
+            &ctx.accounts.token_pool,
+            &ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.sale_authority.key(),
+            supply,
+            output_state_tree_index,
+        )?;
+
+        emit!(TokenLaunchedCompressed {
+            token_mint: ctx.accounts.token_mint.key(),
+            creator: ctx.accounts.creator.key(),
+            compressed_address: address,
+            sale_authority: ctx.accounts.sale_authority.key(),
+            symbol,
+            name,
+            price: price_per_token,
+            supply,
+            limit_per_mint,
+            metadata_id,
+            start_ts,
+            end_ts,
+            merkle_root,
+            max_per_wallet,
+            price_slope,
+            auction,
+            hard_cap_usdc,
+            quote_currency,
+            quote_mint,
+            quote_decimals,
+            vesting,
+        });
+
+        Ok(())
+    }
+
+    /// Buy tokens from a standard TokenSale
+    ///
+    /// `usdc_amount` is the most the buyer will spend. Only the cost of the
+    /// tokens it buys is charged, rounded up to a USDC base unit, and
+    /// `TokenBought` reports that amount; `min_tokens_out` bounds slippage.
+    /// A buy that would pass `hard_cap_usdc` is filled up to the cap, and the
+    /// sale closes once the cap is reached.
+    ///
+    /// Amounts are in the sale's quote currency. SOL sales pay the creator and
+    /// fee recipient wallets in lamports and take no USDC accounts; USDC sales
+    /// take no wallets.
+    ///
+    /// For Token-2022 mints with a transfer fee the buyer still receives the
+    /// full token amount; the fee is taken from the unsold supply. Vesting
+    /// sales deliver the full amount to the vesting vault instead, and the fee
+    /// on each claim_vested release is borne by the buyer.
+    ///
+    /// Sales with `max_per_wallet` or an allowlist track each buyer's lifetime
+    /// purchases in a compressed BuyerRecord. `buyer_record` and `proof` are then
+    /// required, and the Light system accounts follow in remaining accounts.
+    ///
+    /// Vesting sales also track buyers, and deliver to the `vesting_vault`
+    /// rather than `buyer_token_account`.
+    ///
+    /// An optional `referrer` earns `referral_fee_bps` of the platform fee,
+    /// and its compressed ReferrerRecord, passed as `referrer_record` under the
+    /// same `proof`, accumulates the purchases it referred.
+    pub fn buy_tokens<'info>(
+        ctx: Context<'_, '_, '_, 'info, BuyTokens<'info>>,
+        usdc_amount: u64,
+        min_tokens_out: u64,
+        allowlist: Option<AllowlistProof>,
+        proof: ValidityProof,
+        buyer_record: Option<BuyerRecordInput>,
+        referrer_record: Option<ReferrerRecordInput>,
+    ) -> Result<()> {
+        process_buy(
+            ctx,
+            BuyOrder::Spend {
+                usdc_amount,
+                min_tokens_out,
+            },
+            allowlist,
+            proof,
+            buyer_record,
+            referrer_record,
+        )
+    }
+
+    /// Buy exactly `token_amount` tokens from a standard TokenSale, paying at
+    /// most `max_usdc`. Buys that would pass `hard_cap_usdc` are rejected
+    /// rather than partially filled. Otherwise behaves as `buy_tokens`.
+    pub fn buy_exact_tokens<'info>(
+        ctx: Context<'_, '_, '_, 'info, BuyTokens<'info>>,
+        token_amount: u64,
+        max_usdc: u64,
+        allowlist: Option<AllowlistProof>,
+        proof: ValidityProof,
+        buyer_record: Option<BuyerRecordInput>,
+        referrer_record: Option<ReferrerRecordInput>,
+    ) -> Result<()> {
+        process_buy(
+            ctx,
+            BuyOrder::Exact {
+                token_amount,
+                max_usdc,
+            },
+            allowlist,
+            proof,
+            buyer_record,
+            referrer_record,
+        )
+    }
+
+    /// Buy tokens from a compressed TokenSale
+    ///
+    /// This instruction handles:
+    /// 1. Updates compressed sale state (tokens_sold, active status)
+    /// 2. USDC payment from buyer to creator (with platform fee)
+    /// 3. Transfers tokens_to_send compressed tokens from sale_authority to the buyer
+    ///
+    /// `current_sale` is caller-supplied. It must sit at the address derived for
+    /// `token_mint`, and no USDC or tokens move until the Light System Program has
+    /// verified it against `proof`.
+    ///
+    /// `sale_token_accounts` are the compressed token accounts held by sale_authority
+    /// that fund the transfer, proven by `token_proof`. Any change is returned to
+    /// sale_authority in the `token_output_tree_index` tree.
+    ///
+    /// `usdc_amount` and `min_tokens_out` behave as in `buy_tokens`, including
+    /// payment in the sale's quote currency.
+    ///
+    /// For sales that cap wallet purchases, `proof` also covers the buyer's
+    /// record (or the non-existence of its address on the first purchase),
+    /// and likewise the referrer's record when a `referrer` is passed.
+    pub fn buy_tokens_compressed<'info>(
+        ctx: Context<'_, '_, '_, 'info, BuyTokensCompressed<'info>>,
+        proof: ValidityProof,
+        current_sale: CompressedTokenSale,
+        account_meta: CompressedAccountMeta,
+        usdc_amount: u64,
+        min_tokens_out: u64,
+        allowlist: Option<AllowlistProof>,
+        buyer_record: Option<BuyerRecordInput>,
+        referrer_record: Option<ReferrerRecordInput>,
+        token_proof: ValidityProof,
+        sale_token_accounts: Vec<TokenAccountMeta>,
+        token_output_tree_index: u8,
+    ) -> Result<()> {
+        process_buy_compressed(
+            ctx,
+            proof,
+            current_sale,
+            account_meta,
+            BuyOrder::Spend {
+                usdc_amount,
+                min_tokens_out,
+            },
+            allowlist,
+            buyer_record,
+            referrer_record,
+            token_proof,
+            sale_token_accounts,
+            token_output_tree_index,
+        )
+    }
+
+    /// Buy exactly `token_amount` compressed tokens, paying at most `max_usdc`.
+    /// Inputs are verified as in buy_tokens_compressed.
+    pub fn buy_exact_tokens_compressed<'info>(
+        ctx: Context<'_, '_, '_, 'info, BuyTokensCompressed<'info>>,
+        proof: ValidityProof,
+        current_sale: CompressedTokenSale,
+        account_meta: CompressedAccountMeta,
+        token_amount: u64,
+        max_usdc: u64,
+        allowlist: Option<AllowlistProof>,
+        buyer_record: Option<BuyerRecordInput>,
+        referrer_record: Option<ReferrerRecordInput>,
+        token_proof: ValidityProof,
+        sale_token_accounts: Vec<TokenAccountMeta>,
+        token_output_tree_index: u8,
+    ) -> Result<()> {
+        process_buy_compressed(
+            ctx,
+            proof,
+            current_sale,
+            account_meta,
+            BuyOrder::Exact {
+                token_amount,
+                max_usdc,
+            },
+            allowlist,
+            buyer_record,
+            referrer_record,
+            token_proof,
+            sale_token_accounts,
+            token_output_tree_index,
+        )
+    }
+
+    pub fn close_sale(ctx: Context<CloseSale>) -> Result<()> {
+        let sale = &mut ctx.accounts.token_sale;
+        require!(sale.active, ErrorCode::AlreadyClosed);
+        // Closing would end sell-backs and release the reserve backing them
+        require!(
+            sale.price_slope == 0 || sale.tokens_sold == 0,
+            ErrorCode::CurveHasHolders
+        );
+        sale.active = false;
+
+        let remaining = ctx.accounts.sale_token_account.amount;
+
+        if remaining > 0 {
+            let token_mint_key = ctx.accounts.token_mint.key();
+            let seeds = &[b"token_sale", token_mint_key.as_ref(), &[sale.bump]];
+            let signer = &[&seeds[..]];
+
+            token_interface::transfer_checked(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    TransferChecked {
+                        from: ctx.accounts.sale_token_account.to_account_info(),
+                        mint: ctx.accounts.token_mint.to_account_info(),
+                        to: ctx.accounts.creator_token_account.to_account_info(),
+                        authority: ctx.accounts.token_sale.to_account_info(),
+                    },
+                    signer,
+                ),
+                remaining,
+                ctx.accounts.token_mint.decimals,
+            )?;
+        }
+
+        emit!(SaleClosed {
+            token_mint: ctx.accounts.token_mint.key(),
+            remaining_tokens_returned: remaining,
+        });
+
+        Ok(())
+    }
+
+    /// Close a sale whose end_ts has passed. Anyone can call this; unsold
+    /// tokens are returned to the creator. A sale that met its soft cap pays
+    /// out its escrow; one that missed it opens claim_refund to its buyers.
+    pub fn finalize_sale(ctx: Context<FinalizeSale>) -> Result<()> {
+        let sale = &mut ctx.accounts.token_sale;
+        require!(sale.active, ErrorCode::AlreadyClosed);
+        require_sale_ended(sale.end_ts)?;
+        sale.active = false;
+
+        let remaining = ctx.accounts.sale_token_account.amount;
+
+        if remaining > 0 {
+            let token_mint_key = ctx.accounts.token_mint.key();
+            let seeds = &[b"token_sale", token_mint_key.as_ref(), &[sale.bump]];
+            let signer = &[&seeds[..]];
+
+            token_interface::transfer_checked(
+                CpiContext::new_with_signer(
+                    ctx.accounts.sale_token_program.to_account_info(),
+                    TransferChecked {
+                        from: ctx.accounts.sale_token_account.to_account_info(),
+                        mint: ctx.accounts.token_mint.to_account_info(),
+                        to: ctx.accounts.creator_token_account.to_account_info(),
+                        authority: ctx.accounts.token_sale.to_account_info(),
+                    },
+                    signer,
+                ),
+                remaining,
+                ctx.accounts.token_mint.decimals,
+            )?;
+        }
+
+        emit!(SaleClosed {
+            token_mint: ctx.accounts.token_mint.key(),
+            remaining_tokens_returned: remaining,
+        });
+
+        let sale = &mut ctx.accounts.token_sale;
+        if sale.soft_cap > 0 && sale.usdc_raised >= sale.soft_cap {
+            let (Some(sale_escrow), Some(fee_recipient_usdc_account), Some(creator_usdc_account)) = (
+                ctx.accounts.sale_escrow.as_ref(),
+                ctx.accounts.fee_recipient_usdc_account.as_ref(),
+                ctx.accounts.creator_usdc_account.as_ref(),
+            ) else {
+                return err!(ErrorCode::PaymentAccountsRequired);
+            };
+            let (fee, amount) = claim_proceeds(sale)?;
+            pay_out(
+                &ctx.accounts.token_program,
+                sale_escrow,
+                &ctx.accounts.program_authority,
+                ctx.bumps.program_authority,
+                &[
+                    (fee_recipient_usdc_account, fee),
+                    (creator_usdc_account, amount),
+                ],
+            )?;
+
+            emit!(ReserveClaimed {
+                token_mint: ctx.accounts.token_sale.token_mint,
+                creator: ctx.accounts.token_sale.creator,
+                amount,
+                fee,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Sell tokens back down a bonding curve before the sale closes.
+    ///
+    /// The seller receives the area under the curve for `token_amount`, rounded
+    /// down and less the platform fee already taken on purchase, from the sale's
+    /// USDC reserve. `min_usdc_out` bounds slippage.
+    pub fn sell_tokens(
+        ctx: Context<SellTokens>,
+        token_amount: u64,
+        min_usdc_out: u64,
+    ) -> Result<()> {
+        let sale = &mut ctx.accounts.token_sale;
+        require!(sale.active, ErrorCode::SaleNotActive);
+        require!(sale.price_slope > 0, ErrorCode::SellNotSupported);
+        require_sale_open(sale.start_ts, sale.end_ts)?;
+        require!(token_amount > 0, ErrorCode::AmountMustBePositive);
+
+        // The sale buys back only what reaches it after any Token-2022 transfer fee
+        let tokens_returned = token_amount
+            .checked_sub(transfer_fee(
+                &ctx.accounts.token_mint.to_account_info(),
+                token_amount,
+            )?)
+            .ok_or(ErrorCode::MathOverflow)?;
+        let remaining_sold = sale
+            .tokens_sold
+            .checked_sub(tokens_returned)
+            .ok_or(ErrorCode::ExceedsTokensSold)?;
+        let usdc_out = curve_proceeds(
+            sale.price_per_token,
+            sale.price_slope,
+            sale.decimals,
+            remaining_sold,
+            tokens_returned,
+            sale.platform_fee_bps,
+        )?;
+        require!(usdc_out >= min_usdc_out, ErrorCode::SlippageExceeded);
+        require!(
+            usdc_out <= sale.usdc_reserve,
+            ErrorCode::InsufficientReserve
+        );
+
+        sale.tokens_sold = remaining_sold;
+        sale.usdc_reserve -= usdc_out;
+
+        token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.sale_token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.seller_token_account.to_account_info(),
+                    mint: ctx.accounts.token_mint.to_account_info(),
+                    to: ctx.accounts.sale_token_account.to_account_info(),
+                    authority: ctx.accounts.seller.to_account_info(),
+                },
+            ),
+            token_amount,
+            ctx.accounts.token_mint.decimals,
+        )?;
+
+        if usdc_out > 0 {
+            let auth_seeds = &[b"authority".as_ref(), &[ctx.bumps.program_authority]];
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.sale_escrow.to_account_info(),
+                        to: ctx.accounts.seller_usdc_account.to_account_info(),
+                        authority: ctx.accounts.program_authority.to_account_info(),
+                    },
+                    &[&auth_seeds[..]],
+                ),
+                usdc_out,
+            )?;
+        }
+
+        emit!(TokenSold {
+            token_mint: ctx.accounts.token_mint.key(),
+            seller: ctx.accounts.seller.key(),
+            tokens_returned,
+            usdc_received: usdc_out,
+        });
+
+        Ok(())
+    }
+
+    /// Pay a closed sale's USDC reserve to its creator, once.
+    ///
+    /// Curve sales release the whole reserve. Uniform-clearing auctions release
+    /// `tokens_sold` at the clearing price, less the platform fee; the rest stays
+    /// behind for buyers' claim_auction_refund. Soft-cap sales release their
+    /// escrow, less the platform fee, only if the cap was met.
+    pub fn claim_reserve(ctx: Context<ClaimReserve>) -> Result<()> {
+        let sale = &mut ctx.accounts.token_sale;
+        require!(!sale.active, ErrorCode::SaleStillActive);
+        let (fee, amount) = claim_proceeds(sale)?;
+
+        pay_out(
+            &ctx.accounts.token_program,
+            &ctx.accounts.sale_escrow,
+            &ctx.accounts.program_authority,
+            ctx.bumps.program_authority,
+            &[
+                (&ctx.accounts.fee_recipient_usdc_account, fee),
+                (&ctx.accounts.creator_usdc_account, amount),
+            ],
+        )?;
+
+        emit!(ReserveClaimed {
+            token_mint: ctx.accounts.token_sale.token_mint,
+            creator: ctx.accounts.creator.key(),
+            amount,
+            fee,
+        });
+
+        Ok(())
+    }
+
+    /// Pay the creator the part of their escrowed proceeds that has vested and
+    /// not been withdrawn (creator only)
+    pub fn withdraw_proceeds(ctx: Context<WithdrawProceeds>) -> Result<()> {
+        let sale = &mut ctx.accounts.token_sale;
+        let schedule = sale.proceeds_vesting.ok_or(ErrorCode::NoVesting)?;
+        let now = Clock::get()?.unix_timestamp;
+        let amount = schedule
+            .unlocked(sale.proceeds_escrow, now)
+            .checked_sub(sale.proceeds_withdrawn)
+            .ok_or(ErrorCode::MathOverflow)?;
+        require!(amount > 0, ErrorCode::NothingVested);
+        sale.proceeds_withdrawn += amount;
+
+        pay_out(
+            &ctx.accounts.token_program,
+            &ctx.accounts.sale_escrow,
+            &ctx.accounts.program_authority,
+            ctx.bumps.program_authority,
+            &[(&ctx.accounts.creator_usdc_account, amount)],
+        )?;
+
+        emit!(ProceedsWithdrawn {
+            token_mint: ctx.accounts.token_sale.token_mint,
+            creator: ctx.accounts.creator.key(),
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Refund a uniform-clearing auction buyer down to the clearing price once
+    /// the sale has closed. `current` is the buyer's record, proven by `proof`,
+    /// with the Light system accounts in remaining accounts.
+    pub fn claim_auction_refund<'info>(
+        ctx: Context<'_, '_, '_, 'info, ClaimAuctionRefund<'info>>,
+        proof: ValidityProof,
+        current: BuyerRecord,
+        account_meta: CompressedAccountMeta,
     ) -> Result<()> {
+        let buyer_key = ctx.accounts.buyer.key();
+        let sale = &mut ctx.accounts.token_sale;
+        require!(uniform_clearing(sale.auction), ErrorCode::NoAuctionRefund);
+        require!(!sale.active, ErrorCode::SaleStillActive);
         require!(
-            name.len() > 0 && name.len() <= 32,
-            ErrorCode::InvalidNameLength
-        );
-        require!(
-            symbol.len() > 0 && symbol.len() <= 10,
-            ErrorCode::InvalidSymbolLength
+            account_meta.address == BuyerRecord::derive_address(&sale.token_mint, &buyer_key),
+            ErrorCode::InvalidBuyerRecord
         );
-        require!(metadata_id.len() <= 100, ErrorCode::MetadataIdTooLong);
-        require!(supply > 0, ErrorCode::InvalidSupply);
 
-        let decimals = ctx.accounts.token_mint.decimals;
-        let max_supply = 10u64
-            .pow(decimals as u32)
-            .checked_mul(1_000_000_000)
+        let mut record = LightAccount::<BuyerRecord>::new_mut(&crate::ID, &account_meta, current)?;
+        let final_cost = curve_cost(
+            sale.clearing_price,
+            0,
+            sale.decimals,
+            0,
+            record.tokens_bought,
+        )?;
+        let refund = record
+            .usdc_paid
+            .checked_sub(final_cost)
             .ok_or(ErrorCode::MathOverflow)?;
-        require!(supply <= max_supply, ErrorCode::SupplyTooLarge);
+        require!(refund > 0, ErrorCode::NoRefundDue);
+        record.usdc_paid = final_cost;
+        sale.usdc_reserve = sale
+            .usdc_reserve
+            .checked_sub(refund)
+            .ok_or(ErrorCode::InsufficientReserve)?;
 
-        if price_per_token == 0 {
-            require!(limit_per_mint > 0, ErrorCode::FreeMintRequiresLimit);
-        } else {
-            require!(price_per_token >= 1_000, ErrorCode::PriceTooLow);
-            if limit_per_mint > 0 {
-                require!(limit_per_mint <= supply, ErrorCode::LimitExceedsSupply);
-            }
-        }
+        let light_cpi_accounts = CpiAccounts::new(
+            ctx.accounts.buyer.as_ref(),
+            ctx.remaining_accounts,
+            crate::LIGHT_CPI_SIGNER,
+        );
+        LightSystemProgramCpi::new_cpi(LIGHT_CPI_SIGNER, proof)
+            .with_light_account(record)?
+            .invoke(light_cpi_accounts)?;
+
+        let auth_seeds = &[b"authority".as_ref(), &[ctx.bumps.program_authority]];
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.sale_escrow.to_account_info(),
+                    to: ctx.accounts.buyer_usdc_account.to_account_info(),
+                    authority: ctx.accounts.program_authority.to_account_info(),
+                },
+                &[&auth_seeds[..]],
+            ),
+            refund,
+        )?;
+
+        emit!(AuctionRefunded {
+            token_mint: ctx.accounts.token_sale.token_mint,
+            buyer: buyer_key,
+            clearing_price: ctx.accounts.token_sale.clearing_price,
+            refund,
+        });
+
+        Ok(())
+    }
 
+    /// Refund a buyer of a sale that closed below its soft cap. The buyer's
+    /// tokens are burned and everything they paid is returned. `current` is
+    /// the buyer's record, proven by `proof`, with the Light system accounts in
+    /// remaining accounts.
+    pub fn claim_refund<'info>(
+        ctx: Context<'_, '_, '_, 'info, ClaimRefund<'info>>,
+        proof: ValidityProof,
+        current: BuyerRecord,
+        account_meta: CompressedAccountMeta,
+    ) -> Result<()> {
+        let buyer_key = ctx.accounts.buyer.key();
         let sale = &mut ctx.accounts.token_sale;
-        sale.creator = ctx.accounts.creator.key();
-        sale.token_mint = ctx.accounts.token_mint.key();
-        sale.price_per_token = price_per_token;
-        sale.supply_for_sale = supply;
-        sale.tokens_sold = 0;
-        sale.active = true;
-        sale.metadata_id = metadata_id.clone();
-        sale.limit_per_mint = limit_per_mint;
-        sale.decimals = decimals;
-        sale.bump = ctx.bumps.token_sale;
+        require!(sale.soft_cap > 0, ErrorCode::NoSoftCap);
+        require!(!sale.active, ErrorCode::SaleStillActive);
+        require!(sale.usdc_raised < sale.soft_cap, ErrorCode::SoftCapMet);
+        require!(
+            account_meta.address == BuyerRecord::derive_address(&sale.token_mint, &buyer_key),
+            ErrorCode::InvalidBuyerRecord
+        );
 
-        let token_mint_key = ctx.accounts.token_mint.key();
-        let seeds = &[b"token_sale", token_mint_key.as_ref(), &[sale.bump]];
-        let signer = &[&seeds[..]];
+        let mut record = LightAccount::<BuyerRecord>::new_mut(&crate::ID, &account_meta, current)?;
+        let (tokens, refund) = (record.tokens_bought, record.usdc_paid);
+        require!(refund > 0, ErrorCode::NoRefundDue);
+        record.tokens_bought = 0;
+        record.usdc_paid = 0;
+        sale.usdc_reserve = sale
+            .usdc_reserve
+            .checked_sub(refund)
+            .ok_or(ErrorCode::InsufficientReserve)?;
+
+        let light_cpi_accounts = CpiAccounts::new(
+            ctx.accounts.buyer.as_ref(),
+            ctx.remaining_accounts,
+            crate::LIGHT_CPI_SIGNER,
+        );
+        LightSystemProgramCpi::new_cpi(LIGHT_CPI_SIGNER, proof)
+            .with_light_account(record)?
+            .invoke(light_cpi_accounts)?;
+
+        token_interface::burn(
+            CpiContext::new(
+                ctx.accounts.sale_token_program.to_account_info(),
+                token_interface::Burn {
+                    mint: ctx.accounts.token_mint.to_account_info(),
+                    from: ctx.accounts.buyer_token_account.to_account_info(),
+                    authority: ctx.accounts.buyer.to_account_info(),
+                },
+            ),
+            tokens,
+        )?;
 
-        token::mint_to(
+        let auth_seeds = &[b"authority".as_ref(), &[ctx.bumps.program_authority]];
+        token::transfer(
             CpiContext::new_with_signer(
                 ctx.accounts.token_program.to_account_info(),
-                MintTo {
+                Transfer {
+                    from: ctx.accounts.sale_escrow.to_account_info(),
+                    to: ctx.accounts.buyer_usdc_account.to_account_info(),
+                    authority: ctx.accounts.program_authority.to_account_info(),
+                },
+                &[&auth_seeds[..]],
+            ),
+            refund,
+        )?;
+
+        emit!(SaleRefunded {
+            token_mint: ctx.accounts.token_mint.key(),
+            buyer: buyer_key,
+            tokens_burned: tokens,
+            refund,
+        });
+
+        Ok(())
+    }
+
+    /// Release the buyer's unlocked purchases from the vesting vault.
+    /// `current` is the buyer's record, proven by `proof`, with the Light
+    /// system accounts in remaining accounts. The vault pays out the unlocked
+    /// amount as is, so a Token-2022 transfer fee is withheld from it.
+    pub fn claim_vested<'info>(
+        ctx: Context<'_, '_, '_, 'info, ClaimVested<'info>>,
+        proof: ValidityProof,
+        current: BuyerRecord,
+        account_meta: CompressedAccountMeta,
+    ) -> Result<()> {
+        let buyer_key = ctx.accounts.buyer.key();
+        let sale = &ctx.accounts.token_sale;
+        let vesting = sale.vesting.ok_or(ErrorCode::NoVesting)?;
+        require!(
+            account_meta.address == BuyerRecord::derive_address(&sale.token_mint, &buyer_key),
+            ErrorCode::InvalidBuyerRecord
+        );
+
+        let mut record = LightAccount::<BuyerRecord>::new_mut(&crate::ID, &account_meta, current)?;
+        let amount = release_vested(&vesting, &mut record)?;
+
+        let light_cpi_accounts = CpiAccounts::new(
+            ctx.accounts.buyer.as_ref(),
+            ctx.remaining_accounts,
+            crate::LIGHT_CPI_SIGNER,
+        );
+        LightSystemProgramCpi::new_cpi(LIGHT_CPI_SIGNER, proof)
+            .with_light_account(record)?
+            .invoke(light_cpi_accounts)?;
+
+        let token_mint_key = ctx.accounts.token_mint.key();
+        let seeds = &[b"token_sale", token_mint_key.as_ref(), &[sale.bump]];
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.sale_token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.vesting_vault.to_account_info(),
                     mint: ctx.accounts.token_mint.to_account_info(),
-                    to: ctx.accounts.sale_token_account.to_account_info(),
+                    to: ctx.accounts.buyer_token_account.to_account_info(),
                     authority: ctx.accounts.token_sale.to_account_info(),
                 },
-                signer,
+                &[&seeds[..]],
             ),
-            supply,
+            amount,
+            ctx.accounts.token_mint.decimals,
         )?;
 
-        emit!(TokenLaunched {
-            token_mint: ctx.accounts.token_mint.key(),
-            creator: ctx.accounts.creator.key(),
-            symbol,
-            name,
-            price: price_per_token,
-            supply,
-            limit_per_mint,
-            metadata_id,
+        emit!(VestedClaimed {
+            token_mint: token_mint_key,
+            buyer: buyer_key,
+            amount,
         });
 
         Ok(())
     }
 
-    /// Launch a token with compressed TokenSale state using ZK compression
-    ///
-    /// This creates a compressed TokenSale account (rent-free) to track sale state.
-    /// The actual compressed tokens should be:
-    /// 1. Created with a token pool via createTokenPool() client-side
-    /// 2. Minted as compressed tokens via mintTo() from @lightprotocol/compressed-token
-    /// 3. Minted to the sale_authority PDA which will hold the supply for sale
-    ///
-    /// This instruction ONLY creates the sale state - token minting is done client-side
-    /// to properly use compressed tokens (not standard SPL tokens).
-    pub fn launch_token_compressed<'info>(
-        ctx: Context<'_, '_, '_, 'info, LaunchTokenCompressed<'info>>,
+    /// Release the buyer's unlocked compressed purchases from the
+    /// vesting_authority PDA. `proof` covers `current_sale`, which supplies the
+    /// schedule and is written back unchanged, and the buyer's record
+    /// `current`. `vesting_token_accounts` are the vesting_authority's
+    /// compressed token accounts funding the transfer, proven by `token_proof`.
+    pub fn claim_vested_compressed<'info>(
+        ctx: Context<'_, '_, '_, 'info, ClaimVestedCompressed<'info>>,
         proof: ValidityProof,
-        address_tree_info: PackedAddressTreeInfo,
-        output_state_tree_index: u8,
-        name: String,
-        symbol: String,
-        supply: u64,
-        price_per_token: u64,
-        limit_per_mint: u64,
-        metadata_id: String,
+        current_sale: CompressedTokenSale,
+        sale_account_meta: CompressedAccountMeta,
+        current: BuyerRecord,
+        account_meta: CompressedAccountMeta,
+        token_proof: ValidityProof,
+        vesting_token_accounts: Vec<TokenAccountMeta>,
+        token_output_tree_index: u8,
     ) -> Result<()> {
+        let buyer_key = ctx.accounts.buyer.key();
+        let token_mint_key = ctx.accounts.token_mint.key();
         require!(
-            name.len() > 0 && name.len() <= 32,
-            ErrorCode::InvalidNameLength
+            sale_account_meta.address == CompressedTokenSale::derive_address(&token_mint_key),
+            ErrorCode::InvalidSaleAddress
         );
         require!(
-            symbol.len() > 0 && symbol.len() <= 10,
-            ErrorCode::InvalidSymbolLength
+            account_meta.address == BuyerRecord::derive_address(&token_mint_key, &buyer_key),
+            ErrorCode::InvalidBuyerRecord
         );
-        require!(metadata_id.len() <= 100, ErrorCode::MetadataIdTooLong);
-        require!(supply > 0, ErrorCode::InvalidSupply);
-
-        let decimals = ctx.accounts.token_mint.decimals;
-        let max_supply = 10u64
-            .pow(decimals as u32)
-            .checked_mul(1_000_000_000)
-            .ok_or(ErrorCode::MathOverflow)?;
-        require!(supply <= max_supply, ErrorCode::SupplyTooLarge);
+        let vesting = current_sale.vesting.ok_or(ErrorCode::NoVesting)?;
 
-        if price_per_token == 0 {
-            require!(limit_per_mint > 0, ErrorCode::FreeMintRequiresLimit);
-        } else {
-            require!(price_per_token >= 1_000, ErrorCode::PriceTooLow);
-            if limit_per_mint > 0 {
-                require!(limit_per_mint <= supply, ErrorCode::LimitExceedsSupply);
-            }
-        }
+        let sale = LightAccount::<CompressedTokenSale>::new_mut(
+            &crate::ID,
+            &sale_account_meta,
+            current_sale,
+        )?;
+        let mut record = LightAccount::<BuyerRecord>::new_mut(&crate::ID, &account_meta, current)?;
+        let amount = release_vested(&vesting, &mut record)?;
 
-        // Setup Light CPI accounts
         let light_cpi_accounts = CpiAccounts::new(
-            ctx.accounts.creator.as_ref(),
+            ctx.accounts.buyer.as_ref(),
             ctx.remaining_accounts,
             crate::LIGHT_CPI_SIGNER,
         );
+        LightSystemProgramCpi::new_cpi(LIGHT_CPI_SIGNER, proof)
+            .with_light_account(sale)?
+            .with_light_account(record)?
+            .invoke(light_cpi_accounts.clone())?;
+
+        let vesting_authority_seeds = &[
+            b"vesting_authority".as_ref(),
+            token_mint_key.as_ref(),
+            &[ctx.bumps.vesting_authority],
+        ];
+        transfer_compressed_tokens(
+            &light_cpi_accounts,
+            &ctx.accounts.compressed_token_program,
+            &ctx.accounts.compressed_token_cpi_authority,
+            &ctx.accounts.vesting_authority,
+            &[&vesting_authority_seeds[..]],
+            token_mint_key,
+            token_proof,
+            vesting_token_accounts,
+            buyer_key,
+            amount,
+            token_output_tree_index,
+        )?;
 
-        // Derive compressed account address
-        let (address, address_seed) = derive_address(
-            &[
-                b"compressed_token_sale",
-                ctx.accounts.token_mint.key().as_ref(),
-            ],
-            &address_tree_info
-                .get_tree_pubkey(&light_cpi_accounts)
-                .map_err(|_| ErrorCode::InvalidAddressTree)?,
-            &crate::ID,
+        emit!(VestedClaimed {
+            token_mint: token_mint_key,
+            buyer: buyer_key,
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Close a compressed TokenSale and return remaining tokens
+    ///
+    /// This instruction:
+    /// 1. Verifies creator authorization
+    /// 2. Updates compressed sale state to inactive
+    /// 3. Transfers the balance of `sale_token_accounts` from sale_authority to the creator
+    ///
+    /// As with buy_tokens_compressed, `current_sale` must sit at the address derived
+    /// for `token_mint` and is verified against `proof` before any tokens move.
+    ///
+    /// `sale_token_accounts` are the compressed token accounts held by sale_authority,
+    /// proven by `token_proof`. The remaining balance may be spread across several of them,
+    /// and together they must hold at least the unsold `supply_for_sale - tokens_sold`.
+    pub fn close_sale_compressed<'info>(
+        ctx: Context<'_, '_, '_, 'info, CloseSaleCompressed<'info>>,
+        proof: ValidityProof,
+        current_sale: CompressedTokenSale,
+        account_meta: CompressedAccountMeta,
+        token_proof: ValidityProof,
+        sale_token_accounts: Vec<TokenAccountMeta>,
+        token_output_tree_index: u8,
+    ) -> Result<()> {
+        let token_mint_key = ctx.accounts.token_mint.key();
+
+        require!(
+            account_meta.address == CompressedTokenSale::derive_address(&token_mint_key),
+            ErrorCode::InvalidSaleAddress
         );
 
-        // Create compressed TokenSale account (rent-free via Light Protocol)
-        let mut compressed_sale = LightAccount::<CompressedTokenSale>::new_init(
-            &crate::ID,
-            Some(address),
-            output_state_tree_index,
+        let light_cpi_accounts = CpiAccounts::new(
+            ctx.accounts.creator.as_ref(),
+            ctx.remaining_accounts,
+            crate::LIGHT_CPI_SIGNER,
         );
 
-        compressed_sale.creator = ctx.accounts.creator.key();
-        compressed_sale.token_mint = ctx.accounts.token_mint.key();
-        compressed_sale.price_per_token = price_per_token;
-        compressed_sale.supply_for_sale = supply;
-        compressed_sale.tokens_sold = 0;
-        compressed_sale.active = true;
-        compressed_sale.limit_per_mint = limit_per_mint;
-        compressed_sale.decimals = decimals;
-        // Store the sale authority PDA for client-side token operations
-        compressed_sale.sale_authority = ctx.accounts.sale_authority.key();
-        compressed_sale.sale_authority_bump = ctx.bumps.sale_authority;
+        let sale =
+            LightAccount::<CompressedTokenSale>::new_mut(&crate::ID, &account_meta, current_sale)?;
+        require!(sale.active, ErrorCode::AlreadyClosed);
 
-        // Invoke Light System Program to create compressed account
-        LightSystemProgramCpi::new_cpi(LIGHT_CPI_SIGNER, proof)
-            .with_light_account(compressed_sale)?
-            .with_new_addresses(&[address_tree_info.into_new_address_params_packed(address_seed)])
-            .invoke(light_cpi_accounts)?;
+        close_compressed_sale(
+            &light_cpi_accounts,
+            &ctx.accounts.compressed_token_program,
+            &ctx.accounts.compressed_token_cpi_authority,
+            &ctx.accounts.sale_authority,
+            ctx.bumps.sale_authority,
+            sale,
+            proof,
+            token_proof,
+            sale_token_accounts,
+            token_output_tree_index,
+        )
+    }
+
+    /// Close a compressed sale whose end_ts has passed. Anyone can call this;
+    /// the unsold supply is returned to the creator recorded in the sale. Inputs
+    /// are verified as in close_sale_compressed.
+    pub fn finalize_sale_compressed<'info>(
+        ctx: Context<'_, '_, '_, 'info, FinalizeSaleCompressed<'info>>,
+        proof: ValidityProof,
+        current_sale: CompressedTokenSale,
+        account_meta: CompressedAccountMeta,
+        token_proof: ValidityProof,
+        sale_token_accounts: Vec<TokenAccountMeta>,
+        token_output_tree_index: u8,
+    ) -> Result<()> {
+        let token_mint_key = ctx.accounts.token_mint.key();
+
+        require!(
+            account_meta.address == CompressedTokenSale::derive_address(&token_mint_key),
+            ErrorCode::InvalidSaleAddress
+        );
+
+        let light_cpi_accounts = CpiAccounts::new(
+            ctx.accounts.payer.as_ref(),
+            ctx.remaining_accounts,
+            crate::LIGHT_CPI_SIGNER,
+        );
 
-        // NOTE: Compressed tokens are minted CLIENT-SIDE using:
-        // const mintTx = await mintTo(rpc, payer, mint, saleAuthority, creator, supply);
-        // This is done after this instruction succeeds.
-        // The sale_authority PDA will hold the compressed tokens for sale.
+        let sale =
+            LightAccount::<CompressedTokenSale>::new_mut(&crate::ID, &account_meta, current_sale)?;
+        require!(sale.active, ErrorCode::AlreadyClosed);
+        require_sale_ended(sale.end_ts)?;
+
+        close_compressed_sale(
+            &light_cpi_accounts,
+            &ctx.accounts.compressed_token_program,
+            &ctx.accounts.compressed_token_cpi_authority,
+            &ctx.accounts.sale_authority,
+            ctx.bumps.sale_authority,
+            sale,
+            proof,
+            token_proof,
+            sale_token_accounts,
+            token_output_tree_index,
+        )
+    }
+}
 
-        emit!(TokenLaunchedCompressed {
-            token_mint: ctx.accounts.token_mint.key(),
-            creator: ctx.accounts.creator.key(),
-            compressed_address: address,
-            sale_authority: ctx.accounts.sale_authority.key(),
-            symbol,
-            name,
-            price: price_per_token,
-            supply,
-            limit_per_mint,
-            metadata_id,
-        });
+// ==========================
+// Purchases
+// ==========================
+/// How much a buy asks for
+#[derive(Clone, Copy, Debug)]
+enum BuyOrder {
+    /// Spend at most `usdc_amount`, receiving at least `min_tokens_out`
+    Spend {
+        usdc_amount: u64,
+        min_tokens_out: u64,
+    },
+    /// Receive exactly `token_amount`, paying at most `max_usdc`
+    Exact { token_amount: u64, max_usdc: u64 },
+}
 
-        Ok(())
+/// Shared body of buy_tokens and buy_exact_tokens
+fn process_buy<'info>(
+    ctx: Context<'_, '_, '_, 'info, BuyTokens<'info>>,
+    order: BuyOrder,
+    allowlist: Option<AllowlistProof>,
+    proof: ValidityProof,
+    buyer_record: Option<BuyerRecordInput>,
+    referrer_record: Option<ReferrerRecordInput>,
+) -> Result<()> {
+    let buyer_key = ctx.accounts.buyer.key();
+    let token_mint_key = ctx.accounts.token_mint.key();
+    let sale = &mut ctx.accounts.token_sale;
+    let state = &ctx.accounts.app_state;
+    let quote_currency = sale.quote_currency;
+
+    require!(sale.active, ErrorCode::SaleNotActive);
+    require_sale_open(sale.start_ts, sale.end_ts)?;
+
+    let price = current_price(
+        sale.price_per_token,
+        sale.auction,
+        sale.start_ts,
+        sale.end_ts,
+    )?;
+    let (tokens_to_send, usdc_spent) = fill_order(
+        order,
+        price,
+        sale.price_slope,
+        sale.decimals,
+        sale.tokens_sold,
+        sale.limit_per_mint,
+        sale.hard_cap_usdc,
+        sale.usdc_raised,
+    )?;
+    // (platform fee, creator share) for paid sales
+    let usdc_split = if sale.price_per_token > 0 {
+        Some(split_platform_fee(usdc_spent, sale.platform_fee_bps)?)
+    } else {
+        None
+    };
+
+    let light_cpi_accounts = CpiAccounts::new(
+        ctx.accounts.buyer.as_ref(),
+        ctx.remaining_accounts,
+        crate::LIGHT_CPI_SIGNER,
+    );
+    let refundable = uniform_clearing(sale.auction) || sale.soft_cap > 0;
+    let record = if tracks_wallet_purchases(
+        sale.max_per_wallet,
+        sale.merkle_root,
+        refundable || sale.vesting.is_some(),
+    ) {
+        Some(load_buyer_record(
+            &light_cpi_accounts,
+            &token_mint_key,
+            &buyer_key,
+            buyer_record.ok_or(ErrorCode::BuyerRecordRequired)?,
+            tokens_to_send,
+            usdc_spent,
+        )?)
+    } else {
+        None
+    };
+    let wallet_total = record
+        .as_ref()
+        .map_or(tokens_to_send, |(record, _)| record.tokens_bought);
+    check_wallet_limits(
+        sale.max_per_wallet,
+        sale.merkle_root,
+        allowlist.as_ref(),
+        &buyer_key,
+        wallet_total,
+    )?;
+
+    // Refundable sales only take the platform fee at settlement
+    let referrer = ctx.accounts.referrer.as_ref().map(Key::key);
+    require!(
+        referrer.is_none() || !refundable,
+        ErrorCode::ReferralUnavailable
+    );
+    let (referral_fee, referral_record) = load_referral(
+        &light_cpi_accounts,
+        referrer,
+        &sale.quote_mint,
+        referrer_record,
+        usdc_spent,
+        usdc_split,
+        state.referral_fee_bps,
+    )?;
+
+    // Token-2022 transfer fees are paid out of the unsold supply, so the buyer
+    // receives tokens_to_send and the sale account keeps supply_for_sale - tokens_sold
+    let withheld =
+        inverse_transfer_fee(&ctx.accounts.token_mint.to_account_info(), tokens_to_send)?;
+    let new_total = sale
+        .tokens_sold
+        .checked_add(tokens_to_send)
+        .ok_or(ErrorCode::MathOverflow)?;
+    let supply_for_sale = sale
+        .supply_for_sale
+        .checked_sub(withheld)
+        .ok_or(ErrorCode::InsufficientSupply)?;
+    require!(new_total <= supply_for_sale, ErrorCode::InsufficientSupply);
+    sale.supply_for_sale = supply_for_sale;
+    sale.tokens_sold = new_total;
+    sale.usdc_raised = sale
+        .usdc_raised
+        .checked_add(usdc_spent)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    if sale.tokens_sold == sale.supply_for_sale
+        || hard_cap_reached(
+            sale.hard_cap_usdc,
+            sale.usdc_raised,
+            curve_cost(price, sale.price_slope, sale.decimals, sale.tokens_sold, 1)?,
+        )
+    {
+        sale.active = false;
     }
 
-    pub fn buy_tokens(ctx: Context<BuyTokens>, usdc_amount: u64) -> Result<()> {
-        let sale = &mut ctx.accounts.token_sale;
-        let state = &ctx.accounts.app_state;
+    // Curve sales hold the creator share in reserve to fund sell-backs.
+    // Refundable sales hold the whole payment until the sale closes.
+    // Sales with vesting proceeds escrow the creator share for withdraw_proceeds.
+    let fund_reserve = sale.price_slope > 0;
+    let escrow = refundable;
+    let vest_proceeds = sale.proceeds_vesting.is_some();
+    if let Some((_, creator_share)) = usdc_split {
+        let held = if escrow {
+            usdc_spent
+        } else if fund_reserve {
+            creator_share
+        } else {
+            0
+        };
+        sale.usdc_reserve = sale
+            .usdc_reserve
+            .checked_add(held)
+            .ok_or(ErrorCode::MathOverflow)?;
+        if vest_proceeds {
+            sale.proceeds_escrow = sale
+                .proceeds_escrow
+                .checked_add(creator_share)
+                .ok_or(ErrorCode::MathOverflow)?;
+        }
+    }
+    if sale.auction.is_some() {
+        sale.clearing_price = price;
+    }
 
-        require!(sale.active, ErrorCode::SaleNotActive);
+    // Create or update the buyer's and referrer's compressed records via
+    // Light System Program
+    if record.is_some() || referral_record.is_some() {
+        let mut cpi = LightSystemProgramCpi::new_cpi(LIGHT_CPI_SIGNER, proof);
+        let mut new_addresses = Vec::new();
+        if let Some((record, new_address)) = record {
+            cpi = cpi.with_light_account(record)?;
+            new_addresses.extend(new_address);
+        }
+        if let Some((record, new_address)) = referral_record {
+            cpi = cpi.with_light_account(record)?;
+            new_addresses.extend(new_address);
+        }
+        if !new_addresses.is_empty() {
+            cpi = cpi.with_new_addresses(&new_addresses);
+        }
+        cpi.invoke(light_cpi_accounts)?;
+    }
 
-        let tokens_to_send: u64;
+    match (usdc_split, quote_currency) {
+        (Some((fee, creator_share)), QuoteCurrency::Sol) => pay_in_sol(
+            &ctx.accounts.buyer,
+            ctx.accounts.creator.as_ref(),
+            ctx.accounts.fee_recipient.as_ref(),
+            ctx.accounts
+                .referrer
+                .as_ref()
+                .map(|referrer| (referrer, referral_fee)),
+            &ctx.accounts.system_program,
+            fee - referral_fee,
+            creator_share,
+        )?,
+        (Some((fee, creator_share)), QuoteCurrency::Token) => {
+            require!(
+                ctx.accounts.quote_mint_entry.is_some(),
+                ErrorCode::QuoteMintNotRegistered
+            );
+            let (
+                Some(buyer_usdc_account),
+                Some(creator_usdc_account),
+                Some(fee_recipient_usdc_account),
+                Some(sale_escrow),
+            ) = (
+                ctx.accounts.buyer_usdc_account.as_ref(),
+                ctx.accounts.creator_usdc_account.as_ref(),
+                ctx.accounts.fee_recipient_usdc_account.as_ref(),
+                ctx.accounts.sale_escrow.as_ref(),
+            )
+            else {
+                return err!(ErrorCode::PaymentAccountsRequired);
+            };
+            let referrer_usdc_account = referrer_token_account(
+                ctx.accounts.referrer.as_ref(),
+                ctx.accounts.referrer_usdc_account.as_ref(),
+            )?;
+            token::transfer(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: buyer_usdc_account.to_account_info(),
+                        to: sale_escrow.to_account_info(),
+                        authority: ctx.accounts.buyer.to_account_info(),
+                    },
+                ),
+                usdc_spent,
+            )?;
 
-        if sale.price_per_token > 0 {
-            require!(usdc_amount > 0, ErrorCode::AmountMustBePositive);
+            let auth_seeds = &[b"authority".as_ref(), &[ctx.bumps.program_authority]];
+            let auth_signer = &[&auth_seeds[..]];
 
-            let decimals_multiplier = 10u64.pow(sale.decimals as u32);
+            if !escrow {
+                token::transfer(
+                    CpiContext::new_with_signer(
+                        ctx.accounts.token_program.to_account_info(),
+                        Transfer {
+                            from: sale_escrow.to_account_info(),
+                            to: fee_recipient_usdc_account.to_account_info(),
+                            authority: ctx.accounts.program_authority.to_account_info(),
+                        },
+                        auth_signer,
+                    ),
+                    fee - referral_fee,
+                )?;
+            }
+            if let Some(referrer_usdc_account) = referrer_usdc_account {
+                pay_out(
+                    &ctx.accounts.token_program,
+                    sale_escrow,
+                    &ctx.accounts.program_authority,
+                    ctx.bumps.program_authority,
+                    &[(referrer_usdc_account, referral_fee)],
+                )?;
+            }
 
-            tokens_to_send = usdc_amount
-                .checked_mul(decimals_multiplier)
-                .ok_or(ErrorCode::MathOverflow)?
-                .checked_div(sale.price_per_token)
-                .ok_or(ErrorCode::MathOverflow)?;
+            if !fund_reserve && !escrow && !vest_proceeds {
+                token::transfer(
+                    CpiContext::new_with_signer(
+                        ctx.accounts.token_program.to_account_info(),
+                        Transfer {
+                            from: sale_escrow.to_account_info(),
+                            to: creator_usdc_account.to_account_info(),
+                            authority: ctx.accounts.program_authority.to_account_info(),
+                        },
+                        auth_signer,
+                    ),
+                    creator_share,
+                )?;
+            }
+        }
+        (None, _) => {}
+    }
 
-            require!(tokens_to_send > 0, ErrorCode::PurchaseAmountTooSmall);
+    // Vesting sales hold purchases in the vesting vault until claim_vested
+    let recipient = match (sale.vesting, ctx.accounts.vesting_vault.as_ref()) {
+        (None, _) => ctx.accounts.buyer_token_account.to_account_info(),
+        (Some(_), Some(vesting_vault)) => vesting_vault.to_account_info(),
+        (Some(_), None) => return err!(ErrorCode::VestingVaultRequired),
+    };
+    let seeds = &[b"token_sale", token_mint_key.as_ref(), &[sale.bump]];
+    let signer = &[&seeds[..]];
+
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            ctx.accounts.sale_token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.sale_token_account.to_account_info(),
+                mint: ctx.accounts.token_mint.to_account_info(),
+                to: recipient,
+                authority: ctx.accounts.token_sale.to_account_info(),
+            },
+            signer,
+        ),
+        tokens_to_send + withheld,
+        ctx.accounts.token_mint.decimals,
+    )?;
+
+    emit!(TokenBought {
+        token_mint: token_mint_key,
+        buyer: buyer_key,
+        usdc_spent,
+        tokens_received: tokens_to_send,
+        quote_currency,
+        referrer,
+        referral_fee,
+    });
+
+    Ok(())
+}
 
-            if sale.limit_per_mint > 0 {
-                require!(
-                    tokens_to_send <= sale.limit_per_mint,
-                    ErrorCode::ExceedsMintLimit
-                );
-            }
+/// Shared body of buy_tokens_compressed and buy_exact_tokens_compressed
+fn process_buy_compressed<'info>(
+    ctx: Context<'_, '_, '_, 'info, BuyTokensCompressed<'info>>,
+    proof: ValidityProof,
+    current_sale: CompressedTokenSale,
+    account_meta: CompressedAccountMeta,
+    order: BuyOrder,
+    allowlist: Option<AllowlistProof>,
+    buyer_record: Option<BuyerRecordInput>,
+    referrer_record: Option<ReferrerRecordInput>,
+    token_proof: ValidityProof,
+    sale_token_accounts: Vec<TokenAccountMeta>,
+    token_output_tree_index: u8,
+) -> Result<()> {
+    let state = &ctx.accounts.app_state;
+    let token_mint_key = ctx.accounts.token_mint.key();
+
+    require!(
+        account_meta.address == CompressedTokenSale::derive_address(&token_mint_key),
+        ErrorCode::InvalidSaleAddress
+    );
+
+    let light_cpi_accounts = CpiAccounts::new(
+        ctx.accounts.buyer.as_ref(),
+        ctx.remaining_accounts,
+        crate::LIGHT_CPI_SIGNER,
+    );
+
+    // The supplied state becomes the input account hashed for proof verification
+    let mut sale =
+        LightAccount::<CompressedTokenSale>::new_mut(&crate::ID, &account_meta, current_sale)?;
+    let quote_currency = sale.quote_currency;
+    // Vesting sales hold purchases with the vesting authority until claimed
+    let recipient = if sale.vesting.is_some() {
+        ctx.accounts.vesting_authority.key()
+    } else {
+        ctx.accounts.buyer.key()
+    };
 
-            let fee = usdc_amount
-                .checked_mul(state.platform_fee_bps as u64)
-                .ok_or(ErrorCode::MathOverflow)?
-                .checked_div(10_000)
-                .ok_or(ErrorCode::MathOverflow)?;
-            let creator_share = usdc_amount.checked_sub(fee).ok_or(ErrorCode::MathOverflow)?;
+    require!(sale.active, ErrorCode::SaleNotActive);
+    require_sale_open(sale.start_ts, sale.end_ts)?;
+
+    let price = current_price(
+        sale.price_per_token,
+        sale.auction,
+        sale.start_ts,
+        sale.end_ts,
+    )?;
+    let (tokens_to_send, usdc_spent) = fill_order(
+        order,
+        price,
+        sale.price_slope,
+        sale.decimals,
+        sale.tokens_sold,
+        sale.limit_per_mint,
+        sale.hard_cap_usdc,
+        sale.usdc_raised,
+    )?;
+    // (platform fee, creator share) for paid sales
+    let usdc_split = if sale.price_per_token > 0 {
+        Some(split_platform_fee(usdc_spent, sale.platform_fee_bps)?)
+    } else {
+        None
+    };
 
-            let new_total = sale
-                .tokens_sold
-                .checked_add(tokens_to_send)
-                .ok_or(ErrorCode::MathOverflow)?;
-            require!(new_total <= sale.supply_for_sale, ErrorCode::InsufficientSupply);
-            sale.tokens_sold = new_total;
+    let record = if tracks_wallet_purchases(
+        sale.max_per_wallet,
+        sale.merkle_root,
+        uniform_clearing(sale.auction) || sale.vesting.is_some(),
+    ) {
+        Some(load_buyer_record(
+            &light_cpi_accounts,
+            &token_mint_key,
+            &ctx.accounts.buyer.key(),
+            buyer_record.ok_or(ErrorCode::BuyerRecordRequired)?,
+            tokens_to_send,
+            usdc_spent,
+        )?)
+    } else {
+        None
+    };
+    let wallet_total = record
+        .as_ref()
+        .map_or(tokens_to_send, |(record, _)| record.tokens_bought);
+    check_wallet_limits(
+        sale.max_per_wallet,
+        sale.merkle_root,
+        allowlist.as_ref(),
+        &ctx.accounts.buyer.key(),
+        wallet_total,
+    )?;
+
+    let referrer = ctx.accounts.referrer.as_ref().map(Key::key);
+    let (referral_fee, referral_record) = load_referral(
+        &light_cpi_accounts,
+        referrer,
+        &sale.quote_mint,
+        referrer_record,
+        usdc_spent,
+        usdc_split,
+        state.referral_fee_bps,
+    )?;
+
+    let new_total = sale
+        .tokens_sold
+        .checked_add(tokens_to_send)
+        .ok_or(ErrorCode::MathOverflow)?;
+    require!(
+        new_total <= sale.supply_for_sale,
+        ErrorCode::InsufficientSupply
+    );
+
+    sale.tokens_sold = new_total;
+    sale.usdc_raised = sale
+        .usdc_raised
+        .checked_add(usdc_spent)
+        .ok_or(ErrorCode::MathOverflow)?;
+    if sale.tokens_sold == sale.supply_for_sale
+        || hard_cap_reached(
+            sale.hard_cap_usdc,
+            sale.usdc_raised,
+            curve_cost(price, sale.price_slope, sale.decimals, sale.tokens_sold, 1)?,
+        )
+    {
+        sale.active = false;
+    }
 
-            if sale.tokens_sold == sale.supply_for_sale {
-                sale.active = false;
-            }
+    // Verify the input sale state and write the update via Light System Program
+    let mut cpi =
+        LightSystemProgramCpi::new_cpi(LIGHT_CPI_SIGNER, proof).with_light_account(sale)?;
+    let mut new_addresses = Vec::new();
+    if let Some((record, new_address)) = record {
+        cpi = cpi.with_light_account(record)?;
+        new_addresses.extend(new_address);
+    }
+    if let Some((record, new_address)) = referral_record {
+        cpi = cpi.with_light_account(record)?;
+        new_addresses.extend(new_address);
+    }
+    if !new_addresses.is_empty() {
+        cpi = cpi.with_new_addresses(&new_addresses);
+    }
+    cpi.invoke(light_cpi_accounts.clone())?;
 
+    match (usdc_split, quote_currency) {
+        (Some((fee, creator_share)), QuoteCurrency::Sol) => pay_in_sol(
+            &ctx.accounts.buyer,
+            ctx.accounts.creator.as_ref(),
+            ctx.accounts.fee_recipient.as_ref(),
+            ctx.accounts
+                .referrer
+                .as_ref()
+                .map(|referrer| (referrer, referral_fee)),
+            &ctx.accounts.system_program,
+            fee - referral_fee,
+            creator_share,
+        )?,
+        (Some((fee, creator_share)), QuoteCurrency::Token) => {
+            require!(
+                ctx.accounts.quote_mint_entry.is_some(),
+                ErrorCode::QuoteMintNotRegistered
+            );
+            let (
+                Some(buyer_usdc_account),
+                Some(creator_usdc_account),
+                Some(fee_recipient_usdc_account),
+                Some(program_usdc_account),
+            ) = (
+                ctx.accounts.buyer_usdc_account.as_ref(),
+                ctx.accounts.creator_usdc_account.as_ref(),
+                ctx.accounts.fee_recipient_usdc_account.as_ref(),
+                ctx.accounts.program_usdc_account.as_ref(),
+            )
+            else {
+                return err!(ErrorCode::PaymentAccountsRequired);
+            };
+            let referrer_usdc_account = referrer_token_account(
+                ctx.accounts.referrer.as_ref(),
+                ctx.accounts.referrer_usdc_account.as_ref(),
+            )?;
+            // USDC transfers - buyer pays for tokens
             token::transfer(
                 CpiContext::new(
                     ctx.accounts.token_program.to_account_info(),
                     Transfer {
-                        from: ctx.accounts.buyer_usdc_account.to_account_info(),
-                        to: ctx.accounts.program_usdc_account.to_account_info(),
+                        from: buyer_usdc_account.to_account_info(),
+                        to: program_usdc_account.to_account_info(),
                         authority: ctx.accounts.buyer.to_account_info(),
                     },
                 ),
-                usdc_amount,
+                usdc_spent,
             )?;
 
             let auth_seeds = &[b"authority".as_ref(), &[ctx.bumps.program_authority]];
             let auth_signer = &[&auth_seeds[..]];
 
+            // Platform fee to the fee recipient, less the referral share
             token::transfer(
                 CpiContext::new_with_signer(
                     ctx.accounts.token_program.to_account_info(),
                     Transfer {
-                        from: ctx.accounts.program_usdc_account.to_account_info(),
-                        to: ctx.accounts.owner_usdc_account.to_account_info(),
+                        from: program_usdc_account.to_account_info(),
+                        to: fee_recipient_usdc_account.to_account_info(),
                         authority: ctx.accounts.program_authority.to_account_info(),
                     },
                     auth_signer,
                 ),
-                fee,
+                fee - referral_fee,
             )?;
+            if let Some(referrer_usdc_account) = referrer_usdc_account {
+                pay_out(
+                    &ctx.accounts.token_program,
+                    program_usdc_account,
+                    &ctx.accounts.program_authority,
+                    ctx.bumps.program_authority,
+                    &[(referrer_usdc_account, referral_fee)],
+                )?;
+            }
 
+            // Creator receives their share
             token::transfer(
                 CpiContext::new_with_signer(
                     ctx.accounts.token_program.to_account_info(),
                     Transfer {
-                        from: ctx.accounts.program_usdc_account.to_account_info(),
-                        to: ctx.accounts.creator_usdc_account.to_account_info(),
+                        from: program_usdc_account.to_account_info(),
+                        to: creator_usdc_account.to_account_info(),
                         authority: ctx.accounts.program_authority.to_account_info(),
                     },
                     auth_signer,
                 ),
                 creator_share,
             )?;
-        } else {
-            require!(usdc_amount == 0, ErrorCode::FreeMintRequiresZeroPayment);
-            require!(sale.limit_per_mint > 0, ErrorCode::LimitPerMintNotSet);
-            tokens_to_send = sale.limit_per_mint;
+        }
+        (None, _) => {}
+    }
+
+    // Deliver compressed tokens to the buyer, signed by the sale_authority PDA
+    let sale_authority_seeds = &[
+        b"sale_authority".as_ref(),
+        token_mint_key.as_ref(),
+        &[ctx.bumps.sale_authority],
+    ];
+    transfer_compressed_tokens(
+        &light_cpi_accounts,
+        &ctx.accounts.compressed_token_program,
+        &ctx.accounts.compressed_token_cpi_authority,
+        &ctx.accounts.sale_authority,
+        &[&sale_authority_seeds[..]],
+        token_mint_key,
+        token_proof,
+        sale_token_accounts,
+        recipient,
+        tokens_to_send,
+        token_output_tree_index,
+    )?;
+
+    emit!(TokenBoughtCompressed {
+        token_mint: token_mint_key,
+        buyer: ctx.accounts.buyer.key(),
+        usdc_spent,
+        tokens_received: tokens_to_send,
+        sale_authority: ctx.accounts.sale_authority.key(),
+        sale_authority_bump: ctx.bumps.sale_authority,
+        quote_currency,
+        referrer,
+        referral_fee,
+    });
+
+    Ok(())
+}
 
-            let new_total = sale
-                .tokens_sold
-                .checked_add(tokens_to_send)
+/// Tokens delivered and USDC charged for `order` at `price` (0 for free mints)
+fn fill_order(
+    order: BuyOrder,
+    price: u64,
+    price_slope: u64,
+    decimals: u8,
+    tokens_sold: u64,
+    limit_per_mint: u64,
+    hard_cap_usdc: u64,
+    usdc_raised: u64,
+) -> Result<(u64, u64)> {
+    let (tokens, usdc) = match order {
+        BuyOrder::Spend { usdc_amount, .. } if price == 0 => {
+            require!(usdc_amount == 0, ErrorCode::FreeMintRequiresZeroPayment);
+            require!(limit_per_mint > 0, ErrorCode::LimitPerMintNotSet);
+            (limit_per_mint, 0)
+        }
+        BuyOrder::Spend { usdc_amount, .. } => {
+            require!(usdc_amount > 0, ErrorCode::AmountMustBePositive);
+            quote_purchase(
+                price,
+                price_slope,
+                decimals,
+                tokens_sold,
+                capped_budget(usdc_amount, hard_cap_usdc, usdc_raised),
+            )?
+        }
+        BuyOrder::Exact { token_amount, .. } => {
+            require!(token_amount > 0, ErrorCode::AmountMustBePositive);
+            let cost = curve_cost(price, price_slope, decimals, tokens_sold, token_amount)?;
+            let raised = usdc_raised
+                .checked_add(cost)
                 .ok_or(ErrorCode::MathOverflow)?;
-            require!(new_total <= sale.supply_for_sale, ErrorCode::InsufficientSupply);
-            sale.tokens_sold = new_total;
+            require!(
+                hard_cap_usdc == 0 || raised <= hard_cap_usdc,
+                ErrorCode::ExceedsHardCap
+            );
+            (token_amount, cost)
+        }
+    };
 
-            if sale.tokens_sold == sale.supply_for_sale {
-                sale.active = false;
-            }
+    require!(tokens > 0, ErrorCode::PurchaseAmountTooSmall);
+    if limit_per_mint > 0 {
+        require!(tokens <= limit_per_mint, ErrorCode::ExceedsMintLimit);
+    }
+    match order {
+        BuyOrder::Spend { min_tokens_out, .. } => {
+            require!(tokens >= min_tokens_out, ErrorCode::SlippageExceeded);
+        }
+        BuyOrder::Exact { max_usdc, .. } => {
+            require!(usdc <= max_usdc, ErrorCode::MaxUsdcExceeded);
         }
+    }
+    Ok((tokens, usdc))
+}
 
-        let token_mint_key = ctx.accounts.token_mint.key();
-        let seeds = &[b"token_sale", token_mint_key.as_ref(), &[sale.bump]];
-        let signer = &[&seeds[..]];
+/// Splits a payment into (platform fee, creator share)
+fn split_platform_fee(usdc: u64, fee_bps: u16) -> Result<(u64, u64)> {
+    let fee = usdc
+        .checked_mul(fee_bps as u64)
+        .ok_or(ErrorCode::MathOverflow)?
+        .checked_div(10_000)
+        .ok_or(ErrorCode::MathOverflow)?;
+    let creator_share = usdc.checked_sub(fee).ok_or(ErrorCode::MathOverflow)?;
+    Ok((fee, creator_share))
+}
 
-        token::transfer(
-            CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.sale_token_account.to_account_info(),
-                    to: ctx.accounts.buyer_token_account.to_account_info(),
-                    authority: ctx.accounts.token_sale.to_account_info(),
+// ==========================
+// Sale Params
+// ==========================
+/// Optional settings of launch_token and launch_token_compressed. The default
+/// is a fixed-price sale in the registered quote mint, open until closed, with
+/// no allowlist, caps or vesting. Compressed sales reject `soft_cap` and
+/// `proceeds_vesting`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SaleParams {
+    /// Unix timestamp before which buys are rejected
+    pub start_ts: Option<i64>,
+    /// Unix timestamp from which buys are rejected and anyone can finalize
+    pub end_ts: Option<i64>,
+    /// Allowlist root; when set, buyers must prove a (buyer, allocation) leaf
+    pub merkle_root: Option<[u8; 32]>,
+    /// Lifetime purchase cap per wallet (0 = no cap)
+    pub max_per_wallet: u64,
+    /// Price increase per whole token sold (0 = fixed price)
+    pub price_slope: u64,
+    /// Dutch auction decaying the price to a floor
+    pub auction: Option<DutchAuction>,
+    /// USDC to raise by end_ts, refunded to buyers if missed (0 = none)
+    pub soft_cap: u64,
+    /// Sale closes once usdc_raised reaches this (0 = no cap)
+    pub hard_cap_usdc: u64,
+    /// Currency of price_per_token, the caps and usdc_raised
+    pub quote_currency: QuoteCurrency,
+    /// Linear release of purchases to buyers
+    pub vesting: Option<Vesting>,
+    /// Linear release of the creator share of proceeds
+    pub proceeds_vesting: Option<Vesting>,
+}
+
+// ==========================
+// Quote Currency
+// ==========================
+/// Lowest price_per_token (and auction floor) of SOL sales, in lamports
+const MIN_SOL_PRICE: u64 = 1_000;
+/// Decimals of a lamport amount
+const SOL_DECIMALS: u8 = 9;
+
+/// What a sale's prices, caps and payments are denominated in
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq, InitSpace,
+)]
+pub enum QuoteCurrency {
+    /// The sale's `quote_mint`, a registered SPL mint paid through the
+    /// authority PDA's token account
+    #[default]
+    Token,
+    /// Native SOL, paid in lamports straight to the creator and fee recipient
+    Sol,
+}
+
+/// Quote mint (default for SOL), minimum price and decimals of a sale
+/// launched in `quote_currency`
+fn quote_terms(
+    quote_currency: QuoteCurrency,
+    quote_mint_entry: Option<&QuoteMintEntry>,
+) -> Result<(Pubkey, u64, u8)> {
+    match quote_currency {
+        QuoteCurrency::Token => {
+            let entry = quote_mint_entry.ok_or(ErrorCode::QuoteMintNotRegistered)?;
+            Ok((entry.mint, entry.min_price, entry.decimals))
+        }
+        QuoteCurrency::Sol => Ok((Pubkey::default(), MIN_SOL_PRICE, SOL_DECIMALS)),
+    }
+}
+
+/// SOL payments go straight to their recipients, so SOL sales cannot use the
+/// features that hold payments in the reserve
+fn validate_quote_currency(
+    quote_currency: QuoteCurrency,
+    price_slope: u64,
+    auction: Option<DutchAuction>,
+    soft_cap: u64,
+) -> Result<()> {
+    if quote_currency == QuoteCurrency::Sol {
+        require!(
+            price_slope == 0 && !uniform_clearing(auction) && soft_cap == 0,
+            ErrorCode::InvalidQuoteCurrency
+        );
+    }
+    Ok(())
+}
+
+/// Pays the platform fee and creator share of a SOL purchase from the buyer.
+/// A payment may not leave its recipient below the rent-exempt minimum, which
+/// the runtime would reject for a wallet that does not exist yet
+fn pay_in_sol<'info>(
+    buyer: &Signer<'info>,
+    creator: Option<&SystemAccount<'info>>,
+    fee_recipient: Option<&SystemAccount<'info>>,
+    referral: Option<(&SystemAccount<'info>, u64)>,
+    system_program: &Program<'info, System>,
+    fee: u64,
+    creator_share: u64,
+) -> Result<()> {
+    let (Some(creator), Some(fee_recipient)) = (creator, fee_recipient) else {
+        return err!(ErrorCode::PaymentAccountsRequired);
+    };
+    let payouts = [(fee_recipient, fee), (creator, creator_share)]
+        .into_iter()
+        .chain(referral);
+    let rent = Rent::get()?;
+    for (recipient, lamports) in payouts {
+        if lamports == 0 {
+            continue;
+        }
+        let balance = recipient
+            .lamports()
+            .checked_add(lamports)
+            .ok_or(ErrorCode::MathOverflow)?;
+        require!(
+            rent.is_exempt(balance, recipient.data_len()),
+            ErrorCode::RecipientNotRentExempt
+        );
+        system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                system_program::Transfer {
+                    from: buyer.to_account_info(),
+                    to: recipient.to_account_info(),
                 },
-                signer,
             ),
-            tokens_to_send,
+            lamports,
         )?;
+    }
+    Ok(())
+}
 
-        emit!(TokenBought {
-            token_mint: ctx.accounts.token_mint.key(),
-            buyer: ctx.accounts.buyer.key(),
-            usdc_spent: usdc_amount,
-            tokens_received: tokens_to_send,
-        });
+// ==========================
+// Pricing
+// ==========================
+/// Whole tokens bought with at most `usdc_amount` and the USDC they cost.
+/// Prices are USDC base units per whole token. With a slope the price is
+/// `price_per_token + price_slope * sold / 10^decimals` and a purchase costs the
+/// area under that line, rounded up. Without one, the tokens cost
+/// `tokens * price_per_token / 10^decimals`, rounded up, so the remainder of
+/// `usdc_amount` that buys no whole base unit is never charged.
+fn quote_purchase(
+    price_per_token: u64,
+    price_slope: u64,
+    decimals: u8,
+    tokens_sold: u64,
+    usdc_amount: u64,
+) -> Result<(u64, u64)> {
+    let unit = 10u128.pow(decimals as u32);
+    if price_slope == 0 {
+        let tokens = (usdc_amount as u128)
+            .checked_mul(unit)
+            .ok_or(ErrorCode::MathOverflow)?
+            / price_per_token as u128;
+        let tokens = u64::try_from(tokens).map_err(|_| ErrorCode::MathOverflow)?;
+        let cost = curve_cost(price_per_token, 0, decimals, tokens_sold, tokens)?;
+        return Ok((tokens, cost));
+    }
 
-        Ok(())
+    // cost(n) * 2 * unit^2 = a * n^2 + b * n, solved for cost(n) = usdc_amount
+    let a = price_slope as u128;
+    let b = (2 * unit * price_per_token as u128)
+        .checked_add(
+            (2 * a)
+                .checked_mul(tokens_sold as u128)
+                .ok_or(ErrorCode::MathOverflow)?,
+        )
+        .ok_or(ErrorCode::MathOverflow)?;
+    let c = (2 * unit * unit)
+        .checked_mul(usdc_amount as u128)
+        .ok_or(ErrorCode::MathOverflow)?;
+    let discriminant = b
+        .checked_mul(b)
+        .and_then(|b2| a.checked_mul(c)?.checked_mul(4)?.checked_add(b2))
+        .ok_or(ErrorCode::MathOverflow)?;
+    let mut tokens = u64::try_from((isqrt(discriminant) - b) / (2 * a))
+        .map_err(|_| ErrorCode::MathOverflow)?;
+
+    // Rounding up the cost can leave the root one token too high
+    let mut cost = curve_cost(price_per_token, price_slope, decimals, tokens_sold, tokens)?;
+    while cost > usdc_amount {
+        tokens -= 1;
+        cost = curve_cost(price_per_token, price_slope, decimals, tokens_sold, tokens)?;
     }
+    Ok((tokens, cost))
+}
 
-    /// Buy tokens from a compressed TokenSale
-    ///
-    /// This instruction handles:
-    /// 1. USDC payment from buyer to creator (with platform fee)
-    /// 2. Updates compressed sale state (tokens_sold, active status)
-    ///
-    /// The compressed token transfer is done CLIENT-SIDE after this instruction:
-    /// const transferTx = await transfer(rpc, payer, mint, tokens_to_send, saleAuthority, buyer.publicKey);
-    ///
-    /// Returns the tokens_to_send value in the event for client to use.
-    pub fn buy_tokens_compressed<'info>(
-        ctx: Context<'_, '_, '_, 'info, BuyTokensCompressed<'info>>,
-        proof: ValidityProof,
-        current_sale: CompressedTokenSale,
-        account_meta: CompressedAccountMeta,
-        usdc_amount: u64,
-    ) -> Result<()> {
-        let state = &ctx.accounts.app_state;
+/// USDC cost of `tokens` on a linear curve after `tokens_sold`, rounded up
+fn curve_cost(
+    price_per_token: u64,
+    price_slope: u64,
+    decimals: u8,
+    tokens_sold: u64,
+    tokens: u64,
+) -> Result<u64> {
+    let (numerator, denominator) =
+        curve_area(price_per_token, price_slope, decimals, tokens_sold, tokens)?;
+    u64::try_from(numerator.div_ceil(denominator)).map_err(|_| ErrorCode::MathOverflow.into())
+}
+
+/// USDC paid for selling `tokens` back down to `tokens_sold`: the area under
+/// the curve rounded down, less `fee_bps` so it never exceeds the reserve
+/// funded by the original purchases.
+fn curve_proceeds(
+    price_per_token: u64,
+    price_slope: u64,
+    decimals: u8,
+    tokens_sold: u64,
+    tokens: u64,
+    fee_bps: u16,
+) -> Result<u64> {
+    let (numerator, denominator) =
+        curve_area(price_per_token, price_slope, decimals, tokens_sold, tokens)?;
+    let proceeds = (numerator / denominator)
+        .checked_mul(10_000 - fee_bps as u128)
+        .ok_or(ErrorCode::MathOverflow)?
+        / 10_000;
+    u64::try_from(proceeds).map_err(|_| ErrorCode::MathOverflow.into())
+}
 
-        require!(current_sale.active, ErrorCode::SaleNotActive);
+/// Area under the price line for `tokens` after `tokens_sold`, as a fraction
+fn curve_area(
+    price_per_token: u64,
+    price_slope: u64,
+    decimals: u8,
+    tokens_sold: u64,
+    tokens: u64,
+) -> Result<(u128, u128)> {
+    let unit = 10u128.pow(decimals as u32);
+    let (sold, n) = (tokens_sold as u128, tokens as u128);
+    let numerator = (2 * unit * price_per_token as u128)
+        .checked_mul(n)
+        .and_then(|base| {
+            let area = (price_slope as u128).checked_mul(n)?.checked_mul(2 * sold + n)?;
+            base.checked_add(area)
+        })
+        .ok_or(ErrorCode::MathOverflow)?;
+    Ok((numerator, 2 * unit * unit))
+}
+
+/// USDC a buy may spend: `usdc_amount`, clipped to what is left under the hard cap
+fn capped_budget(usdc_amount: u64, hard_cap_usdc: u64, usdc_raised: u64) -> u64 {
+    if hard_cap_usdc == 0 {
+        usdc_amount
+    } else {
+        usdc_amount.min(hard_cap_usdc.saturating_sub(usdc_raised))
+    }
+}
+
+/// Whether a sale with `hard_cap_usdc` has raised enough to close: what is left
+/// under the cap no longer covers `unit_cost`, the price of the next base unit
+fn hard_cap_reached(hard_cap_usdc: u64, usdc_raised: u64, unit_cost: u64) -> bool {
+    hard_cap_usdc > 0 && hard_cap_usdc.saturating_sub(usdc_raised) < unit_cost.max(1)
+}
+
+/// Hard caps need a paid sale and cannot be below the soft cap
+fn validate_hard_cap(hard_cap_usdc: u64, price_per_token: u64, soft_cap: u64) -> Result<()> {
+    require!(
+        hard_cap_usdc == 0 || (price_per_token > 0 && hard_cap_usdc >= soft_cap),
+        ErrorCode::InvalidHardCap
+    );
+    Ok(())
+}
+
+/// Dutch auction price at `now`: decays linearly from `start_price` at
+/// `start_ts` to `floor_price` at `end_ts`, rounded up
+fn auction_price(start_price: u64, floor_price: u64, start_ts: i64, end_ts: i64, now: i64) -> u64 {
+    let elapsed = now.clamp(start_ts, end_ts).abs_diff(start_ts) as u128;
+    let duration = end_ts.abs_diff(start_ts) as u128;
+    let decay = (start_price - floor_price) as u128 * elapsed / duration;
+    start_price - decay as u64
+}
+
+/// Price per whole token a buy pays right now
+fn current_price(
+    price_per_token: u64,
+    auction: Option<DutchAuction>,
+    start_ts: Option<i64>,
+    end_ts: Option<i64>,
+) -> Result<u64> {
+    match (auction, start_ts, end_ts) {
+        (Some(auction), Some(start_ts), Some(end_ts)) => Ok(auction_price(
+            price_per_token,
+            auction.floor_price,
+            start_ts,
+            end_ts,
+            Clock::get()?.unix_timestamp,
+        )),
+        _ => Ok(price_per_token),
+    }
+}
+
+/// Integer square root (floor)
+fn isqrt(value: u128) -> u128 {
+    if value < 2 {
+        return value;
+    }
+    let mut x = 1u128 << (value.ilog2() / 2 + 1);
+    loop {
+        let y = (x + value / x) / 2;
+        if y >= x {
+            return x;
+        }
+        x = y;
+    }
+}
+
+// ==========================
+// Dutch Auction
+// ==========================
+/// Price decays from `price_per_token` at `start_ts` to `floor_price` at
+/// `end_ts`. With `uniform_clearing`, every buyer ends up paying the last
+/// price paid and claims the difference with claim_auction_refund.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub struct DutchAuction {
+    pub floor_price: u64,
+    pub uniform_clearing: bool,
+}
+
+fn validate_auction(
+    auction: Option<DutchAuction>,
+    price_per_token: u64,
+    min_price: u64,
+    price_slope: u64,
+    start_ts: Option<i64>,
+    end_ts: Option<i64>,
+) -> Result<()> {
+    if let Some(auction) = auction {
         require!(
-            current_sale.token_mint == ctx.accounts.token_mint.key(),
-            ErrorCode::InvalidMint
+            start_ts.is_some() && end_ts.is_some(),
+            ErrorCode::InvalidAuction
         );
-
-        let light_cpi_accounts = CpiAccounts::new(
-            ctx.accounts.buyer.as_ref(),
-            ctx.remaining_accounts,
-            crate::LIGHT_CPI_SIGNER,
+        require!(price_slope == 0, ErrorCode::InvalidAuction);
+        require!(
+            auction.floor_price >= min_price && auction.floor_price < price_per_token,
+            ErrorCode::InvalidAuction
         );
+    }
+    Ok(())
+}
 
-        let tokens_to_send: u64;
-        let mut updated_sale = current_sale.clone();
-
-        if current_sale.price_per_token > 0 {
-            require!(usdc_amount > 0, ErrorCode::AmountMustBePositive);
+fn uniform_clearing(auction: Option<DutchAuction>) -> bool {
+    auction.is_some_and(|auction| auction.uniform_clearing)
+}
 
-            let decimals_multiplier = 10u64.pow(current_sale.decimals as u32);
+// ==========================
+// Allowlist
+// ==========================
+/// Buyer's allocation and the merkle proof of its leaf. Leaves are
+/// keccak(buyer || allocation as u64 LE) and pairs are hashed in sorted order.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct AllowlistProof {
+    pub allocation: u64,
+    pub proof: Vec<[u8; 32]>,
+}
 
-            tokens_to_send = usdc_amount
-                .checked_mul(decimals_multiplier)
-                .ok_or(ErrorCode::MathOverflow)?
-                .checked_div(current_sale.price_per_token)
-                .ok_or(ErrorCode::MathOverflow)?;
+impl AllowlistProof {
+    pub fn leaf(buyer: &Pubkey, allocation: u64) -> [u8; 32] {
+        keccak::hashv(&[buyer.as_ref(), &allocation.to_le_bytes()]).to_bytes()
+    }
 
-            require!(tokens_to_send > 0, ErrorCode::PurchaseAmountTooSmall);
+    pub fn verify(&self, root: &[u8; 32], buyer: &Pubkey) -> bool {
+        let computed = self
+            .proof
+            .iter()
+            .fold(Self::leaf(buyer, self.allocation), |node, sibling| {
+                if node <= *sibling {
+                    keccak::hashv(&[&node, sibling]).to_bytes()
+                } else {
+                    keccak::hashv(&[sibling, &node]).to_bytes()
+                }
+            });
+        computed == *root
+    }
+}
 
-            if current_sale.limit_per_mint > 0 {
-                require!(
-                    tokens_to_send <= current_sale.limit_per_mint,
-                    ErrorCode::ExceedsMintLimit
-                );
-            }
+/// Allowlisted sales require a valid proof; `wallet_total` is the buyer's
+/// lifetime purchases including this one
+fn check_wallet_limits(
+    max_per_wallet: u64,
+    merkle_root: Option<[u8; 32]>,
+    allowlist: Option<&AllowlistProof>,
+    buyer: &Pubkey,
+    wallet_total: u64,
+) -> Result<()> {
+    if max_per_wallet > 0 {
+        require!(
+            wallet_total <= max_per_wallet,
+            ErrorCode::ExceedsWalletLimit
+        );
+    }
+    let Some(root) = merkle_root else {
+        return Ok(());
+    };
+    let allowlist = allowlist.ok_or(ErrorCode::InvalidAllowlistProof)?;
+    require!(
+        allowlist.verify(&root, buyer),
+        ErrorCode::InvalidAllowlistProof
+    );
+    require!(
+        wallet_total <= allowlist.allocation,
+        ErrorCode::ExceedsAllocation
+    );
+    Ok(())
+}
 
-            let fee = usdc_amount
-                .checked_mul(state.platform_fee_bps as u64)
-                .ok_or(ErrorCode::MathOverflow)?
-                .checked_div(10_000)
-                .ok_or(ErrorCode::MathOverflow)?;
-            let creator_share = usdc_amount.checked_sub(fee).ok_or(ErrorCode::MathOverflow)?;
+// ==========================
+// Buyer Records
+// ==========================
+/// How the buyer's record enters a buy: created at a new address on the
+/// first purchase, or the current state proven by the instruction's proof
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub enum BuyerRecordInput {
+    New {
+        address_tree_info: PackedAddressTreeInfo,
+        output_state_tree_index: u8,
+    },
+    Existing {
+        current: BuyerRecord,
+        account_meta: CompressedAccountMeta,
+    },
+}
 
-            let new_total = current_sale
-                .tokens_sold
-                .checked_add(tokens_to_send)
-                .ok_or(ErrorCode::MathOverflow)?;
-            require!(
-                new_total <= current_sale.supply_for_sale,
-                ErrorCode::InsufficientSupply
-            );
+/// Sales with a wallet cap, allowlist, buyer refunds or vesting need each
+/// buyer's lifetime totals
+fn tracks_wallet_purchases(
+    max_per_wallet: u64,
+    merkle_root: Option<[u8; 32]>,
+    settles_per_buyer: bool,
+) -> bool {
+    max_per_wallet > 0 || merkle_root.is_some() || settles_per_buyer
+}
 
-            updated_sale.tokens_sold = new_total;
-            if updated_sale.tokens_sold == updated_sale.supply_for_sale {
-                updated_sale.active = false;
-            }
+/// Address and new address params of a record created at `seeds`. Records
+/// share the sale address tree so each seed has exactly one.
+fn new_record_address(
+    light_cpi_accounts: &CpiAccounts,
+    address_tree_info: PackedAddressTreeInfo,
+    seeds: &[&[u8]],
+) -> Result<([u8; 32], PackedNewAddressParams)> {
+    let address_tree = address_tree_info
+        .get_tree_pubkey(light_cpi_accounts)
+        .map_err(|_| ErrorCode::InvalidAddressTree)?;
+    require!(
+        address_tree == COMPRESSED_SALE_ADDRESS_TREE,
+        ErrorCode::InvalidAddressTree
+    );
+    let (address, address_seed) = derive_address(seeds, &address_tree, &crate::ID);
+    Ok((
+        address,
+        address_tree_info.into_new_address_params_packed(address_seed),
+    ))
+}
 
-            // USDC transfers - buyer pays for tokens
-            token::transfer(
-                CpiContext::new(
-                    ctx.accounts.token_program.to_account_info(),
-                    Transfer {
-                        from: ctx.accounts.buyer_usdc_account.to_account_info(),
-                        to: ctx.accounts.program_usdc_account.to_account_info(),
-                        authority: ctx.accounts.buyer.to_account_info(),
-                    },
-                ),
-                usdc_amount,
+/// Returns the buyer's record with `tokens` and `usdc` added, plus the new
+/// address params when the record is being created
+fn load_buyer_record(
+    light_cpi_accounts: &CpiAccounts,
+    token_mint: &Pubkey,
+    buyer: &Pubkey,
+    input: BuyerRecordInput,
+    tokens: u64,
+    usdc: u64,
+) -> Result<(LightAccount<BuyerRecord>, Option<PackedNewAddressParams>)> {
+    let (mut record, new_address) = match input {
+        BuyerRecordInput::New {
+            address_tree_info,
+            output_state_tree_index,
+        } => {
+            let (address, new_address) = new_record_address(
+                light_cpi_accounts,
+                address_tree_info,
+                &[b"buyer_record", token_mint.as_ref(), buyer.as_ref()],
             )?;
+            let mut record = LightAccount::<BuyerRecord>::new_init(
+                &crate::ID,
+                Some(address),
+                output_state_tree_index,
+            );
+            record.token_mint = *token_mint;
+            record.buyer = *buyer;
+            (record, Some(new_address))
+        }
+        BuyerRecordInput::Existing {
+            current,
+            account_meta,
+        } => {
+            require!(
+                account_meta.address == BuyerRecord::derive_address(token_mint, buyer),
+                ErrorCode::InvalidBuyerRecord
+            );
+            (
+                LightAccount::<BuyerRecord>::new_mut(&crate::ID, &account_meta, current)?,
+                None,
+            )
+        }
+    };
+    record.tokens_bought = record
+        .tokens_bought
+        .checked_add(tokens)
+        .ok_or(ErrorCode::MathOverflow)?;
+    record.usdc_paid = record
+        .usdc_paid
+        .checked_add(usdc)
+        .ok_or(ErrorCode::MathOverflow)?;
+    Ok((record, new_address))
+}
 
-            let auth_seeds = &[b"authority".as_ref(), &[ctx.bumps.program_authority]];
-            let auth_signer = &[&auth_seeds[..]];
+// ==========================
+// Referrals
+// ==========================
+/// How the referrer's record enters a buy, as for BuyerRecordInput
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub enum ReferrerRecordInput {
+    New {
+        address_tree_info: PackedAddressTreeInfo,
+        output_state_tree_index: u8,
+    },
+    Existing {
+        current: ReferrerRecord,
+        account_meta: CompressedAccountMeta,
+    },
+}
 
-            // Platform fee to owner
-            token::transfer(
-                CpiContext::new_with_signer(
-                    ctx.accounts.token_program.to_account_info(),
-                    Transfer {
-                        from: ctx.accounts.program_usdc_account.to_account_info(),
-                        to: ctx.accounts.owner_usdc_account.to_account_info(),
-                        authority: ctx.accounts.program_authority.to_account_info(),
-                    },
-                    auth_signer,
-                ),
-                fee,
-            )?;
+/// Part of the platform fee `fee` paid to the referrer
+fn referral_share(fee: u64, referral_fee_bps: u16) -> Result<u64> {
+    Ok(fee
+        .checked_mul(referral_fee_bps as u64)
+        .ok_or(ErrorCode::MathOverflow)?
+        / 10_000)
+}
 
-            // Creator receives their share
-            token::transfer(
-                CpiContext::new_with_signer(
-                    ctx.accounts.token_program.to_account_info(),
-                    Transfer {
-                        from: ctx.accounts.program_usdc_account.to_account_info(),
-                        to: ctx.accounts.creator_usdc_account.to_account_info(),
-                        authority: ctx.accounts.program_authority.to_account_info(),
-                    },
-                    auth_signer,
-                ),
-                creator_share,
+/// Returns the referral fee of a buy paying `usdc` with `usdc_split`, plus the
+/// referrer's record with the purchase added and its new address params when
+/// the record is being created
+#[allow(clippy::type_complexity)]
+fn load_referral(
+    light_cpi_accounts: &CpiAccounts,
+    referrer: Option<Pubkey>,
+    quote_mint: &Pubkey,
+    input: Option<ReferrerRecordInput>,
+    usdc: u64,
+    usdc_split: Option<(u64, u64)>,
+    referral_fee_bps: u16,
+) -> Result<(
+    u64,
+    Option<(LightAccount<ReferrerRecord>, Option<PackedNewAddressParams>)>,
+)> {
+    let Some(referrer) = referrer else {
+        return Ok((0, None));
+    };
+    let referral_fee = match usdc_split {
+        Some((fee, _)) => referral_share(fee, referral_fee_bps)?,
+        None => 0,
+    };
+    let input = input.ok_or(ErrorCode::ReferrerRecordRequired)?;
+    let (mut record, new_address) = match input {
+        ReferrerRecordInput::New {
+            address_tree_info,
+            output_state_tree_index,
+        } => {
+            let (address, new_address) = new_record_address(
+                light_cpi_accounts,
+                address_tree_info,
+                &[b"referrer_record", referrer.as_ref(), quote_mint.as_ref()],
             )?;
-        } else {
-            // Free mint - no USDC payment
-            require!(usdc_amount == 0, ErrorCode::FreeMintRequiresZeroPayment);
-            require!(current_sale.limit_per_mint > 0, ErrorCode::LimitPerMintNotSet);
-            tokens_to_send = current_sale.limit_per_mint;
-
-            let new_total = current_sale
-                .tokens_sold
-                .checked_add(tokens_to_send)
-                .ok_or(ErrorCode::MathOverflow)?;
+            let mut record = LightAccount::<ReferrerRecord>::new_init(
+                &crate::ID,
+                Some(address),
+                output_state_tree_index,
+            );
+            record.referrer = referrer;
+            record.quote_mint = *quote_mint;
+            (record, Some(new_address))
+        }
+        ReferrerRecordInput::Existing {
+            current,
+            account_meta,
+        } => {
             require!(
-                new_total <= current_sale.supply_for_sale,
-                ErrorCode::InsufficientSupply
+                account_meta.address == ReferrerRecord::derive_address(&referrer, quote_mint),
+                ErrorCode::InvalidReferrerRecord
             );
-
-            updated_sale.tokens_sold = new_total;
-            if updated_sale.tokens_sold == updated_sale.supply_for_sale {
-                updated_sale.active = false;
-            }
+            (
+                LightAccount::<ReferrerRecord>::new_mut(&crate::ID, &account_meta, current)?,
+                None,
+            )
         }
+    };
+    record.purchases = record
+        .purchases
+        .checked_add(1)
+        .ok_or(ErrorCode::MathOverflow)?;
+    record.usdc_volume = record
+        .usdc_volume
+        .checked_add(usdc)
+        .ok_or(ErrorCode::MathOverflow)?;
+    record.fees_earned = record
+        .fees_earned
+        .checked_add(referral_fee)
+        .ok_or(ErrorCode::MathOverflow)?;
+    Ok((referral_fee, Some((record, new_address))))
+}
 
-        // Update compressed sale state via Light System Program
-        let mut light_account = LightAccount::<CompressedTokenSale>::new_mut(
-            &crate::ID,
-            &account_meta,
-            current_sale.clone(),
-        )?;
-        light_account.tokens_sold = updated_sale.tokens_sold;
-        light_account.active = updated_sale.active;
+/// The referrer's quote token account, which must belong to the referrer
+fn referrer_token_account<'a, 'info>(
+    referrer: Option<&SystemAccount<'info>>,
+    referrer_usdc_account: Option<&'a Account<'info, TokenAccount>>,
+) -> Result<Option<&'a Account<'info, TokenAccount>>> {
+    match (referrer, referrer_usdc_account) {
+        (None, _) => Ok(None),
+        (Some(referrer), Some(account)) => {
+            require!(
+                account.owner == referrer.key(),
+                ErrorCode::InvalidTokenAccountOwner
+            );
+            Ok(Some(account))
+        }
+        (Some(_), None) => err!(ErrorCode::PaymentAccountsRequired),
+    }
+}
 
-        LightSystemProgramCpi::new_cpi(LIGHT_CPI_SIGNER, proof)
-            .with_light_account(light_account)?
-            .invoke(light_cpi_accounts)?;
+// ==========================
+// Vesting
+// ==========================
+/// Linear release of purchased tokens. Nothing unlocks before `cliff_ts`;
+/// from then on a buyer's tokens unlock in proportion to the time elapsed
+/// from `start_ts`, all of them by `end_ts`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub struct Vesting {
+    pub start_ts: i64,
+    pub cliff_ts: i64,
+    pub end_ts: i64,
+}
 
-        // NOTE: Compressed token transfer is done CLIENT-SIDE after this instruction:
-        // const transferTx = await transfer(rpc, saleAuthorityKeypair, mint, tokens_to_send, saleAuthority, buyer.publicKey);
-        // The sale_authority PDA needs to sign this transfer.
-        // For this, the client needs to derive the PDA and use approveAndMintTo or similar pattern.
+impl Vesting {
+    fn is_valid(&self) -> bool {
+        self.start_ts <= self.cliff_ts
+            && self.cliff_ts <= self.end_ts
+            && self.start_ts < self.end_ts
+    }
 
-        emit!(TokenBoughtCompressed {
-            token_mint: ctx.accounts.token_mint.key(),
-            buyer: ctx.accounts.buyer.key(),
-            usdc_spent: usdc_amount,
-            tokens_received: tokens_to_send,
-            sale_authority: current_sale.sale_authority,
-            sale_authority_bump: current_sale.sale_authority_bump,
-        });
+    /// Tokens of `total` unlocked at `now`
+    pub fn unlocked(&self, total: u64, now: i64) -> u64 {
+        if now < self.cliff_ts {
+            0
+        } else if now >= self.end_ts {
+            total
+        } else {
+            let elapsed = (now - self.start_ts) as u128;
+            let duration = (self.end_ts - self.start_ts) as u128;
+            (total as u128 * elapsed / duration) as u64
+        }
+    }
+}
 
-        Ok(())
+/// Soft-cap refunds burn the buyer's tokens, which vesting sales do not hand out
+fn validate_vesting(vesting: Option<Vesting>, soft_cap: u64) -> Result<()> {
+    if let Some(vesting) = vesting {
+        require!(
+            vesting.is_valid() && soft_cap == 0,
+            ErrorCode::InvalidVesting
+        );
     }
+    Ok(())
+}
 
-    pub fn close_sale(ctx: Context<CloseSale>) -> Result<()> {
-        let sale = &mut ctx.accounts.token_sale;
-        require!(sale.active, ErrorCode::AlreadyClosed);
-        sale.active = false;
+/// Proceeds can only vest where the creator share would otherwise be paid out
+/// on purchase: fixed-price paid sales in a quote mint without a reserve or
+/// soft-cap escrow
+fn validate_proceeds_vesting(
+    proceeds_vesting: Option<Vesting>,
+    quote_currency: QuoteCurrency,
+    price_per_token: u64,
+    price_slope: u64,
+    auction: Option<DutchAuction>,
+    soft_cap: u64,
+) -> Result<()> {
+    if let Some(schedule) = proceeds_vesting {
+        require!(
+            schedule.is_valid()
+                && quote_currency == QuoteCurrency::Token
+                && price_per_token > 0
+                && price_slope == 0
+                && !uniform_clearing(auction)
+                && soft_cap == 0,
+            ErrorCode::InvalidProceedsVesting
+        );
+    }
+    Ok(())
+}
 
-        let remaining = ctx.accounts.sale_token_account.amount;
+/// Marks the tokens of `record` unlocked so far as claimed and returns how
+/// many that releases
+fn release_vested(vesting: &Vesting, record: &mut BuyerRecord) -> Result<u64> {
+    let now = Clock::get()?.unix_timestamp;
+    let amount = vesting
+        .unlocked(record.tokens_bought, now)
+        .checked_sub(record.tokens_claimed)
+        .ok_or(ErrorCode::MathOverflow)?;
+    require!(amount > 0, ErrorCode::NothingVested);
+    record.tokens_claimed += amount;
+    Ok(amount)
+}
 
-        if remaining > 0 {
-            let token_mint_key = ctx.accounts.token_mint.key();
-            let seeds = &[b"token_sale", token_mint_key.as_ref(), &[sale.bump]];
-            let signer = &[&seeds[..]];
+// ==========================
+// Settlement
+// ==========================
+/// Marks a closed sale's proceeds as claimed, takes them out of its reserve
+/// and returns (platform fee, creator amount)
+fn claim_proceeds(sale: &mut TokenSale) -> Result<(u64, u64)> {
+    require!(!sale.proceeds_claimed, ErrorCode::AlreadyClaimed);
+    let gross = if uniform_clearing(sale.auction) {
+        curve_proceeds(sale.clearing_price, 0, sale.decimals, 0, sale.tokens_sold, 0)?
+    } else if sale.soft_cap > 0 {
+        require!(
+            sale.usdc_raised >= sale.soft_cap,
+            ErrorCode::SoftCapNotMet
+        );
+        sale.usdc_reserve
+    } else {
+        // Curve reserves hold only the creator share; the fee was paid on purchase
+        sale.proceeds_claimed = true;
+        return Ok((0, std::mem::take(&mut sale.usdc_reserve)));
+    };
+    let fee = gross
+        .checked_mul(sale.platform_fee_bps as u64)
+        .ok_or(ErrorCode::MathOverflow)?
+        / 10_000;
+    sale.usdc_reserve = sale
+        .usdc_reserve
+        .checked_sub(gross)
+        .ok_or(ErrorCode::InsufficientReserve)?;
+    sale.proceeds_claimed = true;
+    Ok((fee, gross - fee))
+}
 
+/// Transfer each (account, amount) out of a token account owned by the
+/// authority PDA
+fn pay_out<'info>(
+    token_program: &Program<'info, Token>,
+    from: &Account<'info, TokenAccount>,
+    program_authority: &AccountInfo<'info>,
+    authority_bump: u8,
+    payouts: &[(&Account<'info, TokenAccount>, u64)],
+) -> Result<()> {
+    let auth_seeds = &[b"authority".as_ref(), &[authority_bump]];
+    for (to, amount) in payouts {
+        if *amount > 0 {
             token::transfer(
                 CpiContext::new_with_signer(
-                    ctx.accounts.token_program.to_account_info(),
+                    token_program.to_account_info(),
                     Transfer {
-                        from: ctx.accounts.sale_token_account.to_account_info(),
-                        to: ctx.accounts.creator_token_account.to_account_info(),
-                        authority: ctx.accounts.token_sale.to_account_info(),
+                        from: from.to_account_info(),
+                        to: to.to_account_info(),
+                        authority: program_authority.clone(),
                     },
-                    signer,
+                    &[&auth_seeds[..]],
                 ),
-                remaining,
+                *amount,
             )?;
         }
-
-        emit!(SaleClosed {
-            token_mint: ctx.accounts.token_mint.key(),
-            remaining_tokens_returned: remaining,
-        });
-
-        Ok(())
     }
+    Ok(())
+}
 
-    /// Close a compressed TokenSale and return remaining tokens
-    ///
-    /// This instruction:
-    /// 1. Verifies creator authorization
-    /// 2. Updates compressed sale state to inactive
-    ///
-    /// The compressed token transfer (returning remaining tokens to creator) is done CLIENT-SIDE:
-    /// const remainingTokens = supply_for_sale - tokens_sold;
-    /// const transferTx = await transfer(rpc, saleAuthorityKeypair, mint, remainingTokens, saleAuthority, creator.publicKey);
-    pub fn close_sale_compressed<'info>(
-        ctx: Context<'_, '_, '_, 'info, CloseSaleCompressed<'info>>,
-        proof: ValidityProof,
-        current_sale: CompressedTokenSale,
-        account_meta: CompressedAccountMeta,
-    ) -> Result<()> {
-        require!(current_sale.active, ErrorCode::AlreadyClosed);
+// ==========================
+// Sale Mint (SPL Token / Token-2022)
+// ==========================
+/// Rejects Token-2022 extensions that would lock tokens in the sale or let
+/// someone else move them: non-transferable mints, transfer hooks, accounts
+/// frozen by default and a permanent delegate other than the sale itself
+fn validate_sale_mint(mint: &AccountInfo, token_sale: &Pubkey) -> Result<()> {
+    let data = mint.try_borrow_data()?;
+    let mint = StateWithExtensions::<MintState>::unpack(&data)?;
+    require!(
+        mint.get_extension::<NonTransferable>().is_err(),
+        ErrorCode::UnsupportedMintExtension
+    );
+    if let Ok(extension) = mint.get_extension::<PermanentDelegate>() {
+        if let Some(delegate) = Option::<Pubkey>::from(extension.delegate) {
+            require!(
+                delegate == *token_sale,
+                ErrorCode::UnsupportedMintExtension
+            );
+        }
+    }
+    if let Ok(extension) = mint.get_extension::<TransferHook>() {
         require!(
-            current_sale.creator == ctx.accounts.creator.key(),
-            ErrorCode::Unauthorized
+            Option::<Pubkey>::from(extension.program_id).is_none(),
+            ErrorCode::UnsupportedMintExtension
         );
+    }
+    if let Ok(extension) = mint.get_extension::<DefaultAccountState>() {
         require!(
-            current_sale.token_mint == ctx.accounts.token_mint.key(),
-            ErrorCode::InvalidMint
+            extension.state != AccountState::Frozen as u8,
+            ErrorCode::UnsupportedMintExtension
         );
+    }
+    Ok(())
+}
 
-        let light_cpi_accounts = CpiAccounts::new(
-            ctx.accounts.creator.as_ref(),
-            ctx.remaining_accounts,
-            crate::LIGHT_CPI_SIGNER,
+/// Token-2022 transfer fee withheld when `amount` of `mint` is sent
+/// (0 for mints without one)
+fn transfer_fee(mint: &AccountInfo, amount: u64) -> Result<u64> {
+    let data = mint.try_borrow_data()?;
+    let mint = StateWithExtensions::<MintState>::unpack(&data)?;
+    let Ok(config) = mint.get_extension::<TransferFeeConfig>() else {
+        return Ok(0);
+    };
+    config
+        .calculate_epoch_fee(Clock::get()?.epoch, amount)
+        .ok_or_else(|| ErrorCode::MathOverflow.into())
+}
+
+/// Token-2022 transfer fee to send on top of `amount` so that `amount` arrives
+/// (0 for mints without one)
+fn inverse_transfer_fee(mint: &AccountInfo, amount: u64) -> Result<u64> {
+    let data = mint.try_borrow_data()?;
+    let mint = StateWithExtensions::<MintState>::unpack(&data)?;
+    let Ok(config) = mint.get_extension::<TransferFeeConfig>() else {
+        return Ok(0);
+    };
+    config
+        .calculate_inverse_epoch_fee(Clock::get()?.epoch, amount)
+        .ok_or_else(|| ErrorCode::MathOverflow.into())
+}
+
+// ==========================
+// Sale Window
+// ==========================
+fn validate_sale_window(start_ts: Option<i64>, end_ts: Option<i64>) -> Result<()> {
+    if let Some(end_ts) = end_ts {
+        require!(
+            end_ts > Clock::get()?.unix_timestamp,
+            ErrorCode::InvalidSaleWindow
         );
+        if let Some(start_ts) = start_ts {
+            require!(end_ts > start_ts, ErrorCode::InvalidSaleWindow);
+        }
+    }
+    Ok(())
+}
 
-        // Calculate remaining tokens (for the event)
-        let remaining_tokens = current_sale
-            .supply_for_sale
-            .checked_sub(current_sale.tokens_sold)
-            .ok_or(ErrorCode::MathOverflow)?;
+fn require_sale_open(start_ts: Option<i64>, end_ts: Option<i64>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    if let Some(start_ts) = start_ts {
+        require!(now >= start_ts, ErrorCode::SaleNotStarted);
+    }
+    if let Some(end_ts) = end_ts {
+        require!(now < end_ts, ErrorCode::SaleEnded);
+    }
+    Ok(())
+}
 
-        // Update compressed account to mark as closed
-        let mut light_account = LightAccount::<CompressedTokenSale>::new_mut(
-            &crate::ID,
-            &account_meta,
-            current_sale.clone(),
-        )?;
-        light_account.active = false;
+fn require_sale_ended(end_ts: Option<i64>) -> Result<()> {
+    let end_ts = end_ts.ok_or(ErrorCode::SaleNotEnded)?;
+    require!(
+        Clock::get()?.unix_timestamp >= end_ts,
+        ErrorCode::SaleNotEnded
+    );
+    Ok(())
+}
 
-        LightSystemProgramCpi::new_cpi(LIGHT_CPI_SIGNER, proof)
-            .with_light_account(light_account)?
-            .invoke(light_cpi_accounts)?;
+// ==========================
+// Compressed Token CPI
+// ==========================
+/// Transfer `amount` compressed tokens owned by `authority` to `recipient`
+/// via the Light Compressed Token Program, signing with `signer_seeds`.
+///
+/// `inputs` are the authority's compressed token accounts being spent; their
+/// merkle tree indices refer to the packed tree accounts in `light_cpi_accounts`.
+/// Change is returned to the authority in `output_tree_index`.
+fn transfer_compressed_tokens<'info>(
+    light_cpi_accounts: &CpiAccounts<'_, 'info>,
+    compressed_token_program: &AccountInfo<'info>,
+    compressed_token_cpi_authority: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+    mint: Pubkey,
+    proof: ValidityProof,
+    inputs: Vec<TokenAccountMeta>,
+    recipient: Pubkey,
+    amount: u64,
+    output_tree_index: u8,
+) -> Result<()> {
+    let mut sender = CTokenAccount::new(mint, authority.key(), inputs, output_tree_index);
+    let recipient_account = sender
+        .transfer(&recipient, amount, None)
+        .map_err(|_| ErrorCode::InsufficientSupply)?;
+
+    let fee_payer = light_cpi_accounts.fee_payer();
+    let tree_accounts = light_cpi_accounts
+        .tree_accounts()
+        .map_err(|_| ErrorCode::InvalidAddressTree)?;
+
+    let instruction = create_transfer_instruction_raw(
+        mint,
+        vec![recipient_account, sender],
+        proof,
+        TransferConfig {
+            filter_zero_amount_outputs: true,
+            ..Default::default()
+        },
+        TokenAccountsMetaConfig::new(fee_payer.key(), authority.key()),
+        tree_accounts.iter().map(|account| account.key()).collect(),
+    )
+    .map_err(|_| ErrorCode::CompressedTokenCpiFailed)?;
+
+    let mut account_infos = vec![
+        fee_payer.clone(),
+        authority.clone(),
+        compressed_token_cpi_authority.clone(),
+        compressed_token_program.clone(),
+    ];
+    for account in [
+        light_cpi_accounts.light_system_program(),
+        light_cpi_accounts.registered_program_pda(),
+        light_cpi_accounts.noop_program(),
+        light_cpi_accounts.account_compression_authority(),
+        light_cpi_accounts.account_compression_program(),
+        light_cpi_accounts.system_program(),
+    ] {
+        account_infos.push(
+            account
+                .map_err(|_| ErrorCode::CompressedTokenCpiFailed)?
+                .clone(),
+        );
+    }
+    account_infos.extend_from_slice(tree_accounts);
 
-        // NOTE: Compressed token transfer (returning remaining tokens) is done CLIENT-SIDE:
-        // const transferTx = await transfer(rpc, saleAuthorityKeypair, mint, remainingTokens, saleAuthority, creator.publicKey);
+    anchor_lang::solana_program::program::invoke_signed(
+        &instruction,
+        &account_infos,
+        signer_seeds,
+    )?;
 
-        emit!(SaleClosedCompressed {
-            token_mint: ctx.accounts.token_mint.key(),
-            remaining_tokens_returned: remaining_tokens,
-            sale_authority: current_sale.sale_authority,
-            sale_authority_bump: current_sale.sale_authority_bump,
-        });
+    Ok(())
+}
 
-        Ok(())
+/// Balance of `sale_token_accounts`, which must cover the unsold supply of a
+/// compressed sale so closing it cannot strand tokens at the sale authority.
+/// Tokens others sent to the sale authority may push the balance above it.
+fn unsold_compressed_supply(
+    sale: &CompressedTokenSale,
+    sale_token_accounts: &[TokenAccountMeta],
+) -> Result<u64> {
+    let unsold = sale
+        .supply_for_sale
+        .checked_sub(sale.tokens_sold)
+        .ok_or(ErrorCode::MathOverflow)?;
+    let supplied = sale_token_accounts
+        .iter()
+        .try_fold(0u64, |total, account| total.checked_add(account.amount))
+        .ok_or(ErrorCode::MathOverflow)?;
+    require!(supplied >= unsold, ErrorCode::UnsoldSupplyMismatch);
+    Ok(supplied)
+}
+
+/// Marks an active compressed `sale` closed and returns the balance of
+/// `sale_token_accounts`, held by the sale_authority, to the sale's creator.
+/// Shared by close_sale_compressed and finalize_sale_compressed.
+fn close_compressed_sale<'info>(
+    light_cpi_accounts: &CpiAccounts<'_, 'info>,
+    compressed_token_program: &AccountInfo<'info>,
+    compressed_token_cpi_authority: &AccountInfo<'info>,
+    sale_authority: &AccountInfo<'info>,
+    sale_authority_bump: u8,
+    mut sale: LightAccount<CompressedTokenSale>,
+    proof: ValidityProof,
+    token_proof: ValidityProof,
+    sale_token_accounts: Vec<TokenAccountMeta>,
+    token_output_tree_index: u8,
+) -> Result<()> {
+    let token_mint = sale.token_mint;
+    let creator = sale.creator;
+    let remaining_tokens = unsold_compressed_supply(&sale, &sale_token_accounts)?;
+    sale.active = false;
+
+    LightSystemProgramCpi::new_cpi(LIGHT_CPI_SIGNER, proof)
+        .with_light_account(sale)?
+        .invoke(light_cpi_accounts.clone())?;
+
+    if remaining_tokens > 0 {
+        let sale_authority_seeds = &[
+            b"sale_authority".as_ref(),
+            token_mint.as_ref(),
+            &[sale_authority_bump],
+        ];
+        transfer_compressed_tokens(
+            light_cpi_accounts,
+            compressed_token_program,
+            compressed_token_cpi_authority,
+            sale_authority,
+            &[&sale_authority_seeds[..]],
+            token_mint,
+            token_proof,
+            sale_token_accounts,
+            creator,
+            remaining_tokens,
+            token_output_tree_index,
+        )?;
     }
+
+    emit!(SaleClosedCompressed {
+        token_mint,
+        remaining_tokens_returned: remaining_tokens,
+        sale_authority: sale_authority.key(),
+        sale_authority_bump,
+    });
+
+    Ok(())
+}
+
+/// Mint `amount` compressed tokens of `mint` to `recipient` via the Light
+/// Compressed Token Program, signing as mint `authority` with `signer_seeds`.
+///
+/// The SPL backing is minted into the mint's token pool and the compressed
+/// token account is appended to the state tree at `output_tree_index`.
+fn mint_compressed_tokens<'info>(
+    light_cpi_accounts: &CpiAccounts<'_, 'info>,
+    compressed_token_program: &AccountInfo<'info>,
+    compressed_token_cpi_authority: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+    mint: &AccountInfo<'info>,
+    token_pool: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    recipient: Pubkey,
+    amount: u64,
+    output_tree_index: u8,
+) -> Result<()> {
+    let cpi_error = |_| ErrorCode::CompressedTokenCpiFailed;
+    let fee_payer = light_cpi_accounts.fee_payer();
+    let merkle_tree = light_cpi_accounts
+        .get_tree_account_info(output_tree_index as usize)
+        .map_err(|_| ErrorCode::InvalidAddressTree)?;
+    let light_system_program = light_cpi_accounts.light_system_program().map_err(cpi_error)?;
+    let registered_program_pda = light_cpi_accounts.registered_program_pda().map_err(cpi_error)?;
+    let noop_program = light_cpi_accounts.noop_program().map_err(cpi_error)?;
+    let account_compression_authority = light_cpi_accounts
+        .account_compression_authority()
+        .map_err(cpi_error)?;
+    let account_compression_program = light_cpi_accounts
+        .account_compression_program()
+        .map_err(cpi_error)?;
+    let system_program = light_cpi_accounts.system_program().map_err(cpi_error)?;
+
+    let params = MintToParams {
+        public_keys: vec![recipient.to_bytes()],
+        amounts: vec![amount],
+        lamports: None,
+    };
+    let mut data = MINT_TO.to_vec();
+    params
+        .serialize(&mut data)
+        .map_err(|_| ErrorCode::CompressedTokenCpiFailed)?;
+
+    // Account order follows the Compressed Token Program's MintToInstruction;
+    // the unused sol_pool_pda slot is filled with the program id.
+    let instruction = anchor_lang::solana_program::instruction::Instruction {
+        program_id: COMPRESSED_TOKEN_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(fee_payer.key(), true),
+            AccountMeta::new_readonly(authority.key(), true),
+            AccountMeta::new_readonly(compressed_token_cpi_authority.key(), false),
+            AccountMeta::new(mint.key(), false),
+            AccountMeta::new(token_pool.key(), false),
+            AccountMeta::new_readonly(token_program.key(), false),
+            AccountMeta::new_readonly(light_system_program.key(), false),
+            AccountMeta::new_readonly(registered_program_pda.key(), false),
+            AccountMeta::new_readonly(noop_program.key(), false),
+            AccountMeta::new_readonly(account_compression_authority.key(), false),
+            AccountMeta::new_readonly(account_compression_program.key(), false),
+            AccountMeta::new(merkle_tree.key(), false),
+            AccountMeta::new_readonly(COMPRESSED_TOKEN_PROGRAM_ID, false),
+            AccountMeta::new_readonly(system_program.key(), false),
+            AccountMeta::new_readonly(COMPRESSED_TOKEN_PROGRAM_ID, false),
+        ],
+        data,
+    };
+
+    anchor_lang::solana_program::program::invoke_signed(
+        &instruction,
+        &[
+            fee_payer.clone(),
+            authority.clone(),
+            compressed_token_cpi_authority.clone(),
+            mint.clone(),
+            token_pool.clone(),
+            token_program.clone(),
+            light_system_program.clone(),
+            registered_program_pda.clone(),
+            noop_program.clone(),
+            account_compression_authority.clone(),
+            account_compression_program.clone(),
+            merkle_tree.clone(),
+            compressed_token_program.clone(),
+            system_program.clone(),
+        ],
+        signer_seeds,
+    )?;
+
+    Ok(())
 }
 
 // ==========================
@@ -915,8 +4080,18 @@ pub mod gasless_launchpad {
 #[derive(InitSpace)]
 pub struct AppState {
     pub owner: Pubkey,
+    /// Mint registered as a quote mint at initialize; sales may be priced in
+    /// any mint registered with add_quote_mint
     pub usdc_mint: Pubkey,
     pub platform_fee_bps: u16,
+    /// Owner nominated by propose_owner, awaiting accept_owner
+    pub pending_owner: Option<Pubkey>,
+    /// Wallet whose USDC account receives platform fees (defaults to the owner)
+    pub fee_recipient: Pubkey,
+    /// Blocks launches and buys while set
+    pub paused: bool,
+    /// Share of the platform fee paid to a buy's referrer, in bps of the fee
+    pub referral_fee_bps: u16,
 }
 
 #[account]
@@ -925,6 +4100,7 @@ pub struct TokenSale {
     pub creator: Pubkey,
     pub token_mint: Pubkey,
     pub price_per_token: u64,
+    /// Shrinks by any Token-2022 transfer fees paid on delivery
     pub supply_for_sale: u64,
     pub tokens_sold: u64,
     pub active: bool,
@@ -933,6 +4109,53 @@ pub struct TokenSale {
     pub limit_per_mint: u64,
     pub decimals: u8,
     pub bump: u8,
+    pub start_ts: Option<i64>,
+    pub end_ts: Option<i64>,
+    pub merkle_root: Option<[u8; 32]>,
+    pub max_per_wallet: u64,
+    pub price_slope: u64,
+    /// USDC held by the authority PDA for curve sell-backs or auction refunds
+    pub usdc_reserve: u64,
+    pub auction: Option<DutchAuction>,
+    /// Last price paid in a Dutch auction
+    pub clearing_price: u64,
+    /// Set once the sale's proceeds have been paid out
+    pub proceeds_claimed: bool,
+    /// USDC that must be raised by end_ts, or buyers can claim_refund (0 = none)
+    pub soft_cap: u64,
+    /// Gross USDC paid by buyers
+    pub usdc_raised: u64,
+    /// Sale closes once usdc_raised reaches this (0 = no cap)
+    pub hard_cap_usdc: u64,
+    /// Currency of price_per_token, the caps and usdc_raised
+    pub quote_currency: QuoteCurrency,
+    /// Registered SPL mint paid in (default for SOL sales)
+    pub quote_mint: Pubkey,
+    /// Linear release of purchases through claim_vested
+    pub vesting: Option<Vesting>,
+    /// Release of the escrowed creator share through withdraw_proceeds
+    pub proceeds_vesting: Option<Vesting>,
+    /// Creator share escrowed by the authority PDA under proceeds_vesting
+    pub proceeds_escrow: u64,
+    /// Part of proceeds_escrow already withdrawn by the creator
+    pub proceeds_withdrawn: u64,
+    /// Platform fee at launch, charged on buys and on every later payout
+    pub platform_fee_bps: u16,
+    /// Decimals of the quote currency, which price_per_token, the caps and the
+    /// USDC amounts are in base units of
+    pub quote_decimals: u8,
+}
+
+/// Registry entry for a mint sales may be priced and paid in, at
+/// [b"quote_mint", mint]
+#[account]
+#[derive(InitSpace)]
+pub struct QuoteMintEntry {
+    pub mint: Pubkey,
+    pub decimals: u8,
+    /// Lowest price_per_token and auction floor, in `mint` base units
+    pub min_price: u64,
+    pub bump: u8,
 }
 
 // ==========================
@@ -942,9 +4165,9 @@ pub struct TokenSale {
 /// No rent required - only pays for compression proof (~0.00001 SOL vs ~0.002 SOL)
 ///
 /// This state tracks the sale configuration. The actual compressed tokens are:
-/// - Minted via @lightprotocol/compressed-token SDK (client-side)
+/// - Minted on-chain to the sale_authority PDA by launch_token_compressed
 /// - Held by the sale_authority PDA
-/// - Transferred to buyers via compressed token transfers (client-side)
+/// - Transferred to buyers on-chain by buy_tokens_compressed (signed by sale_authority)
 #[event]
 #[derive(Clone, Debug, Default, LightDiscriminator)]
 pub struct CompressedTokenSale {
@@ -968,6 +4191,101 @@ pub struct CompressedTokenSale {
     pub sale_authority: Pubkey,
     /// Bump seed for the sale_authority PDA
     pub sale_authority_bump: u8,
+    /// Unix timestamp before which buys are rejected
+    pub start_ts: Option<i64>,
+    /// Unix timestamp from which buys are rejected and anyone can finalize
+    pub end_ts: Option<i64>,
+    /// Allowlist root; when set, buyers must prove a (buyer, allocation) leaf
+    pub merkle_root: Option<[u8; 32]>,
+    /// Lifetime purchase cap per wallet (0 = no cap)
+    pub max_per_wallet: u64,
+    /// Price increase per whole token sold (0 = fixed price)
+    pub price_slope: u64,
+    /// Dutch auction decaying the price to a floor (never uniform clearing)
+    pub auction: Option<DutchAuction>,
+    /// Sale closes once usdc_raised reaches this (0 = no cap)
+    pub hard_cap_usdc: u64,
+    /// Gross USDC paid by buyers
+    pub usdc_raised: u64,
+    /// Currency of price_per_token, hard_cap_usdc and usdc_raised
+    pub quote_currency: QuoteCurrency,
+    /// Registered SPL mint paid in (default for SOL sales)
+    pub quote_mint: Pubkey,
+    /// Linear release of purchases through claim_vested_compressed
+    pub vesting: Option<Vesting>,
+    /// Decimals of the quote currency, which price_per_token, the cap and
+    /// usdc_raised are in base units of
+    pub quote_decimals: u8,
+    /// Platform fee at launch, charged on every buy
+    pub platform_fee_bps: u16,
+}
+
+impl CompressedTokenSale {
+    /// Address of the compressed TokenSale for `token_mint` in COMPRESSED_SALE_ADDRESS_TREE
+    pub fn derive_address(token_mint: &Pubkey) -> [u8; 32] {
+        derive_address(
+            &[b"compressed_token_sale", token_mint.as_ref()],
+            &COMPRESSED_SALE_ADDRESS_TREE,
+            &crate::ID,
+        )
+        .0
+    }
+}
+
+/// Compressed per-(sale, buyer) record of lifetime purchases
+#[event]
+#[derive(Clone, Debug, Default, LightDiscriminator)]
+pub struct BuyerRecord {
+    /// Token mint of the sale
+    pub token_mint: Pubkey,
+    /// Wallet the record belongs to
+    pub buyer: Pubkey,
+    /// Tokens bought by `buyer` across all purchases
+    pub tokens_bought: u64,
+    /// USDC paid for those tokens, net of any auction refund
+    pub usdc_paid: u64,
+    /// Vested tokens already released to `buyer`
+    pub tokens_claimed: u64,
+}
+
+impl BuyerRecord {
+    /// Address of the record for (`token_mint`, `buyer`) in COMPRESSED_SALE_ADDRESS_TREE
+    pub fn derive_address(token_mint: &Pubkey, buyer: &Pubkey) -> [u8; 32] {
+        derive_address(
+            &[b"buyer_record", token_mint.as_ref(), buyer.as_ref()],
+            &COMPRESSED_SALE_ADDRESS_TREE,
+            &crate::ID,
+        )
+        .0
+    }
+}
+
+/// Compressed per-(referrer, quote mint) record of referred purchases
+#[event]
+#[derive(Clone, Debug, Default, LightDiscriminator)]
+pub struct ReferrerRecord {
+    /// Wallet the record belongs to
+    pub referrer: Pubkey,
+    /// Quote mint of the referred sales (default for SOL sales)
+    pub quote_mint: Pubkey,
+    /// Purchases made with `referrer`
+    pub purchases: u64,
+    /// Amount those purchases paid, in `quote_mint` base units
+    pub usdc_volume: u64,
+    /// Referral fees paid to `referrer`
+    pub fees_earned: u64,
+}
+
+impl ReferrerRecord {
+    /// Address of the record for (`referrer`, `quote_mint`) in COMPRESSED_SALE_ADDRESS_TREE
+    pub fn derive_address(referrer: &Pubkey, quote_mint: &Pubkey) -> [u8; 32] {
+        derive_address(
+            &[b"referrer_record", referrer.as_ref(), quote_mint.as_ref()],
+            &COMPRESSED_SALE_ADDRESS_TREE,
+            &crate::ID,
+        )
+        .0
+    }
 }
 
 // ==========================
@@ -983,6 +4301,20 @@ pub struct TokenLaunched {
     pub supply: u64,
     pub limit_per_mint: u64,
     pub metadata_id: String,
+    pub start_ts: Option<i64>,
+    pub end_ts: Option<i64>,
+    pub merkle_root: Option<[u8; 32]>,
+    pub max_per_wallet: u64,
+    pub price_slope: u64,
+    pub auction: Option<DutchAuction>,
+    pub soft_cap: u64,
+    pub hard_cap_usdc: u64,
+    pub quote_currency: QuoteCurrency,
+    pub quote_mint: Pubkey,
+    /// Decimals of price, caps and usdc amounts
+    pub quote_decimals: u8,
+    pub vesting: Option<Vesting>,
+    pub proceeds_vesting: Option<Vesting>,
 }
 
 #[event]
@@ -990,7 +4322,7 @@ pub struct TokenLaunchedCompressed {
     pub token_mint: Pubkey,
     pub creator: Pubkey,
     pub compressed_address: [u8; 32],
-    /// Sale authority PDA - client should mint compressed tokens to this address
+    /// Sale authority PDA holding the minted compressed supply
     pub sale_authority: Pubkey,
     pub symbol: String,
     pub name: String,
@@ -998,27 +4330,98 @@ pub struct TokenLaunchedCompressed {
     pub supply: u64,
     pub limit_per_mint: u64,
     pub metadata_id: String,
+    pub start_ts: Option<i64>,
+    pub end_ts: Option<i64>,
+    pub merkle_root: Option<[u8; 32]>,
+    pub max_per_wallet: u64,
+    pub price_slope: u64,
+    pub auction: Option<DutchAuction>,
+    pub hard_cap_usdc: u64,
+    pub quote_currency: QuoteCurrency,
+    pub quote_mint: Pubkey,
+    /// Decimals of price, caps and usdc amounts
+    pub quote_decimals: u8,
+    pub vesting: Option<Vesting>,
 }
 
 #[event]
 pub struct TokenBought {
     pub token_mint: Pubkey,
     pub buyer: Pubkey,
+    /// Amount paid, in `quote_currency` base units
     pub usdc_spent: u64,
     pub tokens_received: u64,
+    pub quote_currency: QuoteCurrency,
+    /// Referrer credited with the purchase, if any
+    pub referrer: Option<Pubkey>,
+    /// Part of the platform fee paid to `referrer`
+    pub referral_fee: u64,
+}
+
+#[event]
+pub struct TokenSold {
+    pub token_mint: Pubkey,
+    pub seller: Pubkey,
+    pub tokens_returned: u64,
+    pub usdc_received: u64,
+}
+
+#[event]
+pub struct ReserveClaimed {
+    pub token_mint: Pubkey,
+    pub creator: Pubkey,
+    pub amount: u64,
+    /// Platform fee taken at settlement (uniform-clearing auctions)
+    pub fee: u64,
+}
+
+#[event]
+pub struct ProceedsWithdrawn {
+    pub token_mint: Pubkey,
+    pub creator: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct SaleRefunded {
+    pub token_mint: Pubkey,
+    pub buyer: Pubkey,
+    pub tokens_burned: u64,
+    pub refund: u64,
+}
+
+#[event]
+pub struct AuctionRefunded {
+    pub token_mint: Pubkey,
+    pub buyer: Pubkey,
+    pub clearing_price: u64,
+    pub refund: u64,
+}
+
+#[event]
+pub struct VestedClaimed {
+    pub token_mint: Pubkey,
+    pub buyer: Pubkey,
+    pub amount: u64,
 }
 
 #[event]
 pub struct TokenBoughtCompressed {
     pub token_mint: Pubkey,
     pub buyer: Pubkey,
+    /// Amount paid, in `quote_currency` base units
     pub usdc_spent: u64,
-    /// Number of compressed tokens to transfer to buyer (client-side)
+    /// Number of compressed tokens transferred to buyer
     pub tokens_received: u64,
     /// Sale authority PDA holding the compressed tokens
     pub sale_authority: Pubkey,
     /// Bump for the sale authority PDA
     pub sale_authority_bump: u8,
+    pub quote_currency: QuoteCurrency,
+    /// Referrer credited with the purchase, if any
+    pub referrer: Option<Pubkey>,
+    /// Part of the platform fee paid to `referrer`
+    pub referral_fee: u64,
 }
 
 #[event]
@@ -1030,7 +4433,7 @@ pub struct SaleClosed {
 #[event]
 pub struct SaleClosedCompressed {
     pub token_mint: Pubkey,
-    /// Number of remaining compressed tokens to return to creator (client-side)
+    /// Number of remaining compressed tokens returned to creator
     pub remaining_tokens_returned: u64,
     /// Sale authority PDA holding the remaining compressed tokens
     pub sale_authority: Pubkey,
@@ -1038,6 +4441,28 @@ pub struct SaleClosedCompressed {
     pub sale_authority_bump: u8,
 }
 
+#[event]
+pub struct OwnershipProposed {
+    pub owner: Pubkey,
+    pub pending_owner: Pubkey,
+}
+
+#[event]
+pub struct OwnershipTransferred {
+    pub previous_owner: Pubkey,
+    pub new_owner: Pubkey,
+}
+
+#[event]
+pub struct LaunchpadPaused {
+    pub owner: Pubkey,
+}
+
+#[event]
+pub struct LaunchpadUnpaused {
+    pub owner: Pubkey,
+}
+
 // ==========================
 // Errors
 // ==========================
@@ -1053,7 +4478,7 @@ pub enum ErrorCode {
     SupplyTooLarge,
     #[msg("Free mints require a positive limit_per_mint")]
     FreeMintRequiresLimit,
-    #[msg("Price must be at least 1000 (0.001 USDC)")]
+    #[msg("Price is below the quote currency's minimum")]
     PriceTooLow,
     #[msg("Limit per mint cannot exceed total supply")]
     LimitExceedsSupply,
@@ -1087,4 +4512,251 @@ pub enum ErrorCode {
     InvalidAddressTree,
     #[msg("Unauthorized")]
     Unauthorized,
+    #[msg("Compressed token CPI failed")]
+    CompressedTokenCpiFailed,
+    #[msg("Compressed sale address does not match the token mint")]
+    InvalidSaleAddress,
+    #[msg("Launchpad is paused")]
+    ProgramPaused,
+    #[msg("Sale has not started yet")]
+    SaleNotStarted,
+    #[msg("Sale has ended")]
+    SaleEnded,
+    #[msg("Sale end must be in the future and after its start")]
+    InvalidSaleWindow,
+    #[msg("Sale has no end time or it has not passed yet")]
+    SaleNotEnded,
+    #[msg("Allowlist proof is missing or invalid")]
+    InvalidAllowlistProof,
+    #[msg("Purchase exceeds allowlist allocation")]
+    ExceedsAllocation,
+    #[msg("Purchase exceeds the per-wallet limit")]
+    ExceedsWalletLimit,
+    #[msg("Per-wallet limit must be at least the per-mint limit")]
+    InvalidWalletLimit,
+    #[msg("Sale tracks wallet purchases and needs a buyer record")]
+    BuyerRecordRequired,
+    #[msg("Buyer record does not belong to this sale and buyer")]
+    InvalidBuyerRecord,
+    #[msg("Bonding curve sales need a non-zero start price")]
+    InvalidPriceSlope,
+    #[msg("Trade output is below the requested minimum")]
+    SlippageExceeded,
+    #[msg("Only bonding curve sales can be sold back")]
+    SellNotSupported,
+    #[msg("Cannot sell back more tokens than the sale has sold")]
+    ExceedsTokensSold,
+    #[msg("Sale reserve cannot cover this sale")]
+    InsufficientReserve,
+    #[msg("Sale must be closed first")]
+    SaleStillActive,
+    #[msg("Dutch auctions need start and end times, no slope, and a floor between the minimum price and the start price")]
+    InvalidAuction,
+    #[msg("Sale proceeds already claimed")]
+    AlreadyClaimed,
+    #[msg("Sale is not a uniform-clearing auction")]
+    NoAuctionRefund,
+    #[msg("No refund is due")]
+    NoRefundDue,
+    #[msg("Soft caps need a paid sale with an end time, no slope and no uniform clearing")]
+    InvalidSoftCap,
+    #[msg("Sale did not reach its soft cap")]
+    SoftCapNotMet,
+    #[msg("Sale reached its soft cap")]
+    SoftCapMet,
+    #[msg("Sale has no soft cap")]
+    NoSoftCap,
+    #[msg("Hard caps need a paid sale and cannot be below the soft cap")]
+    InvalidHardCap,
+    #[msg("Purchase would cost more than max_usdc")]
+    MaxUsdcExceeded,
+    #[msg("Purchase would raise more than the sale's hard cap")]
+    ExceedsHardCap,
+    #[msg("Sale mint has a Token-2022 extension the launchpad cannot sell")]
+    UnsupportedMintExtension,
+    #[msg("SOL sales cannot use bonding curves, uniform clearing or soft caps")]
+    InvalidQuoteCurrency,
+    #[msg("Payment accounts for the sale's quote currency are missing")]
+    PaymentAccountsRequired,
+    #[msg("Payment account does not belong to the sale creator, fee recipient or referrer")]
+    InvalidPaymentAccount,
+    #[msg("Quote mint is not in the registry")]
+    QuoteMintNotRegistered,
+    #[msg("Minimum price must be positive")]
+    InvalidMinPrice,
+    #[msg("Vesting needs start_ts <= cliff_ts <= end_ts, start_ts < end_ts and no soft cap")]
+    InvalidVesting,
+    #[msg("Vesting sales need the vesting vault")]
+    VestingVaultRequired,
+    #[msg("Sale has no vesting schedule")]
+    NoVesting,
+    #[msg("Nothing has vested since the last claim")]
+    NothingVested,
+    #[msg("Proceeds vesting needs a valid schedule on a fixed-price token sale without soft cap or uniform clearing")]
+    InvalidProceedsVesting,
+    #[msg("Buyers cannot refer themselves")]
+    SelfReferral,
+    #[msg("Referred buys need the referrer's record")]
+    ReferrerRecordRequired,
+    #[msg("Referrer record does not belong to this referrer and quote mint")]
+    InvalidReferrerRecord,
+    #[msg("Refundable sales do not pay referral fees")]
+    ReferralUnavailable,
+    #[msg("Referral fee must be <= 10000 basis points of the platform fee")]
+    InvalidReferralFee,
+    #[msg("Sale token accounts must hold at least the unsold supply")]
+    UnsoldSupplyMismatch,
+    #[msg("A curve sale cannot be closed while buyers hold its tokens")]
+    CurveHasHolders,
+    #[msg("Quote-mint sales require their escrow account")]
+    SaleEscrowRequired,
+    #[msg("SOL payment would leave its recipient below the rent-exempt minimum")]
+    RecipientNotRentExempt,
+    #[msg("Bonding curve sales need an end time")]
+    CurveRequiresEndTime,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DECIMALS: u8 = 9;
+    const PLATFORM_FEE_BPS: u16 = 500;
+    const PRICE: u64 = 1_000_000;
+    /// 0.001 USDC more per whole token sold
+    const SLOPE: u64 = 1_000;
+    const WHOLE: u64 = 1_000_000_000;
+
+    #[test]
+    fn test_curve_cost_is_area_under_price_line() {
+        // 10 tokens from 1 USDC rising to 1.01 USDC average 1.005 USDC
+        assert_eq!(
+            curve_cost(PRICE, SLOPE, DECIMALS, 0, 10 * WHOLE).unwrap(),
+            10_050_000
+        );
+        // The next 10 start at 1.01 USDC
+        assert_eq!(
+            curve_cost(PRICE, SLOPE, DECIMALS, 10 * WHOLE, 10 * WHOLE).unwrap(),
+            10_150_000
+        );
+        // Fractions of a base unit round up
+        assert_eq!(curve_cost(PRICE, SLOPE, DECIMALS, 0, 1).unwrap(), 1);
+        assert_eq!(curve_cost(PRICE, SLOPE, DECIMALS, 0, 0).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_quote_buys_the_most_tokens_within_budget() {
+        assert_eq!(
+            quote_purchase(PRICE, SLOPE, DECIMALS, 0, 10_050_000).unwrap(),
+            (10 * WHOLE, 10_050_000)
+        );
+
+        for tokens_sold in [0, 7 * WHOLE + 3, 500_000 * WHOLE] {
+            for usdc_amount in [1, 999, 1_000_000, 12_345_678, 1_000_000_000_000] {
+                let (tokens, cost) =
+                    quote_purchase(PRICE, SLOPE, DECIMALS, tokens_sold, usdc_amount).unwrap();
+                assert!(cost <= usdc_amount);
+                assert_eq!(
+                    cost,
+                    curve_cost(PRICE, SLOPE, DECIMALS, tokens_sold, tokens).unwrap()
+                );
+                assert!(
+                    curve_cost(PRICE, SLOPE, DECIMALS, tokens_sold, tokens + 1).unwrap()
+                        > usdc_amount
+                );
+            }
+        }
+
+        // Without a slope the fixed price is charged for the tokens bought
+        assert_eq!(
+            quote_purchase(PRICE, 0, DECIMALS, 0, 1_500_000).unwrap(),
+            (1_500_000_000, 1_500_000)
+        );
+        // USDC that buys no whole base unit is not charged
+        assert_eq!(
+            quote_purchase(PRICE, 0, 0, 0, 2_500_000).unwrap(),
+            (2, 2_000_000)
+        );
+        assert_eq!(
+            quote_purchase(3_000_000, 0, DECIMALS, 0, 1_000_000).unwrap(),
+            (333_333_333, 1_000_000)
+        );
+    }
+
+    #[test]
+    fn test_proceeds_are_area_less_fee() {
+        assert_eq!(
+            curve_proceeds(PRICE, SLOPE, DECIMALS, 0, 10 * WHOLE, 0).unwrap(),
+            10_050_000
+        );
+        assert_eq!(
+            curve_proceeds(PRICE, SLOPE, DECIMALS, 0, 10 * WHOLE, 100).unwrap(),
+            9_949_500
+        );
+        // Fractions of a base unit round down
+        assert_eq!(curve_proceeds(PRICE, SLOPE, DECIMALS, 0, 1, 0).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_reserve_covers_selling_everything_back() {
+        let fee_bps = PLATFORM_FEE_BPS;
+        let mut tokens_sold = 0;
+        let mut reserve = 0;
+        for usdc_amount in [1, 999, 1_000_000, 12_345_678, 3_333_333] {
+            let (tokens, cost) =
+                quote_purchase(PRICE, SLOPE, DECIMALS, tokens_sold, usdc_amount).unwrap();
+            assert_eq!(
+                cost,
+                curve_cost(PRICE, SLOPE, DECIMALS, tokens_sold, tokens).unwrap()
+            );
+            reserve += cost - cost * fee_bps as u64 / 10_000;
+            tokens_sold += tokens;
+        }
+
+        // Sell back in uneven slices from the top of the curve
+        while tokens_sold > 0 {
+            let tokens = tokens_sold.min(2 * WHOLE + 7);
+            let proceeds = curve_proceeds(
+                PRICE,
+                SLOPE,
+                DECIMALS,
+                tokens_sold - tokens,
+                tokens,
+                fee_bps,
+            )
+            .unwrap();
+            assert!(proceeds <= reserve);
+            reserve -= proceeds;
+            tokens_sold -= tokens;
+        }
+    }
+
+    #[test]
+    fn test_auction_price_decays_linearly() {
+        let price = |now| auction_price(2_000_000, 1_000_000, 100, 200, now);
+        assert_eq!(price(0), 2_000_000);
+        assert_eq!(price(100), 2_000_000);
+        assert_eq!(price(150), 1_500_000);
+        assert_eq!(price(199), 1_010_000);
+        assert_eq!(price(200), 1_000_000);
+        assert_eq!(price(1_000), 1_000_000);
+        // Partial decay rounds the price up
+        assert_eq!(auction_price(1_000_001, 1_000_000, 0, 3, 1), 1_000_001);
+    }
+
+    #[test]
+    fn test_hard_cap_is_reached_when_a_unit_no_longer_fits() {
+        assert!(!hard_cap_reached(0, 5_000_000, 1_000_000));
+        assert!(!hard_cap_reached(5_000_000, 4_000_000, 1_000_000));
+        assert!(hard_cap_reached(5_000_000, 4_500_000, 1_000_000));
+        assert!(hard_cap_reached(5_000_000, 5_000_000, 0));
+        assert!(!hard_cap_reached(5_000_000, 4_999_999, 1));
+    }
+
+    #[test]
+    fn test_quote_overflow_is_an_error() {
+        // Ten decimals put unit^2 * usdc_amount and a * tokens_sold past u128
+        assert!(quote_purchase(PRICE, u64::MAX, 10, u64::MAX, u64::MAX).is_err());
+        assert!(quote_purchase(PRICE, SLOPE, 10, 0, u64::MAX).is_err());
+    }
 }
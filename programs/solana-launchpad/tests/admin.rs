@@ -0,0 +1,194 @@
+#![cfg(feature = "test-sbf")]
+
+mod common;
+
+use common::*;
+use light_program_test::{utils::assert::assert_rpc_error, Rpc};
+use solana_launchpad::ErrorCode;
+use solana_sdk::signature::Signer;
+
+#[tokio::test]
+async fn test_initialize() {
+    let mut env = setup().await;
+
+    let state = get_app_state(&mut env.rpc).await;
+    assert_eq!(state.owner, env.owner.pubkey());
+    assert_eq!(state.usdc_mint, env.usdc_mint);
+    assert_eq!(state.platform_fee_bps, PLATFORM_FEE_BPS);
+    assert_eq!(state.fee_recipient, env.owner.pubkey());
+    assert_eq!(state.pending_owner, None);
+}
+
+#[tokio::test]
+async fn test_initialize_twice_fails() {
+    let mut env = setup().await;
+    let owner = env.owner.insecure_clone();
+
+    let result = initialize(&mut env.rpc, &owner, env.usdc_mint, 100).await;
+    assert!(result.is_err(), "app_state was initialized twice");
+
+    let state = get_app_state(&mut env.rpc).await;
+    assert_eq!(state.platform_fee_bps, PLATFORM_FEE_BPS);
+}
+
+#[tokio::test]
+async fn test_initialize_rejects_fee_above_max() {
+    let mut rpc = new_rpc().await;
+    let owner = rpc.get_payer().insecure_clone();
+    let usdc_mint = create_mint(&mut rpc, &owner, &owner.pubkey(), USDC_DECIMALS).await;
+
+    let result = initialize(&mut rpc, &owner, usdc_mint, 1_001).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::InvalidFee)).unwrap();
+
+    initialize(&mut rpc, &owner, usdc_mint, 1_000)
+        .await
+        .unwrap();
+    assert_eq!(get_app_state(&mut rpc).await.platform_fee_bps, 1_000);
+}
+
+#[tokio::test]
+async fn test_update_fee() {
+    let mut env = setup().await;
+    let owner = env.owner.insecure_clone();
+
+    update_fee(&mut env.rpc, &owner, 250).await.unwrap();
+    assert_eq!(get_app_state(&mut env.rpc).await.platform_fee_bps, 250);
+
+    update_fee(&mut env.rpc, &owner, 0).await.unwrap();
+    assert_eq!(get_app_state(&mut env.rpc).await.platform_fee_bps, 0);
+}
+
+#[tokio::test]
+async fn test_update_fee_rejects_fee_above_max() {
+    let mut env = setup().await;
+    let owner = env.owner.insecure_clone();
+
+    let result = update_fee(&mut env.rpc, &owner, 1_001).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::InvalidFee)).unwrap();
+    assert_eq!(
+        get_app_state(&mut env.rpc).await.platform_fee_bps,
+        PLATFORM_FEE_BPS
+    );
+}
+
+#[tokio::test]
+async fn test_update_fee_rejects_non_owner() {
+    let mut env = setup().await;
+    let attacker = funded_keypair(&mut env.rpc).await;
+
+    let result = update_fee(&mut env.rpc, &attacker, 0).await;
+    assert_rpc_error(
+        result,
+        0,
+        anchor_lang::error::ErrorCode::ConstraintHasOne.into(),
+    )
+    .unwrap();
+    assert_eq!(
+        get_app_state(&mut env.rpc).await.platform_fee_bps,
+        PLATFORM_FEE_BPS
+    );
+}
+
+#[tokio::test]
+async fn test_ownership_transfer() {
+    let mut env = setup().await;
+    let owner = env.owner.insecure_clone();
+    let new_owner = funded_keypair(&mut env.rpc).await;
+
+    propose_owner(&mut env.rpc, &owner, new_owner.pubkey())
+        .await
+        .unwrap();
+    let state = get_app_state(&mut env.rpc).await;
+    assert_eq!(state.owner, owner.pubkey());
+    assert_eq!(state.pending_owner, Some(new_owner.pubkey()));
+
+    accept_owner(&mut env.rpc, &new_owner).await.unwrap();
+    let state = get_app_state(&mut env.rpc).await;
+    assert_eq!(state.owner, new_owner.pubkey());
+    assert_eq!(state.pending_owner, None);
+
+    // Fee control moves with ownership
+    let result = update_fee(&mut env.rpc, &owner, 100).await;
+    assert_rpc_error(
+        result,
+        0,
+        anchor_lang::error::ErrorCode::ConstraintHasOne.into(),
+    )
+    .unwrap();
+    update_fee(&mut env.rpc, &new_owner, 100).await.unwrap();
+    assert_eq!(get_app_state(&mut env.rpc).await.platform_fee_bps, 100);
+}
+
+#[tokio::test]
+async fn test_propose_owner_rejects_non_owner() {
+    let mut env = setup().await;
+    let attacker = funded_keypair(&mut env.rpc).await;
+
+    let result = propose_owner(&mut env.rpc, &attacker, attacker.pubkey()).await;
+    assert_rpc_error(
+        result,
+        0,
+        anchor_lang::error::ErrorCode::ConstraintHasOne.into(),
+    )
+    .unwrap();
+    assert_eq!(get_app_state(&mut env.rpc).await.pending_owner, None);
+}
+
+#[tokio::test]
+async fn test_accept_owner_requires_pending_owner() {
+    let mut env = setup().await;
+    let owner = env.owner.insecure_clone();
+    let new_owner = funded_keypair(&mut env.rpc).await;
+    let attacker = funded_keypair(&mut env.rpc).await;
+
+    // Nothing proposed yet
+    let result = accept_owner(&mut env.rpc, &new_owner).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::Unauthorized)).unwrap();
+
+    propose_owner(&mut env.rpc, &owner, new_owner.pubkey())
+        .await
+        .unwrap();
+    let result = accept_owner(&mut env.rpc, &attacker).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::Unauthorized)).unwrap();
+
+    // A later proposal replaces the earlier one
+    propose_owner(&mut env.rpc, &owner, attacker.pubkey())
+        .await
+        .unwrap();
+    let result = accept_owner(&mut env.rpc, &new_owner).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::Unauthorized)).unwrap();
+
+    assert_eq!(get_app_state(&mut env.rpc).await.owner, owner.pubkey());
+}
+
+#[tokio::test]
+async fn test_update_fee_recipient() {
+    let mut env = setup().await;
+    let owner = env.owner.insecure_clone();
+    let treasury = solana_sdk::pubkey::Pubkey::new_unique();
+
+    update_fee_recipient(&mut env.rpc, &owner, treasury)
+        .await
+        .unwrap();
+    let state = get_app_state(&mut env.rpc).await;
+    assert_eq!(state.fee_recipient, treasury);
+    assert_eq!(state.owner, owner.pubkey());
+}
+
+#[tokio::test]
+async fn test_update_fee_recipient_rejects_non_owner() {
+    let mut env = setup().await;
+    let attacker = funded_keypair(&mut env.rpc).await;
+
+    let result = update_fee_recipient(&mut env.rpc, &attacker, attacker.pubkey()).await;
+    assert_rpc_error(
+        result,
+        0,
+        anchor_lang::error::ErrorCode::ConstraintHasOne.into(),
+    )
+    .unwrap();
+    assert_eq!(
+        get_app_state(&mut env.rpc).await.fee_recipient,
+        env.owner.pubkey()
+    );
+}
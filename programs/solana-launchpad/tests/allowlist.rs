@@ -0,0 +1,344 @@
+#![cfg(feature = "test-sbf")]
+
+mod common;
+
+use common::*;
+use light_program_test::{utils::assert::assert_rpc_error, RpcError};
+use solana_launchpad::{AllowlistProof, ErrorCode};
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+};
+
+const SUPPLY: u64 = 1_000_000_000_000;
+const PRICE: u64 = 1_000_000;
+const ALLOCATION: u64 = 2_000_000_000;
+
+/// Buyer plus the accounts needed to buy from a standard sale
+struct StandardBuyer {
+    keypair: Keypair,
+    accounts: BuyAccounts,
+}
+
+async fn standard_buyer(
+    env: &mut TestEnv,
+    sale: &StandardSale,
+    creator_usdc_account: Pubkey,
+) -> StandardBuyer {
+    let (keypair, accounts) = create_sale_buyer(env, sale, creator_usdc_account, 10_000_000).await;
+    StandardBuyer { keypair, accounts }
+}
+
+/// Creates a standard sale mint and `count` buyers, ready for `launch_token`
+async fn prepare_sale(
+    env: &mut TestEnv,
+    count: usize,
+) -> (Keypair, StandardSale, Vec<StandardBuyer>) {
+    let owner = env.owner.insecure_clone();
+    let creator = funded_keypair(&mut env.rpc).await;
+    let sale = create_standard_sale_mint(&mut env.rpc, &creator, TOKEN_DECIMALS).await;
+    let creator_usdc_account =
+        create_token_account(&mut env.rpc, &owner, &env.usdc_mint, &creator.pubkey()).await;
+    let mut buyers = Vec::new();
+    for _ in 0..count {
+        buyers.push(standard_buyer(env, &sale, creator_usdc_account).await);
+    }
+    (creator, sale, buyers)
+}
+
+/// Buys from a sale that tracks wallets, passing the buyer's record
+async fn buy(
+    env: &mut TestEnv,
+    buyer: &StandardBuyer,
+    sale: &StandardSale,
+    usdc_amount: u64,
+    allowlist: Option<AllowlistProof>,
+) -> Result<Signature, RpcError> {
+    let record = buyer_record_inputs(&mut env.rpc, &sale.token_mint, &buyer.keypair.pubkey()).await;
+    let instruction = buy_tokens_instruction_with(
+        env,
+        &buyer.keypair.pubkey(),
+        sale,
+        &buyer.accounts,
+        usdc_amount,
+        0,
+        allowlist,
+        Some(record),
+    );
+    send(&mut env.rpc, instruction, &buyer.keypair, &[]).await
+}
+
+#[test]
+fn test_allowlist_proofs_verify_off_chain() {
+    let entries: Vec<_> = (1..=5).map(|i| (Pubkey::new_unique(), i * 1_000)).collect();
+    let allowlist = Allowlist::new(&entries);
+    for (buyer, allocation) in &entries {
+        let proof = allowlist.proof(buyer);
+        assert_eq!(proof.allocation, *allocation);
+        assert!(proof.verify(&allowlist.root(), buyer));
+        assert!(!proof.verify(&allowlist.root(), &Pubkey::new_unique()));
+    }
+
+    let forged = AllowlistProof {
+        allocation: 1_000_000,
+        ..allowlist.proof(&entries[0].0)
+    };
+    assert!(!forged.verify(&allowlist.root(), &entries[0].0));
+}
+
+#[tokio::test]
+async fn test_standard_allowlist() {
+    let mut env = setup().await;
+    let (creator, sale, buyers) = prepare_sale(&mut env, 2).await;
+    let (listed, outsider) = (&buyers[0], &buyers[1]);
+    let allowlist = Allowlist::new(&[
+        (listed.keypair.pubkey(), ALLOCATION),
+        (Pubkey::new_unique(), ALLOCATION),
+        (Pubkey::new_unique(), ALLOCATION),
+    ]);
+    launch_token(
+        &mut env.rpc,
+        &creator,
+        &sale,
+        LaunchArgs::new(SUPPLY, PRICE, 0).with_merkle_root(allowlist.root()),
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        get_token_sale(&mut env.rpc, &sale.token_mint)
+            .await
+            .merkle_root,
+        Some(allowlist.root())
+    );
+
+    // No proof, a proof for another wallet, and an inflated allocation are all rejected
+    let proof = allowlist.proof(&listed.keypair.pubkey());
+    let inflated = AllowlistProof {
+        allocation: ALLOCATION * 2,
+        ..proof.clone()
+    };
+    let cases = [
+        (listed, None, 1_000_000, ErrorCode::InvalidAllowlistProof),
+        (
+            outsider,
+            Some(proof.clone()),
+            1_000_000,
+            ErrorCode::InvalidAllowlistProof,
+        ),
+        (
+            listed,
+            Some(inflated),
+            1_000_000,
+            ErrorCode::InvalidAllowlistProof,
+        ),
+        (
+            listed,
+            Some(proof.clone()),
+            3_000_000,
+            ErrorCode::ExceedsAllocation,
+        ),
+    ];
+    for (buyer, allowlist_proof, usdc_amount, expected) in cases {
+        let result = buy(&mut env, buyer, &sale, usdc_amount, allowlist_proof).await;
+        assert_rpc_error(result, 0, error_code(expected)).unwrap();
+    }
+
+    // The allocation covers all purchases, not each one
+    buy(&mut env, listed, &sale, 1_000_000, Some(proof.clone()))
+        .await
+        .unwrap();
+    buy(&mut env, listed, &sale, 1_000_000, Some(proof.clone()))
+        .await
+        .unwrap();
+    let result = buy(&mut env, listed, &sale, 1_000_000, Some(proof)).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::ExceedsAllocation)).unwrap();
+
+    assert_eq!(
+        token_balance(&mut env.rpc, &listed.accounts.buyer_token_account).await,
+        ALLOCATION
+    );
+    let (_, record) = get_buyer_record(&mut env.rpc, &sale.token_mint, &listed.keypair.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(record.tokens_bought, ALLOCATION);
+}
+
+#[tokio::test]
+async fn test_standard_free_mint_allowlist() {
+    let mut env = setup().await;
+    let limit = 5_000_000_000;
+    let (creator, sale, buyers) = prepare_sale(&mut env, 2).await;
+    let (small, large) = (&buyers[0], &buyers[1]);
+    let allowlist = Allowlist::new(&[
+        (small.keypair.pubkey(), limit - 1),
+        (large.keypair.pubkey(), limit),
+    ]);
+    launch_token(
+        &mut env.rpc,
+        &creator,
+        &sale,
+        LaunchArgs::new(SUPPLY, 0, limit).with_merkle_root(allowlist.root()),
+    )
+    .await
+    .unwrap();
+
+    // A free mint always sends limit_per_mint, so smaller allocations cannot claim
+    let proof = allowlist.proof(&small.keypair.pubkey());
+    let result = buy(&mut env, small, &sale, 0, Some(proof)).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::ExceedsAllocation)).unwrap();
+
+    // One claim per listed wallet
+    let proof = allowlist.proof(&large.keypair.pubkey());
+    buy(&mut env, large, &sale, 0, Some(proof.clone()))
+        .await
+        .unwrap();
+    let result = buy(&mut env, large, &sale, 0, Some(proof)).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::ExceedsAllocation)).unwrap();
+    assert_eq!(
+        token_balance(&mut env.rpc, &large.accounts.buyer_token_account).await,
+        limit
+    );
+}
+
+#[tokio::test]
+async fn test_public_sale_ignores_allowlist_proof() {
+    let mut env = setup().await;
+    let (creator, sale, buyers) = prepare_sale(&mut env, 1).await;
+    let buyer = &buyers[0];
+    launch_token(
+        &mut env.rpc,
+        &creator,
+        &sale,
+        LaunchArgs::new(SUPPLY, PRICE, 0),
+    )
+    .await
+    .unwrap();
+
+    let instruction = buy_tokens_instruction_with(
+        &env,
+        &buyer.keypair.pubkey(),
+        &sale,
+        &buyer.accounts,
+        3_000_000,
+        0,
+        Some(AllowlistProof {
+            allocation: 0,
+            proof: vec![[7; 32]],
+        }),
+        None,
+    );
+    send(&mut env.rpc, instruction, &buyer.keypair, &[])
+        .await
+        .unwrap();
+    assert_eq!(
+        token_balance(&mut env.rpc, &buyer.accounts.buyer_token_account).await,
+        3_000_000_000
+    );
+}
+
+#[tokio::test]
+async fn test_compressed_allowlist() {
+    let mut env = setup().await;
+    let owner = env.owner.insecure_clone();
+    let creator = funded_keypair(&mut env.rpc).await;
+    let (listed, listed_usdc_account) = create_buyer(&mut env, 10_000_000).await;
+    let (outsider, outsider_usdc_account) = create_buyer(&mut env, 10_000_000).await;
+    let allowlist = Allowlist::new(&[
+        (listed.pubkey(), ALLOCATION),
+        (Pubkey::new_unique(), ALLOCATION),
+    ]);
+
+    let token_mint = create_compressed_sale_mint(&mut env.rpc, &creator).await;
+    launch_token_compressed(
+        &mut env.rpc,
+        &creator,
+        token_mint,
+        LaunchArgs::new(SUPPLY, PRICE, 0).with_merkle_root(allowlist.root()),
+    )
+    .await
+    .unwrap();
+    let creator_usdc_account =
+        create_token_account(&mut env.rpc, &owner, &env.usdc_mint, &creator.pubkey()).await;
+    let (_, sale) = get_compressed_sale(&mut env.rpc, &token_mint).await;
+    assert_eq!(sale.merkle_root, Some(allowlist.root()));
+
+    let proof = allowlist.proof(&listed.pubkey());
+    let cases = [
+        (
+            &listed,
+            listed_usdc_account,
+            None,
+            1_000_000,
+            Some(ErrorCode::InvalidAllowlistProof),
+        ),
+        (
+            &outsider,
+            outsider_usdc_account,
+            Some(proof.clone()),
+            1_000_000,
+            Some(ErrorCode::InvalidAllowlistProof),
+        ),
+        (
+            &listed,
+            listed_usdc_account,
+            Some(proof.clone()),
+            3_000_000,
+            Some(ErrorCode::ExceedsAllocation),
+        ),
+        (
+            &listed,
+            listed_usdc_account,
+            Some(proof.clone()),
+            1_000_000,
+            None,
+        ),
+        (
+            &listed,
+            listed_usdc_account,
+            Some(proof.clone()),
+            1_000_000,
+            None,
+        ),
+        (
+            &listed,
+            listed_usdc_account,
+            Some(proof),
+            1_000_000,
+            Some(ErrorCode::ExceedsAllocation),
+        ),
+    ];
+    for (buyer, buyer_usdc_account, allowlist_proof, usdc_amount, expected) in cases {
+        let inputs = compressed_sale_inputs_for_buyer(
+            &mut env.rpc,
+            &token_mint,
+            &token_mint,
+            Some(&buyer.pubkey()),
+        )
+        .await;
+        let instruction = buy_tokens_compressed_instruction_with(
+            &env,
+            &buyer.pubkey(),
+            buyer_usdc_account,
+            creator_usdc_account,
+            token_mint,
+            inputs,
+            usdc_amount,
+            0,
+            allowlist_proof,
+        );
+        let result = send(&mut env.rpc, instruction, buyer, &[]).await;
+        match expected {
+            Some(error) => assert_rpc_error(result, 0, error_code(error)).unwrap(),
+            None => {
+                result.unwrap();
+            }
+        }
+    }
+
+    assert_eq!(
+        compressed_token_balance(&mut env.rpc, &listed.pubkey(), &token_mint).await,
+        ALLOCATION
+    );
+    let (_, sale) = get_compressed_sale(&mut env.rpc, &token_mint).await;
+    assert_eq!(sale.tokens_sold, ALLOCATION);
+}
@@ -0,0 +1,189 @@
+#![cfg(feature = "test-sbf")]
+
+mod common;
+
+use common::*;
+use light_program_test::utils::assert::assert_rpc_error;
+use solana_launchpad::ErrorCode;
+use solana_sdk::signature::Signer;
+
+const SUPPLY: u64 = 1_000_000_000_000;
+const PRICE: u64 = 1_000_000;
+/// 0.001 USDC more per whole token sold
+const SLOPE: u64 = 1_000;
+const WHOLE: u64 = 1_000_000_000;
+
+#[tokio::test]
+async fn test_launch_rejects_slope_on_free_mint() {
+    let mut env = setup().await;
+    let creator = funded_keypair(&mut env.rpc).await;
+    let args = LaunchArgs::new(SUPPLY, 0, WHOLE).with_price_slope(SLOPE);
+
+    let sale = create_standard_sale_mint(&mut env.rpc, &creator, TOKEN_DECIMALS).await;
+    let result = launch_token(&mut env.rpc, &creator, &sale, args.clone()).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::InvalidPriceSlope)).unwrap();
+
+    let token_mint = create_compressed_sale_mint(&mut env.rpc, &creator).await;
+    let result = launch_token_compressed(&mut env.rpc, &creator, token_mint, args).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::InvalidPriceSlope)).unwrap();
+}
+
+#[tokio::test]
+async fn test_standard_curve_sale_requires_end_time() {
+    let mut env = setup().await;
+    let creator = funded_keypair(&mut env.rpc).await;
+    let sale = create_standard_sale_mint(&mut env.rpc, &creator, TOKEN_DECIMALS).await;
+    let result = launch_token(
+        &mut env.rpc,
+        &creator,
+        &sale,
+        LaunchArgs::new(SUPPLY, PRICE, 0).with_price_slope(SLOPE),
+    )
+    .await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::CurveRequiresEndTime)).unwrap();
+}
+
+#[tokio::test]
+async fn test_standard_curve_sale() {
+    let mut env = setup().await;
+    let end_ts = current_timestamp(&env.rpc) + 3_600;
+    let (_, sale, creator_usdc_account) = launch_sale(
+        &mut env,
+        LaunchArgs::new(SUPPLY, PRICE, 0)
+            .with_price_slope(SLOPE)
+            .with_window(None, Some(end_ts)),
+    )
+    .await;
+    assert_eq!(
+        get_token_sale(&mut env.rpc, &sale.token_mint)
+            .await
+            .price_slope,
+        SLOPE
+    );
+    let (buyer, accounts) =
+        create_sale_buyer(&mut env, &sale, creator_usdc_account, 100_000_000).await;
+
+    // Slippage: 10.05 USDC cannot buy 10 tokens plus one base unit
+    let instruction = buy_tokens_instruction_with(
+        &env,
+        &buyer.pubkey(),
+        &sale,
+        &accounts,
+        10_050_000,
+        10 * WHOLE + 1,
+        None,
+        None,
+    );
+    let result = send(&mut env.rpc, instruction, &buyer, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::SlippageExceeded)).unwrap();
+
+    let instruction = buy_tokens_instruction_with(
+        &env,
+        &buyer.pubkey(),
+        &sale,
+        &accounts,
+        10_050_000,
+        10 * WHOLE,
+        None,
+        None,
+    );
+    send(&mut env.rpc, instruction, &buyer, &[]).await.unwrap();
+    assert_eq!(
+        token_balance(&mut env.rpc, &accounts.buyer_token_account).await,
+        10 * WHOLE
+    );
+    assert_eq!(
+        token_balance(&mut env.rpc, &accounts.buyer_usdc_account).await,
+        100_000_000 - 10_050_000
+    );
+
+    // The same budget buys fewer tokens higher up the curve and only the cost is charged
+    let (tokens, cost) = (9_901_956_072, 10_050_000);
+    let instruction = buy_tokens_instruction(&env, &buyer.pubkey(), &sale, &accounts, 10_050_000);
+    send(&mut env.rpc, instruction, &buyer, &[]).await.unwrap();
+
+    assert_eq!(
+        token_balance(&mut env.rpc, &accounts.buyer_token_account).await,
+        10 * WHOLE + tokens
+    );
+    assert_eq!(
+        token_balance(&mut env.rpc, &accounts.buyer_usdc_account).await,
+        100_000_000 - 10_050_000 - cost
+    );
+    let spent = 10_050_000 + cost;
+    let fee_paid =
+        10_050_000 * PLATFORM_FEE_BPS as u64 / 10_000 + cost * PLATFORM_FEE_BPS as u64 / 10_000;
+    assert_eq!(
+        token_balance(&mut env.rpc, &env.fee_recipient_usdc_account).await,
+        fee_paid
+    );
+    // The creator share is held in reserve until the sale closes
+    assert_eq!(token_balance(&mut env.rpc, &creator_usdc_account).await, 0);
+    let token_sale = get_token_sale(&mut env.rpc, &sale.token_mint).await;
+    assert_eq!(token_sale.usdc_reserve, spent - fee_paid);
+    assert_eq!(token_sale.tokens_sold, 10 * WHOLE + tokens);
+}
+
+#[tokio::test]
+async fn test_compressed_curve_sale() {
+    let mut env = setup().await;
+    let owner = env.owner.insecure_clone();
+    let creator = funded_keypair(&mut env.rpc).await;
+    let token_mint = create_compressed_sale_mint(&mut env.rpc, &creator).await;
+    launch_token_compressed(
+        &mut env.rpc,
+        &creator,
+        token_mint,
+        LaunchArgs::new(SUPPLY, PRICE, 0).with_price_slope(SLOPE),
+    )
+    .await
+    .unwrap();
+    let creator_usdc_account =
+        create_token_account(&mut env.rpc, &owner, &env.usdc_mint, &creator.pubkey()).await;
+    let (buyer, buyer_usdc_account) = create_buyer(&mut env, 100_000_000).await;
+    let (_, sale) = get_compressed_sale(&mut env.rpc, &token_mint).await;
+    assert_eq!(sale.price_slope, SLOPE);
+
+    // 10.05 USDC buys exactly 10 tokens, so asking for one more base unit fails
+    let cases = [
+        (
+            10_050_000,
+            10 * WHOLE + 1,
+            Some(ErrorCode::SlippageExceeded),
+        ),
+        (10_060_000, 10 * WHOLE, None),
+    ];
+    for (usdc_amount, min_tokens_out, expected) in cases {
+        let inputs = compressed_sale_inputs(&mut env.rpc, &token_mint, &token_mint).await;
+        let instruction = buy_tokens_compressed_instruction_with(
+            &env,
+            &buyer.pubkey(),
+            buyer_usdc_account,
+            creator_usdc_account,
+            token_mint,
+            inputs,
+            usdc_amount,
+            min_tokens_out,
+            None,
+        );
+        let result = send(&mut env.rpc, instruction, &buyer, &[]).await;
+        match expected {
+            Some(error) => assert_rpc_error(result, 0, error_code(error)).unwrap(),
+            None => {
+                result.unwrap();
+            }
+        }
+    }
+
+    let (tokens, cost) = (10_009_900_941, 10_060_000);
+    assert_eq!(
+        compressed_token_balance(&mut env.rpc, &buyer.pubkey(), &token_mint).await,
+        tokens
+    );
+    assert_eq!(
+        token_balance(&mut env.rpc, &buyer_usdc_account).await,
+        100_000_000 - cost
+    );
+    let (_, sale) = get_compressed_sale(&mut env.rpc, &token_mint).await;
+    assert_eq!(sale.tokens_sold, tokens);
+}
@@ -0,0 +1,2493 @@
+#![allow(dead_code)]
+#![allow(deprecated)]
+
+use anchor_lang::{
+    solana_program::{program_pack::Pack, system_instruction},
+    AccountDeserialize, AnchorDeserialize, InstructionData, ToAccountMetas,
+};
+use anchor_spl::{
+    associated_token::{
+        get_associated_token_address, get_associated_token_address_with_program_id,
+        spl_associated_token_account,
+    },
+    token::spl_token,
+    token_2022::spl_token_2022::{
+        self,
+        extension::{
+            default_account_state, transfer_fee, transfer_hook, BaseStateWithExtensions,
+            ExtensionType, StateWithExtensions,
+        },
+        state::AccountState,
+    },
+};
+use light_client::indexer::{
+    CompressedAccount, GetCompressedTokenAccountsByOwnerOrDelegateOptions,
+};
+use light_ctoken_sdk::{
+    compressed_token::{
+        transfer::{
+            account_metas::TokenAccountsMetaConfig,
+            instruction::{create_transfer_instruction_raw, TransferConfig},
+        },
+        CTokenAccount, TokenAccountMeta,
+    },
+    spl_interface,
+};
+use light_program_test::{
+    AddressWithTree, Indexer, LightProgramTest, ProgramTestConfig, Rpc, RpcError,
+};
+use light_sdk::instruction::{
+    account_meta::CompressedAccountMeta, PackedAccounts, PackedAddressTreeInfo,
+    SystemAccountMetaConfig, ValidityProof,
+};
+use solana_launchpad::{
+    AllowlistProof, AppState, BuyerRecord, BuyerRecordInput, CompressedTokenSale, DutchAuction,
+    QuoteCurrency, QuoteMintEntry, ReferrerRecord, ReferrerRecordInput, SaleParams, TokenSale,
+    Vesting, COMPRESSED_SALE_ADDRESS_TREE, COMPRESSED_TOKEN_CPI_AUTHORITY,
+    COMPRESSED_TOKEN_PROGRAM_ID,
+};
+use solana_sdk::{
+    clock::Clock,
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+};
+
+pub const USDC_DECIMALS: u8 = 6;
+pub const TOKEN_DECIMALS: u8 = 9;
+pub const PLATFORM_FEE_BPS: u16 = 500;
+/// Minimum price of the USDC quote mint registered by `setup`
+pub const USDC_MIN_PRICE: u64 = 1_000;
+
+/// Light test environment with an initialized launchpad and a USDC mint
+pub struct TestEnv {
+    pub rpc: LightProgramTest,
+    pub owner: Keypair,
+    pub usdc_mint: Pubkey,
+    pub app_state: Pubkey,
+    pub program_authority: Pubkey,
+    /// Authority PDA's quote token account, paid through by compressed sales
+    pub program_usdc_account: Pubkey,
+    pub fee_recipient_usdc_account: Pubkey,
+}
+
+/// Accounts describing a compressed sale and the state needed to spend it
+pub struct CompressedSaleInputs {
+    pub sale: CompressedTokenSale,
+    pub account_meta: CompressedAccountMeta,
+    pub proof: ValidityProof,
+    pub token_proof: ValidityProof,
+    pub sale_token_accounts: Vec<TokenAccountMeta>,
+    pub token_output_tree_index: u8,
+    pub buyer_record: Option<BuyerRecordInput>,
+    pub remaining_accounts: Vec<AccountMeta>,
+}
+
+/// Buyer record and its proof for a standard buy on a sale that tracks wallets
+pub struct BuyerRecordInputs {
+    pub proof: ValidityProof,
+    pub record: BuyerRecordInput,
+    pub remaining_accounts: Vec<AccountMeta>,
+}
+
+/// Referrer record, and the buyer's for sales that track wallets, with the
+/// proof covering both for a referred standard buy
+pub struct ReferralInputs {
+    pub proof: ValidityProof,
+    pub buyer_record: Option<BuyerRecordInput>,
+    pub referrer_record: ReferrerRecordInput,
+    pub remaining_accounts: Vec<AccountMeta>,
+}
+
+/// Arguments shared by launch_token and launch_token_compressed
+#[derive(Clone)]
+pub struct LaunchArgs {
+    pub name: String,
+    pub symbol: String,
+    pub supply: u64,
+    pub price_per_token: u64,
+    pub limit_per_mint: u64,
+    pub metadata_id: String,
+    /// Registered mint of a `Token` sale; the launchpad's USDC mint if unset
+    pub quote_mint: Option<Pubkey>,
+    pub params: SaleParams,
+}
+
+impl LaunchArgs {
+    pub fn new(supply: u64, price_per_token: u64, limit_per_mint: u64) -> Self {
+        Self {
+            name: "Test Token".to_string(),
+            symbol: "TEST".to_string(),
+            supply,
+            price_per_token,
+            limit_per_mint,
+            metadata_id: "meta".to_string(),
+            quote_mint: None,
+            params: SaleParams::default(),
+        }
+    }
+
+    pub fn with_window(mut self, start_ts: Option<i64>, end_ts: Option<i64>) -> Self {
+        self.params.start_ts = start_ts;
+        self.params.end_ts = end_ts;
+        self
+    }
+
+    pub fn with_merkle_root(mut self, merkle_root: [u8; 32]) -> Self {
+        self.params.merkle_root = Some(merkle_root);
+        self
+    }
+
+    pub fn with_max_per_wallet(mut self, max_per_wallet: u64) -> Self {
+        self.params.max_per_wallet = max_per_wallet;
+        self
+    }
+
+    pub fn with_price_slope(mut self, price_slope: u64) -> Self {
+        self.params.price_slope = price_slope;
+        self
+    }
+
+    pub fn with_auction(mut self, floor_price: u64, uniform_clearing: bool) -> Self {
+        self.params.auction = Some(DutchAuction {
+            floor_price,
+            uniform_clearing,
+        });
+        self
+    }
+
+    pub fn with_soft_cap(mut self, soft_cap: u64) -> Self {
+        self.params.soft_cap = soft_cap;
+        self
+    }
+
+    pub fn with_hard_cap(mut self, hard_cap_usdc: u64) -> Self {
+        self.params.hard_cap_usdc = hard_cap_usdc;
+        self
+    }
+
+    pub fn with_quote_currency(mut self, quote_currency: QuoteCurrency) -> Self {
+        self.params.quote_currency = quote_currency;
+        self
+    }
+
+    pub fn with_quote_mint(mut self, quote_mint: Pubkey) -> Self {
+        self.params.quote_currency = QuoteCurrency::Token;
+        self.quote_mint = Some(quote_mint);
+        self
+    }
+
+    pub fn with_vesting(mut self, start_ts: i64, cliff_ts: i64, end_ts: i64) -> Self {
+        self.params.vesting = Some(Vesting {
+            start_ts,
+            cliff_ts,
+            end_ts,
+        });
+        self
+    }
+
+    pub fn with_proceeds_vesting(mut self, start_ts: i64, cliff_ts: i64, end_ts: i64) -> Self {
+        self.params.proceeds_vesting = Some(Vesting {
+            start_ts,
+            cliff_ts,
+            end_ts,
+        });
+        self
+    }
+}
+
+/// Merkle tree over (buyer, allocation) leaves, hashed the way the program verifies them
+pub struct Allowlist {
+    entries: Vec<(Pubkey, u64)>,
+    layers: Vec<Vec<[u8; 32]>>,
+}
+
+impl Allowlist {
+    pub fn new(entries: &[(Pubkey, u64)]) -> Self {
+        let mut layers = vec![entries
+            .iter()
+            .map(|(buyer, allocation)| AllowlistProof::leaf(buyer, *allocation))
+            .collect::<Vec<_>>()];
+        while layers.last().unwrap().len() > 1 {
+            let next = layers
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| match pair {
+                    [a, b] => hash_pair(a, b),
+                    [a] => *a,
+                    _ => unreachable!(),
+                })
+                .collect();
+            layers.push(next);
+        }
+        Self {
+            entries: entries.to_vec(),
+            layers,
+        }
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        self.layers.last().unwrap()[0]
+    }
+
+    /// Proof for the entry of `buyer`; panics if the buyer is not listed
+    pub fn proof(&self, buyer: &Pubkey) -> AllowlistProof {
+        let mut index = self
+            .entries
+            .iter()
+            .position(|(entry, _)| entry == buyer)
+            .expect("buyer not in allowlist");
+        let allocation = self.entries[index].1;
+        let mut proof = Vec::new();
+        for layer in &self.layers[..self.layers.len() - 1] {
+            if let Some(sibling) = layer.get(index ^ 1) {
+                proof.push(*sibling);
+            }
+            index /= 2;
+        }
+        AllowlistProof { allocation, proof }
+    }
+}
+
+fn hash_pair(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (first, second) = if a <= b { (a, b) } else { (b, a) };
+    anchor_lang::solana_program::keccak::hashv(&[first, second]).to_bytes()
+}
+
+/// Light test environment with the launchpad deployed but not initialized
+pub async fn new_rpc() -> LightProgramTest {
+    let config =
+        ProgramTestConfig::new(true, Some(vec![("solana_launchpad", solana_launchpad::ID)]));
+    LightProgramTest::new(config).await.unwrap()
+}
+
+pub async fn setup() -> TestEnv {
+    let mut rpc = new_rpc().await;
+    let owner = rpc.get_payer().insecure_clone();
+
+    let usdc_mint = create_mint(&mut rpc, &owner, &owner.pubkey(), USDC_DECIMALS).await;
+    let app_state = app_state_pda();
+    let program_authority = program_authority_pda();
+
+    initialize(&mut rpc, &owner, usdc_mint, PLATFORM_FEE_BPS)
+        .await
+        .unwrap();
+
+    let program_usdc_account =
+        create_token_account(&mut rpc, &owner, &usdc_mint, &program_authority).await;
+    let fee_recipient_usdc_account =
+        create_token_account(&mut rpc, &owner, &usdc_mint, &owner.pubkey()).await;
+
+    TestEnv {
+        rpc,
+        owner,
+        usdc_mint,
+        app_state,
+        program_authority,
+        program_usdc_account,
+        fee_recipient_usdc_account,
+    }
+}
+
+pub fn app_state_pda() -> Pubkey {
+    Pubkey::find_program_address(&[b"app_state"], &solana_launchpad::ID).0
+}
+
+pub fn program_authority_pda() -> Pubkey {
+    Pubkey::find_program_address(&[b"authority"], &solana_launchpad::ID).0
+}
+
+pub fn token_sale_pda(token_mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"token_sale", token_mint.as_ref()], &solana_launchpad::ID).0
+}
+
+pub fn quote_mint_entry_pda(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"quote_mint", mint.as_ref()], &solana_launchpad::ID).0
+}
+
+pub fn sale_authority_pda(token_mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"sale_authority", token_mint.as_ref()],
+        &solana_launchpad::ID,
+    )
+    .0
+}
+
+pub fn vesting_vault_pda(token_mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"vesting_vault", token_mint.as_ref()],
+        &solana_launchpad::ID,
+    )
+    .0
+}
+
+pub fn sale_escrow_pda(token_mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"escrow", token_mint.as_ref()], &solana_launchpad::ID).0
+}
+
+pub fn vesting_authority_pda(token_mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"vesting_authority", token_mint.as_ref()],
+        &solana_launchpad::ID,
+    )
+    .0
+}
+
+pub fn error_code(error: solana_launchpad::ErrorCode) -> u32 {
+    error.into()
+}
+
+pub async fn send(
+    rpc: &mut LightProgramTest,
+    instruction: Instruction,
+    payer: &Keypair,
+    signers: &[&Keypair],
+) -> Result<Signature, RpcError> {
+    let mut all_signers = vec![payer];
+    all_signers.extend(signers.iter().filter(|s| s.pubkey() != payer.pubkey()));
+    // Fresh blockhash so resending an identical instruction is not a duplicate
+    rpc.context.expire_blockhash();
+    rpc.create_and_send_transaction(&[instruction], &payer.pubkey(), &all_signers)
+        .await
+}
+
+pub async fn funded_keypair(rpc: &mut LightProgramTest) -> Keypair {
+    let keypair = Keypair::new();
+    rpc.airdrop_lamports(&keypair.pubkey(), 10_000_000_000)
+        .await
+        .unwrap();
+    keypair
+}
+
+pub fn current_timestamp(rpc: &LightProgramTest) -> i64 {
+    rpc.context.get_sysvar::<Clock>().unix_timestamp
+}
+
+pub fn warp_to_timestamp(rpc: &mut LightProgramTest, unix_timestamp: i64) {
+    let mut clock = rpc.context.get_sysvar::<Clock>();
+    clock.unix_timestamp = unix_timestamp;
+    rpc.context.set_sysvar(&clock);
+}
+
+// ==========================
+// SPL helpers
+// ==========================
+pub async fn create_mint(
+    rpc: &mut LightProgramTest,
+    payer: &Keypair,
+    authority: &Pubkey,
+    decimals: u8,
+) -> Pubkey {
+    let mint = Keypair::new();
+    let rent = rpc
+        .get_minimum_balance_for_rent_exemption(spl_token::state::Mint::LEN)
+        .await
+        .unwrap();
+    let instructions = [
+        system_instruction::create_account(
+            &payer.pubkey(),
+            &mint.pubkey(),
+            rent,
+            spl_token::state::Mint::LEN as u64,
+            &spl_token::ID,
+        ),
+        spl_token::instruction::initialize_mint2(
+            &spl_token::ID,
+            &mint.pubkey(),
+            authority,
+            None,
+            decimals,
+        )
+        .unwrap(),
+    ];
+    rpc.create_and_send_transaction(&instructions, &payer.pubkey(), &[payer, &mint])
+        .await
+        .unwrap();
+    mint.pubkey()
+}
+
+pub async fn create_token_account(
+    rpc: &mut LightProgramTest,
+    payer: &Keypair,
+    mint: &Pubkey,
+    owner: &Pubkey,
+) -> Pubkey {
+    create_token_account_for(rpc, payer, mint, owner, &spl_token::ID).await
+}
+
+/// Associated token account of a mint owned by `token_program`
+pub async fn create_token_account_for(
+    rpc: &mut LightProgramTest,
+    payer: &Keypair,
+    mint: &Pubkey,
+    owner: &Pubkey,
+    token_program: &Pubkey,
+) -> Pubkey {
+    let instruction =
+        spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+            &payer.pubkey(),
+            owner,
+            mint,
+            token_program,
+        );
+    rpc.create_and_send_transaction(&[instruction], &payer.pubkey(), &[payer])
+        .await
+        .unwrap();
+    if *token_program == spl_token::ID {
+        get_associated_token_address(owner, mint)
+    } else {
+        get_associated_token_address_with_program_id(owner, mint, token_program)
+    }
+}
+
+pub async fn mint_tokens(
+    rpc: &mut LightProgramTest,
+    authority: &Keypair,
+    mint: &Pubkey,
+    destination: &Pubkey,
+    amount: u64,
+) {
+    let instruction = spl_token::instruction::mint_to(
+        &spl_token::ID,
+        mint,
+        destination,
+        &authority.pubkey(),
+        &[],
+        amount,
+    )
+    .unwrap();
+    rpc.create_and_send_transaction(&[instruction], &authority.pubkey(), &[authority])
+        .await
+        .unwrap();
+}
+
+/// Balance of an SPL Token or Token-2022 account
+pub async fn token_balance(rpc: &mut LightProgramTest, token_account: &Pubkey) -> u64 {
+    let account = rpc.get_account(*token_account).await.unwrap().unwrap();
+    StateWithExtensions::<spl_token_2022::state::Account>::unpack(&account.data)
+        .unwrap()
+        .base
+        .amount
+}
+
+/// Token-2022 transfer fee withheld in `token_account`
+pub async fn withheld_transfer_fee(rpc: &mut LightProgramTest, token_account: &Pubkey) -> u64 {
+    let account = rpc.get_account(*token_account).await.unwrap().unwrap();
+    let state =
+        StateWithExtensions::<spl_token_2022::state::Account>::unpack(&account.data).unwrap();
+    state
+        .get_extension::<transfer_fee::TransferFeeAmount>()
+        .unwrap()
+        .withheld_amount
+        .into()
+}
+
+pub async fn lamport_balance(rpc: &mut LightProgramTest, address: &Pubkey) -> u64 {
+    rpc.get_balance(address).await.unwrap()
+}
+
+/// Funded buyer with a USDC account holding `usdc_amount`
+pub async fn create_buyer(env: &mut TestEnv, usdc_amount: u64) -> (Keypair, Pubkey) {
+    let buyer = funded_keypair(&mut env.rpc).await;
+    let buyer_usdc_account =
+        create_token_account(&mut env.rpc, &env.owner, &env.usdc_mint, &buyer.pubkey()).await;
+    let owner = env.owner.insecure_clone();
+    mint_tokens(
+        &mut env.rpc,
+        &owner,
+        &env.usdc_mint,
+        &buyer_usdc_account,
+        usdc_amount,
+    )
+    .await;
+    (buyer, buyer_usdc_account)
+}
+
+// ==========================
+// Launchpad instructions
+// ==========================
+/// Initializes the launchpad, registering `usdc_mint` at `USDC_MIN_PRICE`
+pub async fn initialize(
+    rpc: &mut LightProgramTest,
+    owner: &Keypair,
+    usdc_mint: Pubkey,
+    platform_fee_bps: u16,
+) -> Result<Signature, RpcError> {
+    let instruction = Instruction {
+        program_id: solana_launchpad::ID,
+        accounts: solana_launchpad::accounts::Initialize {
+            owner: owner.pubkey(),
+            app_state: app_state_pda(),
+            usdc_mint,
+            usdc_mint_entry: quote_mint_entry_pda(&usdc_mint),
+            system_program: solana_sdk::system_program::ID,
+        }
+        .to_account_metas(None),
+        data: solana_launchpad::instruction::Initialize {
+            platform_fee_bps,
+            usdc_min_price: USDC_MIN_PRICE,
+        }
+        .data(),
+    };
+    send(rpc, instruction, owner, &[]).await
+}
+
+pub async fn update_fee(
+    rpc: &mut LightProgramTest,
+    owner: &Keypair,
+    new_fee_bps: u16,
+) -> Result<Signature, RpcError> {
+    let instruction = Instruction {
+        program_id: solana_launchpad::ID,
+        accounts: solana_launchpad::accounts::UpdateFee {
+            app_state: app_state_pda(),
+            owner: owner.pubkey(),
+        }
+        .to_account_metas(None),
+        data: solana_launchpad::instruction::UpdateFee { new_fee_bps }.data(),
+    };
+    send(rpc, instruction, owner, &[]).await
+}
+
+pub async fn update_referral_fee(
+    rpc: &mut LightProgramTest,
+    owner: &Keypair,
+    new_referral_fee_bps: u16,
+) -> Result<Signature, RpcError> {
+    let instruction = Instruction {
+        program_id: solana_launchpad::ID,
+        accounts: solana_launchpad::accounts::UpdateFee {
+            app_state: app_state_pda(),
+            owner: owner.pubkey(),
+        }
+        .to_account_metas(None),
+        data: solana_launchpad::instruction::UpdateReferralFee {
+            new_referral_fee_bps,
+        }
+        .data(),
+    };
+    send(rpc, instruction, owner, &[]).await
+}
+
+pub async fn update_fee_recipient(
+    rpc: &mut LightProgramTest,
+    owner: &Keypair,
+    new_fee_recipient: Pubkey,
+) -> Result<Signature, RpcError> {
+    let instruction = Instruction {
+        program_id: solana_launchpad::ID,
+        accounts: solana_launchpad::accounts::UpdateFeeRecipient {
+            app_state: app_state_pda(),
+            owner: owner.pubkey(),
+        }
+        .to_account_metas(None),
+        data: solana_launchpad::instruction::UpdateFeeRecipient { new_fee_recipient }.data(),
+    };
+    send(rpc, instruction, owner, &[]).await
+}
+
+pub async fn set_paused(
+    rpc: &mut LightProgramTest,
+    owner: &Keypair,
+    paused: bool,
+) -> Result<Signature, RpcError> {
+    let accounts = solana_launchpad::accounts::SetPaused {
+        app_state: app_state_pda(),
+        owner: owner.pubkey(),
+    }
+    .to_account_metas(None);
+    let data = if paused {
+        solana_launchpad::instruction::Pause {}.data()
+    } else {
+        solana_launchpad::instruction::Unpause {}.data()
+    };
+    let instruction = Instruction {
+        program_id: solana_launchpad::ID,
+        accounts,
+        data,
+    };
+    send(rpc, instruction, owner, &[]).await
+}
+
+pub async fn propose_owner(
+    rpc: &mut LightProgramTest,
+    owner: &Keypair,
+    new_owner: Pubkey,
+) -> Result<Signature, RpcError> {
+    let instruction = Instruction {
+        program_id: solana_launchpad::ID,
+        accounts: solana_launchpad::accounts::ProposeOwner {
+            app_state: app_state_pda(),
+            owner: owner.pubkey(),
+        }
+        .to_account_metas(None),
+        data: solana_launchpad::instruction::ProposeOwner { new_owner }.data(),
+    };
+    send(rpc, instruction, owner, &[]).await
+}
+
+pub async fn accept_owner(
+    rpc: &mut LightProgramTest,
+    new_owner: &Keypair,
+) -> Result<Signature, RpcError> {
+    let instruction = Instruction {
+        program_id: solana_launchpad::ID,
+        accounts: solana_launchpad::accounts::AcceptOwner {
+            app_state: app_state_pda(),
+            new_owner: new_owner.pubkey(),
+        }
+        .to_account_metas(None),
+        data: solana_launchpad::instruction::AcceptOwner {}.data(),
+    };
+    send(rpc, instruction, new_owner, &[]).await
+}
+
+pub async fn add_quote_mint(
+    rpc: &mut LightProgramTest,
+    owner: &Keypair,
+    mint: Pubkey,
+    min_price: u64,
+) -> Result<Signature, RpcError> {
+    let instruction = Instruction {
+        program_id: solana_launchpad::ID,
+        accounts: solana_launchpad::accounts::AddQuoteMint {
+            app_state: app_state_pda(),
+            owner: owner.pubkey(),
+            quote_mint: mint,
+            quote_mint_entry: quote_mint_entry_pda(&mint),
+            system_program: solana_sdk::system_program::ID,
+        }
+        .to_account_metas(None),
+        data: solana_launchpad::instruction::AddQuoteMint { min_price }.data(),
+    };
+    send(rpc, instruction, owner, &[]).await
+}
+
+pub async fn update_quote_mint(
+    rpc: &mut LightProgramTest,
+    owner: &Keypair,
+    mint: Pubkey,
+    min_price: u64,
+) -> Result<Signature, RpcError> {
+    let instruction = Instruction {
+        program_id: solana_launchpad::ID,
+        accounts: solana_launchpad::accounts::UpdateQuoteMint {
+            app_state: app_state_pda(),
+            owner: owner.pubkey(),
+            quote_mint_entry: quote_mint_entry_pda(&mint),
+        }
+        .to_account_metas(None),
+        data: solana_launchpad::instruction::UpdateQuoteMint { min_price }.data(),
+    };
+    send(rpc, instruction, owner, &[]).await
+}
+
+pub async fn remove_quote_mint(
+    rpc: &mut LightProgramTest,
+    owner: &Keypair,
+    mint: Pubkey,
+) -> Result<Signature, RpcError> {
+    let instruction = Instruction {
+        program_id: solana_launchpad::ID,
+        accounts: solana_launchpad::accounts::RemoveQuoteMint {
+            app_state: app_state_pda(),
+            owner: owner.pubkey(),
+            quote_mint_entry: quote_mint_entry_pda(&mint),
+        }
+        .to_account_metas(None),
+        data: solana_launchpad::instruction::RemoveQuoteMint {}.data(),
+    };
+    send(rpc, instruction, owner, &[]).await
+}
+
+/// Registers `mint` and points the environment's platform token accounts, and
+/// the buyers `create_buyer` funds, at it instead of USDC
+pub async fn use_quote_mint(env: &mut TestEnv, mint: Pubkey, min_price: u64) {
+    let owner = env.owner.insecure_clone();
+    add_quote_mint(&mut env.rpc, &owner, mint, min_price)
+        .await
+        .unwrap();
+    env.program_usdc_account =
+        create_token_account(&mut env.rpc, &owner, &mint, &env.program_authority).await;
+    env.fee_recipient_usdc_account =
+        create_token_account(&mut env.rpc, &owner, &mint, &owner.pubkey()).await;
+    env.usdc_mint = mint;
+}
+
+pub async fn get_app_state(rpc: &mut LightProgramTest) -> AppState {
+    let account = rpc.get_account(app_state_pda()).await.unwrap().unwrap();
+    AppState::try_deserialize(&mut &account.data[..]).unwrap()
+}
+
+pub async fn get_token_sale(rpc: &mut LightProgramTest, token_mint: &Pubkey) -> TokenSale {
+    let account = rpc
+        .get_account(token_sale_pda(token_mint))
+        .await
+        .unwrap()
+        .unwrap();
+    TokenSale::try_deserialize(&mut &account.data[..]).unwrap()
+}
+
+/// Registry entry of `mint`, or None if it is not an accepted quote mint
+pub async fn get_quote_mint_entry(
+    rpc: &mut LightProgramTest,
+    mint: &Pubkey,
+) -> Option<QuoteMintEntry> {
+    let account = rpc.get_account(quote_mint_entry_pda(mint)).await.unwrap()?;
+    Some(QuoteMintEntry::try_deserialize(&mut &account.data[..]).unwrap())
+}
+
+/// Accounts of a standard sale: the mint and the token_sale PDA's token account
+pub struct StandardSale {
+    pub token_mint: Pubkey,
+    pub token_sale: Pubkey,
+    pub sale_token_account: Pubkey,
+    /// SPL Token or Token-2022
+    pub token_program: Pubkey,
+}
+
+/// SPL mint with the token_sale PDA as mint authority, plus the sale's token account
+pub async fn create_standard_sale_mint(
+    rpc: &mut LightProgramTest,
+    payer: &Keypair,
+    decimals: u8,
+) -> StandardSale {
+    let mint_keypair = Keypair::new();
+    let token_sale = token_sale_pda(&mint_keypair.pubkey());
+    let rent = rpc
+        .get_minimum_balance_for_rent_exemption(spl_token::state::Mint::LEN)
+        .await
+        .unwrap();
+    let instructions = [
+        system_instruction::create_account(
+            &payer.pubkey(),
+            &mint_keypair.pubkey(),
+            rent,
+            spl_token::state::Mint::LEN as u64,
+            &spl_token::ID,
+        ),
+        spl_token::instruction::initialize_mint2(
+            &spl_token::ID,
+            &mint_keypair.pubkey(),
+            &token_sale,
+            None,
+            decimals,
+        )
+        .unwrap(),
+    ];
+    rpc.create_and_send_transaction(&instructions, &payer.pubkey(), &[payer, &mint_keypair])
+        .await
+        .unwrap();
+    let sale_token_account =
+        create_token_account(rpc, payer, &mint_keypair.pubkey(), &token_sale).await;
+    StandardSale {
+        token_mint: mint_keypair.pubkey(),
+        token_sale,
+        sale_token_account,
+        token_program: spl_token::ID,
+    }
+}
+
+/// Token-2022 extension to initialize on a sale mint
+pub enum SaleMintExtension {
+    TransferFee {
+        basis_points: u16,
+        maximum_fee: u64,
+    },
+    NonTransferable,
+    PermanentDelegate(Pubkey),
+    TransferHook(Pubkey),
+    /// New token accounts start frozen; the payer is the freeze authority
+    DefaultFrozen,
+}
+
+/// Token-2022 mint with the token_sale PDA as mint authority, plus the sale's
+/// token account
+pub async fn create_token_2022_sale_mint(
+    rpc: &mut LightProgramTest,
+    payer: &Keypair,
+    decimals: u8,
+    extension: SaleMintExtension,
+) -> StandardSale {
+    let mint_keypair = Keypair::new();
+    let mint = mint_keypair.pubkey();
+    let token_sale = token_sale_pda(&mint);
+    let freeze_authority =
+        matches!(extension, SaleMintExtension::DefaultFrozen).then(|| payer.pubkey());
+    let (extension_type, initialize_extension) = match extension {
+        SaleMintExtension::TransferFee {
+            basis_points,
+            maximum_fee,
+        } => (
+            ExtensionType::TransferFeeConfig,
+            transfer_fee::instruction::initialize_transfer_fee_config(
+                &spl_token_2022::ID,
+                &mint,
+                None,
+                None,
+                basis_points,
+                maximum_fee,
+            )
+            .unwrap(),
+        ),
+        SaleMintExtension::NonTransferable => (
+            ExtensionType::NonTransferable,
+            spl_token_2022::instruction::initialize_non_transferable_mint(
+                &spl_token_2022::ID,
+                &mint,
+            )
+            .unwrap(),
+        ),
+        SaleMintExtension::PermanentDelegate(delegate) => (
+            ExtensionType::PermanentDelegate,
+            spl_token_2022::instruction::initialize_permanent_delegate(
+                &spl_token_2022::ID,
+                &mint,
+                &delegate,
+            )
+            .unwrap(),
+        ),
+        SaleMintExtension::TransferHook(program_id) => (
+            ExtensionType::TransferHook,
+            transfer_hook::instruction::initialize(
+                &spl_token_2022::ID,
+                &mint,
+                None,
+                Some(program_id),
+            )
+            .unwrap(),
+        ),
+        SaleMintExtension::DefaultFrozen => (
+            ExtensionType::DefaultAccountState,
+            default_account_state::instruction::initialize_default_account_state(
+                &spl_token_2022::ID,
+                &mint,
+                &AccountState::Frozen,
+            )
+            .unwrap(),
+        ),
+    };
+    let len =
+        ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[extension_type])
+            .unwrap();
+    let rent = rpc
+        .get_minimum_balance_for_rent_exemption(len)
+        .await
+        .unwrap();
+    let instructions = [
+        system_instruction::create_account(
+            &payer.pubkey(),
+            &mint,
+            rent,
+            len as u64,
+            &spl_token_2022::ID,
+        ),
+        initialize_extension,
+        spl_token_2022::instruction::initialize_mint2(
+            &spl_token_2022::ID,
+            &mint,
+            &token_sale,
+            freeze_authority.as_ref(),
+            decimals,
+        )
+        .unwrap(),
+    ];
+    rpc.create_and_send_transaction(&instructions, &payer.pubkey(), &[payer, &mint_keypair])
+        .await
+        .unwrap();
+    let sale_token_account =
+        create_token_account_for(rpc, payer, &mint, &token_sale, &spl_token_2022::ID).await;
+    StandardSale {
+        token_mint: mint,
+        token_sale,
+        sale_token_account,
+        token_program: spl_token_2022::ID,
+    }
+}
+
+/// Quote mint a launch passes: none for SOL sales, otherwise the requested
+/// quote mint or the launchpad's USDC mint
+async fn launch_quote_mint(rpc: &mut LightProgramTest, args: &LaunchArgs) -> Option<Pubkey> {
+    match (args.params.quote_currency, args.quote_mint) {
+        (QuoteCurrency::Sol, _) => None,
+        (QuoteCurrency::Token, Some(mint)) => Some(mint),
+        (QuoteCurrency::Token, None) => Some(get_app_state(rpc).await.usdc_mint),
+    }
+}
+
+pub async fn launch_token(
+    rpc: &mut LightProgramTest,
+    creator: &Keypair,
+    sale: &StandardSale,
+    args: LaunchArgs,
+) -> Result<Signature, RpcError> {
+    let quote_mint = launch_quote_mint(rpc, &args).await;
+    let instruction = Instruction {
+        program_id: solana_launchpad::ID,
+        accounts: solana_launchpad::accounts::LaunchToken {
+            creator: creator.pubkey(),
+            token_sale: sale.token_sale,
+            token_mint: sale.token_mint,
+            sale_token_account: sale.sale_token_account,
+            vesting_vault: args
+                .params
+                .vesting
+                .map(|_| vesting_vault_pda(&sale.token_mint)),
+            quote_mint_entry: quote_mint.as_ref().map(quote_mint_entry_pda),
+            quote_mint,
+            sale_escrow: quote_mint.map(|_| sale_escrow_pda(&sale.token_mint)),
+            app_state: app_state_pda(),
+            program_authority: program_authority_pda(),
+            token_program: sale.token_program,
+            quote_token_program: spl_token::ID,
+            system_program: solana_sdk::system_program::ID,
+        }
+        .to_account_metas(None),
+        data: solana_launchpad::instruction::LaunchToken {
+            name: args.name,
+            symbol: args.symbol,
+            supply: args.supply,
+            price_per_token: args.price_per_token,
+            limit_per_mint: args.limit_per_mint,
+            metadata_id: args.metadata_id,
+            params: args.params,
+        }
+        .data(),
+    };
+    send(rpc, instruction, creator, &[]).await
+}
+
+/// Token and USDC accounts a buyer pays from and receives into
+pub struct BuyAccounts {
+    pub buyer_usdc_account: Pubkey,
+    pub buyer_token_account: Pubkey,
+    pub creator_usdc_account: Pubkey,
+}
+
+/// Launches a standard sale from a new creator, returning the creator and
+/// their (empty) USDC account
+pub async fn launch_sale(env: &mut TestEnv, args: LaunchArgs) -> (Keypair, StandardSale, Pubkey) {
+    let creator = funded_keypair(&mut env.rpc).await;
+    let sale = create_standard_sale_mint(&mut env.rpc, &creator, TOKEN_DECIMALS).await;
+    launch_token(&mut env.rpc, &creator, &sale, args)
+        .await
+        .unwrap();
+    let owner = env.owner.insecure_clone();
+    let creator_usdc_account =
+        create_token_account(&mut env.rpc, &owner, &env.usdc_mint, &creator.pubkey()).await;
+    (creator, sale, creator_usdc_account)
+}
+
+/// Buyer holding `usdc_amount` USDC and an empty token account for `sale`
+pub async fn create_sale_buyer(
+    env: &mut TestEnv,
+    sale: &StandardSale,
+    creator_usdc_account: Pubkey,
+    usdc_amount: u64,
+) -> (Keypair, BuyAccounts) {
+    let (buyer, buyer_usdc_account) = create_buyer(env, usdc_amount).await;
+    let owner = env.owner.insecure_clone();
+    let buyer_token_account = create_token_account_for(
+        &mut env.rpc,
+        &owner,
+        &sale.token_mint,
+        &buyer.pubkey(),
+        &sale.token_program,
+    )
+    .await;
+    (
+        buyer,
+        BuyAccounts {
+            buyer_usdc_account,
+            buyer_token_account,
+            creator_usdc_account,
+        },
+    )
+}
+
+/// Launches a standard sale and a buyer holding `usdc_amount` USDC
+pub async fn launch_sale_with_buyer(
+    env: &mut TestEnv,
+    args: LaunchArgs,
+    usdc_amount: u64,
+) -> (StandardSale, Keypair, BuyAccounts) {
+    let (_, sale, creator_usdc_account) = launch_sale(env, args).await;
+    let (buyer, accounts) = create_sale_buyer(env, &sale, creator_usdc_account, usdc_amount).await;
+    (sale, buyer, accounts)
+}
+
+/// Launches a compressed sale from a new creator and returns (creator,
+/// token_mint, creator_usdc_account)
+pub async fn launch_compressed_sale(
+    env: &mut TestEnv,
+    args: LaunchArgs,
+) -> (Keypair, Pubkey, Pubkey) {
+    let creator = funded_keypair(&mut env.rpc).await;
+    let token_mint = create_compressed_sale_mint(&mut env.rpc, &creator).await;
+    launch_token_compressed(&mut env.rpc, &creator, token_mint, args)
+        .await
+        .unwrap();
+    let owner = env.owner.insecure_clone();
+    let creator_usdc_account =
+        create_token_account(&mut env.rpc, &owner, &env.usdc_mint, &creator.pubkey()).await;
+    (creator, token_mint, creator_usdc_account)
+}
+
+/// Buys `usdc_amount` with the buyer's record, which sales that track wallets
+/// require
+pub async fn buy_with_record(
+    env: &mut TestEnv,
+    buyer: &Keypair,
+    sale: &StandardSale,
+    accounts: &BuyAccounts,
+    usdc_amount: u64,
+) -> Result<Signature, RpcError> {
+    let record = buyer_record_inputs(&mut env.rpc, &sale.token_mint, &buyer.pubkey()).await;
+    let instruction = buy_tokens_instruction_with(
+        env,
+        &buyer.pubkey(),
+        sale,
+        accounts,
+        usdc_amount,
+        0,
+        None,
+        Some(record),
+    );
+    send(&mut env.rpc, instruction, buyer, &[]).await
+}
+
+/// Refund instruction a buyer claims
+#[derive(Clone, Copy)]
+pub enum RefundKind {
+    /// claim_refund of a sale that missed its soft cap
+    SoftCap,
+    /// claim_auction_refund of a uniform clearing auction
+    Auction,
+}
+
+/// Claims the buyer's refund of `kind` with their record
+pub async fn claim_refund(
+    env: &mut TestEnv,
+    kind: RefundKind,
+    buyer: &Keypair,
+    sale: &StandardSale,
+    accounts: &BuyAccounts,
+) -> Result<Signature, RpcError> {
+    let record = buyer_record_inputs(&mut env.rpc, &sale.token_mint, &buyer.pubkey()).await;
+    let instruction = match kind {
+        RefundKind::SoftCap => {
+            claim_refund_instruction(env, &buyer.pubkey(), sale, accounts, record)
+        }
+        RefundKind::Auction => claim_auction_refund_instruction(
+            env,
+            &buyer.pubkey(),
+            sale,
+            accounts.buyer_usdc_account,
+            record,
+        ),
+    };
+    send(&mut env.rpc, instruction, buyer, &[]).await
+}
+
+pub fn buy_tokens_instruction(
+    env: &TestEnv,
+    buyer: &Pubkey,
+    sale: &StandardSale,
+    accounts: &BuyAccounts,
+    usdc_amount: u64,
+) -> Instruction {
+    buy_tokens_instruction_with(env, buyer, sale, accounts, usdc_amount, 0, None, None)
+}
+
+/// Buy with slippage protection, an allowlist proof and, for sales that track
+/// wallets, the buyer record
+#[allow(clippy::too_many_arguments)]
+pub fn buy_tokens_instruction_with(
+    env: &TestEnv,
+    buyer: &Pubkey,
+    sale: &StandardSale,
+    accounts: &BuyAccounts,
+    usdc_amount: u64,
+    min_tokens_out: u64,
+    allowlist: Option<AllowlistProof>,
+    record: Option<BuyerRecordInputs>,
+) -> Instruction {
+    let (proof, buyer_record, remaining_accounts) = match record {
+        Some(record) => (record.proof, Some(record.record), record.remaining_accounts),
+        None => (ValidityProof::default(), None, vec![]),
+    };
+    Instruction {
+        program_id: solana_launchpad::ID,
+        accounts: [
+            solana_launchpad::accounts::BuyTokens {
+                buyer: *buyer,
+                buyer_usdc_account: Some(accounts.buyer_usdc_account),
+                buyer_token_account: accounts.buyer_token_account,
+                creator_usdc_account: Some(accounts.creator_usdc_account),
+                creator: None,
+                fee_recipient: None,
+                fee_recipient_usdc_account: Some(env.fee_recipient_usdc_account),
+                referrer: None,
+                referrer_usdc_account: None,
+                sale_escrow: Some(sale_escrow_pda(&sale.token_mint)),
+                token_sale: sale.token_sale,
+                token_mint: sale.token_mint,
+                sale_token_account: sale.sale_token_account,
+                vesting_vault: None,
+                app_state: env.app_state,
+                quote_mint_entry: Some(quote_mint_entry_pda(&env.usdc_mint)),
+                program_authority: env.program_authority,
+                token_program: spl_token::ID,
+                sale_token_program: sale.token_program,
+                system_program: solana_sdk::system_program::ID,
+            }
+            .to_account_metas(None),
+            remaining_accounts,
+        ]
+        .concat(),
+        data: solana_launchpad::instruction::BuyTokens {
+            usdc_amount,
+            min_tokens_out,
+            allowlist,
+            proof,
+            buyer_record,
+            referrer_record: None,
+        }
+        .data(),
+    }
+}
+
+/// Buy exactly `token_amount` tokens for at most `max_usdc`
+pub fn buy_exact_tokens_instruction(
+    env: &TestEnv,
+    buyer: &Pubkey,
+    sale: &StandardSale,
+    accounts: &BuyAccounts,
+    token_amount: u64,
+    max_usdc: u64,
+) -> Instruction {
+    Instruction {
+        program_id: solana_launchpad::ID,
+        accounts: solana_launchpad::accounts::BuyTokens {
+            buyer: *buyer,
+            buyer_usdc_account: Some(accounts.buyer_usdc_account),
+            buyer_token_account: accounts.buyer_token_account,
+            creator_usdc_account: Some(accounts.creator_usdc_account),
+            creator: None,
+            fee_recipient: None,
+            fee_recipient_usdc_account: Some(env.fee_recipient_usdc_account),
+            referrer: None,
+            referrer_usdc_account: None,
+            sale_escrow: Some(sale_escrow_pda(&sale.token_mint)),
+            token_sale: sale.token_sale,
+            token_mint: sale.token_mint,
+            sale_token_account: sale.sale_token_account,
+            vesting_vault: None,
+            app_state: env.app_state,
+            quote_mint_entry: Some(quote_mint_entry_pda(&env.usdc_mint)),
+            program_authority: env.program_authority,
+            token_program: spl_token::ID,
+            sale_token_program: sale.token_program,
+            system_program: solana_sdk::system_program::ID,
+        }
+        .to_account_metas(None),
+        data: solana_launchpad::instruction::BuyExactTokens {
+            token_amount,
+            max_usdc,
+            allowlist: None,
+            proof: ValidityProof::default(),
+            buyer_record: None,
+            referrer_record: None,
+        }
+        .data(),
+    }
+}
+
+/// Buy from a SOL sale, paying `lamports` at most straight to the creator and
+/// fee recipient wallets
+pub fn buy_tokens_sol_instruction(
+    env: &TestEnv,
+    buyer: &Pubkey,
+    sale: &StandardSale,
+    buyer_token_account: Pubkey,
+    creator: Pubkey,
+    fee_recipient: Pubkey,
+    lamports: u64,
+) -> Instruction {
+    Instruction {
+        program_id: solana_launchpad::ID,
+        accounts: solana_launchpad::accounts::BuyTokens {
+            buyer: *buyer,
+            buyer_usdc_account: None,
+            buyer_token_account,
+            creator_usdc_account: None,
+            creator: Some(creator),
+            fee_recipient: Some(fee_recipient),
+            fee_recipient_usdc_account: None,
+            referrer: None,
+            referrer_usdc_account: None,
+            sale_escrow: None,
+            token_sale: sale.token_sale,
+            token_mint: sale.token_mint,
+            sale_token_account: sale.sale_token_account,
+            vesting_vault: None,
+            app_state: env.app_state,
+            quote_mint_entry: None,
+            program_authority: env.program_authority,
+            token_program: spl_token::ID,
+            sale_token_program: sale.token_program,
+            system_program: solana_sdk::system_program::ID,
+        }
+        .to_account_metas(None),
+        data: solana_launchpad::instruction::BuyTokens {
+            usdc_amount: lamports,
+            min_tokens_out: 0,
+            allowlist: None,
+            proof: ValidityProof::default(),
+            buyer_record: None,
+            referrer_record: None,
+        }
+        .data(),
+    }
+}
+
+pub fn close_sale_instruction(
+    creator: &Pubkey,
+    sale: &StandardSale,
+    creator_token_account: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: solana_launchpad::ID,
+        accounts: solana_launchpad::accounts::CloseSale {
+            creator: *creator,
+            token_sale: sale.token_sale,
+            token_mint: sale.token_mint,
+            sale_token_account: sale.sale_token_account,
+            creator_token_account,
+            token_program: sale.token_program,
+        }
+        .to_account_metas(None),
+        data: solana_launchpad::instruction::CloseSale {}.data(),
+    }
+}
+
+pub fn finalize_sale_instruction(
+    env: &TestEnv,
+    sale: &StandardSale,
+    creator_token_account: Pubkey,
+    creator_usdc_account: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: solana_launchpad::ID,
+        accounts: solana_launchpad::accounts::FinalizeSale {
+            token_sale: sale.token_sale,
+            token_mint: sale.token_mint,
+            sale_token_account: sale.sale_token_account,
+            creator_token_account,
+            creator_usdc_account: Some(creator_usdc_account),
+            fee_recipient_usdc_account: Some(env.fee_recipient_usdc_account),
+            sale_escrow: Some(sale_escrow_pda(&sale.token_mint)),
+            app_state: env.app_state,
+            program_authority: env.program_authority,
+            token_program: spl_token::ID,
+            sale_token_program: sale.token_program,
+        }
+        .to_account_metas(None),
+        data: solana_launchpad::instruction::FinalizeSale {}.data(),
+    }
+}
+
+pub fn sell_tokens_instruction(
+    env: &TestEnv,
+    seller: &Pubkey,
+    sale: &StandardSale,
+    seller_usdc_account: Pubkey,
+    seller_token_account: Pubkey,
+    token_amount: u64,
+    min_usdc_out: u64,
+) -> Instruction {
+    Instruction {
+        program_id: solana_launchpad::ID,
+        accounts: solana_launchpad::accounts::SellTokens {
+            seller: *seller,
+            seller_usdc_account,
+            seller_token_account,
+            sale_escrow: sale_escrow_pda(&sale.token_mint),
+            token_sale: sale.token_sale,
+            token_mint: sale.token_mint,
+            sale_token_account: sale.sale_token_account,
+            app_state: env.app_state,
+            program_authority: env.program_authority,
+            token_program: spl_token::ID,
+            sale_token_program: sale.token_program,
+        }
+        .to_account_metas(None),
+        data: solana_launchpad::instruction::SellTokens {
+            token_amount,
+            min_usdc_out,
+        }
+        .data(),
+    }
+}
+
+pub fn claim_reserve_instruction(
+    env: &TestEnv,
+    creator: &Pubkey,
+    sale: &StandardSale,
+    creator_usdc_account: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: solana_launchpad::ID,
+        accounts: solana_launchpad::accounts::ClaimReserve {
+            creator: *creator,
+            token_sale: sale.token_sale,
+            creator_usdc_account,
+            fee_recipient_usdc_account: env.fee_recipient_usdc_account,
+            sale_escrow: sale_escrow_pda(&sale.token_mint),
+            app_state: env.app_state,
+            program_authority: env.program_authority,
+            token_program: spl_token::ID,
+        }
+        .to_account_metas(None),
+        data: solana_launchpad::instruction::ClaimReserve {}.data(),
+    }
+}
+
+pub fn withdraw_proceeds_instruction(
+    env: &TestEnv,
+    creator: &Pubkey,
+    sale: &StandardSale,
+    creator_usdc_account: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: solana_launchpad::ID,
+        accounts: solana_launchpad::accounts::WithdrawProceeds {
+            creator: *creator,
+            token_sale: sale.token_sale,
+            creator_usdc_account,
+            sale_escrow: sale_escrow_pda(&sale.token_mint),
+            program_authority: env.program_authority,
+            token_program: spl_token::ID,
+        }
+        .to_account_metas(None),
+        data: solana_launchpad::instruction::WithdrawProceeds {}.data(),
+    }
+}
+
+/// claim_auction_refund for a buyer whose existing record is in `record`
+pub fn claim_auction_refund_instruction(
+    env: &TestEnv,
+    buyer: &Pubkey,
+    sale: &StandardSale,
+    buyer_usdc_account: Pubkey,
+    record: BuyerRecordInputs,
+) -> Instruction {
+    let BuyerRecordInput::Existing {
+        current,
+        account_meta,
+    } = record.record
+    else {
+        panic!("buyer has no record to refund");
+    };
+    Instruction {
+        program_id: solana_launchpad::ID,
+        accounts: [
+            solana_launchpad::accounts::ClaimAuctionRefund {
+                buyer: *buyer,
+                buyer_usdc_account,
+                sale_escrow: sale_escrow_pda(&sale.token_mint),
+                token_sale: sale.token_sale,
+                app_state: env.app_state,
+                program_authority: env.program_authority,
+                token_program: spl_token::ID,
+            }
+            .to_account_metas(None),
+            record.remaining_accounts,
+        ]
+        .concat(),
+        data: solana_launchpad::instruction::ClaimAuctionRefund {
+            proof: record.proof,
+            current,
+            account_meta,
+        }
+        .data(),
+    }
+}
+
+/// claim_refund for a buyer whose existing record is in `record`
+pub fn claim_refund_instruction(
+    env: &TestEnv,
+    buyer: &Pubkey,
+    sale: &StandardSale,
+    accounts: &BuyAccounts,
+    record: BuyerRecordInputs,
+) -> Instruction {
+    let BuyerRecordInput::Existing {
+        current,
+        account_meta,
+    } = record.record
+    else {
+        panic!("buyer has no record to refund");
+    };
+    Instruction {
+        program_id: solana_launchpad::ID,
+        accounts: [
+            solana_launchpad::accounts::ClaimRefund {
+                buyer: *buyer,
+                buyer_usdc_account: accounts.buyer_usdc_account,
+                buyer_token_account: accounts.buyer_token_account,
+                sale_escrow: sale_escrow_pda(&sale.token_mint),
+                token_sale: sale.token_sale,
+                token_mint: sale.token_mint,
+                app_state: env.app_state,
+                program_authority: env.program_authority,
+                token_program: spl_token::ID,
+                sale_token_program: sale.token_program,
+            }
+            .to_account_metas(None),
+            record.remaining_accounts,
+        ]
+        .concat(),
+        data: solana_launchpad::instruction::ClaimRefund {
+            proof: record.proof,
+            current,
+            account_meta,
+        }
+        .data(),
+    }
+}
+
+/// Buy from a vesting sale, delivering into the vesting vault and recording
+/// the purchase in the buyer's `record`
+pub fn buy_vesting_tokens_instruction(
+    env: &TestEnv,
+    buyer: &Pubkey,
+    sale: &StandardSale,
+    accounts: &BuyAccounts,
+    usdc_amount: u64,
+    record: BuyerRecordInputs,
+) -> Instruction {
+    Instruction {
+        program_id: solana_launchpad::ID,
+        accounts: [
+            solana_launchpad::accounts::BuyTokens {
+                buyer: *buyer,
+                buyer_usdc_account: Some(accounts.buyer_usdc_account),
+                buyer_token_account: accounts.buyer_token_account,
+                creator_usdc_account: Some(accounts.creator_usdc_account),
+                creator: None,
+                fee_recipient: None,
+                fee_recipient_usdc_account: Some(env.fee_recipient_usdc_account),
+                referrer: None,
+                referrer_usdc_account: None,
+                sale_escrow: Some(sale_escrow_pda(&sale.token_mint)),
+                token_sale: sale.token_sale,
+                token_mint: sale.token_mint,
+                sale_token_account: sale.sale_token_account,
+                vesting_vault: Some(vesting_vault_pda(&sale.token_mint)),
+                app_state: env.app_state,
+                quote_mint_entry: Some(quote_mint_entry_pda(&env.usdc_mint)),
+                program_authority: env.program_authority,
+                token_program: spl_token::ID,
+                sale_token_program: sale.token_program,
+                system_program: solana_sdk::system_program::ID,
+            }
+            .to_account_metas(None),
+            record.remaining_accounts,
+        ]
+        .concat(),
+        data: solana_launchpad::instruction::BuyTokens {
+            usdc_amount,
+            min_tokens_out: 0,
+            allowlist: None,
+            proof: record.proof,
+            buyer_record: Some(record.record),
+            referrer_record: None,
+        }
+        .data(),
+    }
+}
+
+/// Buy crediting `referrer`, whose quote token account receives the referral fee
+#[allow(clippy::too_many_arguments)]
+pub fn buy_referred_tokens_instruction(
+    env: &TestEnv,
+    buyer: &Pubkey,
+    sale: &StandardSale,
+    accounts: &BuyAccounts,
+    usdc_amount: u64,
+    referrer: &Pubkey,
+    referrer_usdc_account: Pubkey,
+    inputs: ReferralInputs,
+) -> Instruction {
+    Instruction {
+        program_id: solana_launchpad::ID,
+        accounts: [
+            solana_launchpad::accounts::BuyTokens {
+                buyer: *buyer,
+                buyer_usdc_account: Some(accounts.buyer_usdc_account),
+                buyer_token_account: accounts.buyer_token_account,
+                creator_usdc_account: Some(accounts.creator_usdc_account),
+                creator: None,
+                fee_recipient: None,
+                fee_recipient_usdc_account: Some(env.fee_recipient_usdc_account),
+                referrer: Some(*referrer),
+                referrer_usdc_account: Some(referrer_usdc_account),
+                sale_escrow: Some(sale_escrow_pda(&sale.token_mint)),
+                token_sale: sale.token_sale,
+                token_mint: sale.token_mint,
+                sale_token_account: sale.sale_token_account,
+                vesting_vault: None,
+                app_state: env.app_state,
+                quote_mint_entry: Some(quote_mint_entry_pda(&env.usdc_mint)),
+                program_authority: env.program_authority,
+                token_program: spl_token::ID,
+                sale_token_program: sale.token_program,
+                system_program: solana_sdk::system_program::ID,
+            }
+            .to_account_metas(None),
+            inputs.remaining_accounts,
+        ]
+        .concat(),
+        data: solana_launchpad::instruction::BuyTokens {
+            usdc_amount,
+            min_tokens_out: 0,
+            allowlist: None,
+            proof: inputs.proof,
+            buyer_record: inputs.buyer_record,
+            referrer_record: Some(inputs.referrer_record),
+        }
+        .data(),
+    }
+}
+
+/// claim_vested for a buyer whose existing record is in `record`
+pub fn claim_vested_instruction(
+    buyer: &Pubkey,
+    sale: &StandardSale,
+    buyer_token_account: Pubkey,
+    record: BuyerRecordInputs,
+) -> Instruction {
+    let BuyerRecordInput::Existing {
+        current,
+        account_meta,
+    } = record.record
+    else {
+        panic!("buyer has no record to claim");
+    };
+    Instruction {
+        program_id: solana_launchpad::ID,
+        accounts: [
+            solana_launchpad::accounts::ClaimVested {
+                buyer: *buyer,
+                buyer_token_account,
+                token_sale: sale.token_sale,
+                token_mint: sale.token_mint,
+                vesting_vault: vesting_vault_pda(&sale.token_mint),
+                sale_token_program: sale.token_program,
+            }
+            .to_account_metas(None),
+            record.remaining_accounts,
+        ]
+        .concat(),
+        data: solana_launchpad::instruction::ClaimVested {
+            proof: record.proof,
+            current,
+            account_meta,
+        }
+        .data(),
+    }
+}
+
+// ==========================
+// Compressed sale helpers
+// ==========================
+pub async fn create_token_pool(rpc: &mut LightProgramTest, payer: &Keypair, mint: &Pubkey) {
+    let instruction =
+        spl_interface::CreateSplInterfacePda::new(payer.pubkey(), *mint, spl_token::ID)
+            .instruction();
+    rpc.create_and_send_transaction(&[instruction], &payer.pubkey(), &[payer])
+        .await
+        .unwrap();
+}
+
+/// SPL mint with the sale_authority PDA as mint authority and a registered token pool
+pub async fn create_compressed_sale_mint(rpc: &mut LightProgramTest, payer: &Keypair) -> Pubkey {
+    let mint_keypair = Keypair::new();
+    let sale_authority = sale_authority_pda(&mint_keypair.pubkey());
+    let rent = rpc
+        .get_minimum_balance_for_rent_exemption(spl_token::state::Mint::LEN)
+        .await
+        .unwrap();
+    let instructions = [
+        system_instruction::create_account(
+            &payer.pubkey(),
+            &mint_keypair.pubkey(),
+            rent,
+            spl_token::state::Mint::LEN as u64,
+            &spl_token::ID,
+        ),
+        spl_token::instruction::initialize_mint2(
+            &spl_token::ID,
+            &mint_keypair.pubkey(),
+            &sale_authority,
+            None,
+            TOKEN_DECIMALS,
+        )
+        .unwrap(),
+    ];
+    rpc.create_and_send_transaction(&instructions, &payer.pubkey(), &[payer, &mint_keypair])
+        .await
+        .unwrap();
+    create_token_pool(rpc, payer, &mint_keypair.pubkey()).await;
+    mint_keypair.pubkey()
+}
+
+pub fn compressed_token_pool(mint: &Pubkey) -> Pubkey {
+    spl_interface::get_spl_interface_pda(mint)
+}
+
+pub async fn launch_token_compressed(
+    rpc: &mut LightProgramTest,
+    creator: &Keypair,
+    token_mint: Pubkey,
+    args: LaunchArgs,
+) -> Result<Signature, RpcError> {
+    let instruction = launch_token_compressed_instruction(
+        rpc,
+        &creator.pubkey(),
+        token_mint,
+        args,
+        COMPRESSED_SALE_ADDRESS_TREE,
+    )
+    .await?;
+    send(rpc, instruction, creator, &[]).await
+}
+
+/// Builds launch_token_compressed against `address_tree`. Only the sale address
+/// tree gets a real proof; any other tree is packed with an empty one.
+pub async fn launch_token_compressed_instruction(
+    rpc: &mut LightProgramTest,
+    creator: &Pubkey,
+    token_mint: Pubkey,
+    args: LaunchArgs,
+    address_tree: Pubkey,
+) -> Result<Instruction, RpcError> {
+    let mut remaining_accounts = PackedAccounts::default();
+    remaining_accounts
+        .add_system_accounts(SystemAccountMetaConfig::new(solana_launchpad::ID))
+        .unwrap();
+
+    let (proof, address_tree_info) = if address_tree == COMPRESSED_SALE_ADDRESS_TREE {
+        let address = CompressedTokenSale::derive_address(&token_mint);
+        let address_tree_info = rpc.get_address_tree_v1();
+        let proof = rpc
+            .get_validity_proof(
+                vec![],
+                vec![AddressWithTree {
+                    address,
+                    tree: address_tree_info.tree,
+                }],
+                None,
+            )
+            .await?
+            .value;
+        let packed_tree_infos = proof.pack_tree_infos(&mut remaining_accounts);
+        (proof.proof, packed_tree_infos.address_trees[0])
+    } else {
+        let index = remaining_accounts.insert_or_get(address_tree);
+        (
+            ValidityProof::default(),
+            PackedAddressTreeInfo {
+                address_merkle_tree_pubkey_index: index,
+                address_queue_pubkey_index: index,
+                root_index: 0,
+            },
+        )
+    };
+    let output_state_tree_index = rpc
+        .get_random_state_tree_info()?
+        .pack_output_tree_index(&mut remaining_accounts)
+        .unwrap();
+    let (remaining_metas, _, _) = remaining_accounts.to_account_metas();
+    let quote_mint_entry = launch_quote_mint(rpc, &args)
+        .await
+        .as_ref()
+        .map(quote_mint_entry_pda);
+
+    Ok(Instruction {
+        program_id: solana_launchpad::ID,
+        accounts: [
+            solana_launchpad::accounts::LaunchTokenCompressed {
+                creator: *creator,
+                token_mint,
+                sale_authority: sale_authority_pda(&token_mint),
+                token_pool: compressed_token_pool(&token_mint),
+                compressed_token_program: COMPRESSED_TOKEN_PROGRAM_ID,
+                compressed_token_cpi_authority: COMPRESSED_TOKEN_CPI_AUTHORITY,
+                quote_mint_entry,
+                app_state: app_state_pda(),
+                token_program: spl_token::ID,
+                system_program: solana_sdk::system_program::ID,
+            }
+            .to_account_metas(None),
+            remaining_metas,
+        ]
+        .concat(),
+        data: solana_launchpad::instruction::LaunchTokenCompressed {
+            proof,
+            address_tree_info,
+            output_state_tree_index,
+            name: args.name,
+            symbol: args.symbol,
+            supply: args.supply,
+            price_per_token: args.price_per_token,
+            limit_per_mint: args.limit_per_mint,
+            metadata_id: args.metadata_id,
+            params: args.params,
+        }
+        .data(),
+    })
+}
+
+pub async fn get_compressed_sale(
+    rpc: &mut LightProgramTest,
+    token_mint: &Pubkey,
+) -> (CompressedAccount, CompressedTokenSale) {
+    let address = CompressedTokenSale::derive_address(token_mint);
+    let account = rpc
+        .get_compressed_account(address, None)
+        .await
+        .unwrap()
+        .value
+        .unwrap();
+    let sale =
+        CompressedTokenSale::deserialize(&mut &account.data.as_ref().unwrap().data[..]).unwrap();
+    (account, sale)
+}
+
+pub async fn compressed_token_balance(
+    rpc: &mut LightProgramTest,
+    owner: &Pubkey,
+    mint: &Pubkey,
+) -> u64 {
+    rpc.get_compressed_token_accounts_by_owner(
+        owner,
+        Some(GetCompressedTokenAccountsByOwnerOrDelegateOptions::new(
+            Some(*mint),
+        )),
+        None,
+    )
+    .await
+    .unwrap()
+    .value
+    .items
+    .iter()
+    .map(|account| account.token.amount)
+    .sum()
+}
+
+/// Send `amount` of `owner`'s compressed tokens of `mint` to `recipient`,
+/// spending all of `owner`'s accounts and keeping the change
+pub async fn transfer_compressed(
+    rpc: &mut LightProgramTest,
+    owner: &Keypair,
+    mint: &Pubkey,
+    recipient: &Pubkey,
+    amount: u64,
+) -> Result<Signature, RpcError> {
+    let token_accounts = rpc
+        .get_compressed_token_accounts_by_owner(
+            &owner.pubkey(),
+            Some(GetCompressedTokenAccountsByOwnerOrDelegateOptions::new(
+                Some(*mint),
+            )),
+            None,
+        )
+        .await
+        .unwrap()
+        .value
+        .items;
+    let proof = rpc
+        .get_validity_proof(
+            token_accounts
+                .iter()
+                .map(|account| account.account.hash)
+                .collect(),
+            vec![],
+            None,
+        )
+        .await
+        .unwrap()
+        .value;
+
+    // Tree indices are relative to the tree accounts alone, as in the program
+    let mut tree_accounts = PackedAccounts::default();
+    let trees = proof
+        .pack_tree_infos(&mut tree_accounts)
+        .state_trees
+        .unwrap();
+    let inputs = token_accounts
+        .iter()
+        .zip(trees.packed_tree_infos)
+        .map(|(account, packed_tree_info)| TokenAccountMeta {
+            amount: account.token.amount,
+            delegate_index: None,
+            packed_tree_info,
+            lamports: None,
+            tlv: None,
+        })
+        .collect();
+    let mut sender = CTokenAccount::new(*mint, owner.pubkey(), inputs, trees.output_tree_index);
+    let recipient_account = sender.transfer(recipient, amount, None).unwrap();
+    let (tree_metas, _, _) = tree_accounts.to_account_metas();
+
+    let instruction = create_transfer_instruction_raw(
+        *mint,
+        vec![recipient_account, sender],
+        proof.proof,
+        TransferConfig {
+            filter_zero_amount_outputs: true,
+            ..Default::default()
+        },
+        TokenAccountsMetaConfig::new(owner.pubkey(), owner.pubkey()),
+        tree_metas.iter().map(|meta| meta.pubkey).collect(),
+    )
+    .unwrap();
+    send(rpc, instruction, owner, &[]).await
+}
+
+/// The buyer's compressed record for the sale of `token_mint`, if created
+pub async fn get_buyer_record(
+    rpc: &mut LightProgramTest,
+    token_mint: &Pubkey,
+    buyer: &Pubkey,
+) -> Option<(CompressedAccount, BuyerRecord)> {
+    let address = BuyerRecord::derive_address(token_mint, buyer);
+    let account = rpc
+        .get_compressed_account(address, None)
+        .await
+        .ok()?
+        .value?;
+    let record = BuyerRecord::deserialize(&mut &account.data.as_ref().unwrap().data[..]).unwrap();
+    Some((account, record))
+}
+
+/// The referrer's compressed record of purchases in `quote_mint`, if created
+pub async fn get_referrer_record(
+    rpc: &mut LightProgramTest,
+    referrer: &Pubkey,
+    quote_mint: &Pubkey,
+) -> Option<(CompressedAccount, ReferrerRecord)> {
+    let address = ReferrerRecord::derive_address(referrer, quote_mint);
+    let account = rpc
+        .get_compressed_account(address, None)
+        .await
+        .ok()?
+        .value?;
+    let record =
+        ReferrerRecord::deserialize(&mut &account.data.as_ref().unwrap().data[..]).unwrap();
+    Some((account, record))
+}
+
+/// Proof inputs for the buyer's record: its hash when it exists, otherwise its new address
+async fn buyer_record_proof_inputs(
+    rpc: &mut LightProgramTest,
+    token_mint: &Pubkey,
+    buyer: &Pubkey,
+) -> (
+    Option<(CompressedAccount, BuyerRecord)>,
+    Vec<AddressWithTree>,
+) {
+    match get_buyer_record(rpc, token_mint, buyer).await {
+        Some(existing) => (Some(existing), vec![]),
+        None => (
+            None,
+            vec![AddressWithTree {
+                address: BuyerRecord::derive_address(token_mint, buyer),
+                tree: rpc.get_address_tree_v1().tree,
+            }],
+        ),
+    }
+}
+
+/// Buyer record inputs for a standard buy
+pub async fn buyer_record_inputs(
+    rpc: &mut LightProgramTest,
+    token_mint: &Pubkey,
+    buyer: &Pubkey,
+) -> BuyerRecordInputs {
+    let (existing, new_addresses) = buyer_record_proof_inputs(rpc, token_mint, buyer).await;
+    let hashes = existing.iter().map(|(account, _)| account.hash).collect();
+    let proof = rpc
+        .get_validity_proof(hashes, new_addresses, None)
+        .await
+        .unwrap()
+        .value;
+
+    let mut remaining_accounts = PackedAccounts::default();
+    remaining_accounts
+        .add_system_accounts(SystemAccountMetaConfig::new(solana_launchpad::ID))
+        .unwrap();
+    let packed_tree_infos = proof.pack_tree_infos(&mut remaining_accounts);
+    let record = match existing {
+        Some((account, current)) => {
+            let state_trees = packed_tree_infos.state_trees.unwrap();
+            BuyerRecordInput::Existing {
+                current,
+                account_meta: CompressedAccountMeta {
+                    tree_info: state_trees.packed_tree_infos[0],
+                    address: account.address.unwrap(),
+                    output_state_tree_index: state_trees.output_tree_index,
+                },
+            }
+        }
+        None => BuyerRecordInput::New {
+            address_tree_info: packed_tree_infos.address_trees[0],
+            output_state_tree_index: rpc
+                .get_random_state_tree_info()
+                .unwrap()
+                .pack_output_tree_index(&mut remaining_accounts)
+                .unwrap(),
+        },
+    };
+    let (remaining_metas, _, _) = remaining_accounts.to_account_metas();
+
+    BuyerRecordInputs {
+        proof: proof.proof,
+        record,
+        remaining_accounts: remaining_metas,
+    }
+}
+
+/// Referrer record inputs for a standard buy of `token_mint` in `quote_mint`,
+/// with `buyer`'s record for sales that track wallets. The proof lists the
+/// buyer's record before the referrer's, as the program adds them.
+pub async fn referral_inputs(
+    rpc: &mut LightProgramTest,
+    token_mint: &Pubkey,
+    buyer: Option<&Pubkey>,
+    referrer: &Pubkey,
+    quote_mint: &Pubkey,
+) -> ReferralInputs {
+    let (existing_buyer, mut new_addresses) = match buyer {
+        Some(buyer) => buyer_record_proof_inputs(rpc, token_mint, buyer).await,
+        None => (None, vec![]),
+    };
+    let existing_referrer = get_referrer_record(rpc, referrer, quote_mint).await;
+    if existing_referrer.is_none() {
+        new_addresses.push(AddressWithTree {
+            address: ReferrerRecord::derive_address(referrer, quote_mint),
+            tree: rpc.get_address_tree_v1().tree,
+        });
+    }
+    let hashes = existing_buyer
+        .iter()
+        .map(|(account, _)| account.hash)
+        .chain(existing_referrer.iter().map(|(account, _)| account.hash))
+        .collect();
+    let proof = rpc
+        .get_validity_proof(hashes, new_addresses, None)
+        .await
+        .unwrap()
+        .value;
+
+    let mut remaining_accounts = PackedAccounts::default();
+    remaining_accounts
+        .add_system_accounts(SystemAccountMetaConfig::new(solana_launchpad::ID))
+        .unwrap();
+    let packed_tree_infos = proof.pack_tree_infos(&mut remaining_accounts);
+    let output_state_tree_index = match &packed_tree_infos.state_trees {
+        Some(state_trees) => state_trees.output_tree_index,
+        None => rpc
+            .get_random_state_tree_info()
+            .unwrap()
+            .pack_output_tree_index(&mut remaining_accounts)
+            .unwrap(),
+    };
+    let mut state_tree_infos = packed_tree_infos
+        .state_trees
+        .iter()
+        .flat_map(|state_trees| state_trees.packed_tree_infos.clone());
+    let mut address_tree_infos = packed_tree_infos.address_trees.into_iter();
+
+    let buyer_record = match (buyer, existing_buyer) {
+        (None, _) => None,
+        (Some(_), Some((account, current))) => Some(BuyerRecordInput::Existing {
+            current,
+            account_meta: CompressedAccountMeta {
+                tree_info: state_tree_infos.next().unwrap(),
+                address: account.address.unwrap(),
+                output_state_tree_index,
+            },
+        }),
+        (Some(_), None) => Some(BuyerRecordInput::New {
+            address_tree_info: address_tree_infos.next().unwrap(),
+            output_state_tree_index,
+        }),
+    };
+    let referrer_record = match existing_referrer {
+        Some((account, current)) => ReferrerRecordInput::Existing {
+            current,
+            account_meta: CompressedAccountMeta {
+                tree_info: state_tree_infos.next().unwrap(),
+                address: account.address.unwrap(),
+                output_state_tree_index,
+            },
+        },
+        None => ReferrerRecordInput::New {
+            address_tree_info: address_tree_infos.next().unwrap(),
+            output_state_tree_index,
+        },
+    };
+    let (remaining_metas, _, _) = remaining_accounts.to_account_metas();
+
+    ReferralInputs {
+        proof: proof.proof,
+        buyer_record,
+        referrer_record,
+        remaining_accounts: remaining_metas,
+    }
+}
+
+/// Fetch the sale at `sale_mint`'s address and the sale_authority's compressed
+/// tokens of `token_mint`, packed for a buy or close instruction.
+pub async fn compressed_sale_inputs(
+    rpc: &mut LightProgramTest,
+    sale_mint: &Pubkey,
+    token_mint: &Pubkey,
+) -> CompressedSaleInputs {
+    compressed_sale_inputs_for_buyer(rpc, sale_mint, token_mint, None).await
+}
+
+/// Sale inputs whose proof also covers `buyer`'s record, for sales that track wallets
+pub async fn compressed_sale_inputs_for_buyer(
+    rpc: &mut LightProgramTest,
+    sale_mint: &Pubkey,
+    token_mint: &Pubkey,
+    buyer: Option<&Pubkey>,
+) -> CompressedSaleInputs {
+    compressed_inputs(
+        rpc,
+        sale_mint,
+        token_mint,
+        buyer,
+        &sale_authority_pda(token_mint),
+    )
+    .await
+}
+
+/// Sale and `buyer`'s record, plus the vesting_authority's compressed tokens,
+/// packed for claim_vested_compressed
+pub async fn vesting_claim_inputs(
+    rpc: &mut LightProgramTest,
+    token_mint: &Pubkey,
+    buyer: &Pubkey,
+) -> CompressedSaleInputs {
+    compressed_inputs(
+        rpc,
+        token_mint,
+        token_mint,
+        Some(buyer),
+        &vesting_authority_pda(token_mint),
+    )
+    .await
+}
+
+/// Sale inputs spending `token_owner`'s compressed tokens of `token_mint`
+async fn compressed_inputs(
+    rpc: &mut LightProgramTest,
+    sale_mint: &Pubkey,
+    token_mint: &Pubkey,
+    buyer: Option<&Pubkey>,
+    token_owner: &Pubkey,
+) -> CompressedSaleInputs {
+    let (sale_account, sale) = get_compressed_sale(rpc, sale_mint).await;
+    let (existing_record, new_addresses) = match buyer {
+        Some(buyer) => buyer_record_proof_inputs(rpc, sale_mint, buyer).await,
+        None => (None, vec![]),
+    };
+    let token_accounts = rpc
+        .get_compressed_token_accounts_by_owner(
+            token_owner,
+            Some(GetCompressedTokenAccountsByOwnerOrDelegateOptions::new(
+                Some(*token_mint),
+            )),
+            None,
+        )
+        .await
+        .unwrap()
+        .value
+        .items;
+
+    let hashes = std::iter::once(sale_account.hash)
+        .chain(existing_record.iter().map(|(account, _)| account.hash))
+        .collect();
+    let proof = rpc
+        .get_validity_proof(hashes, new_addresses, None)
+        .await
+        .unwrap()
+        .value;
+    let token_proof = rpc
+        .get_validity_proof(
+            token_accounts
+                .iter()
+                .map(|account| account.account.hash)
+                .collect(),
+            vec![],
+            None,
+        )
+        .await
+        .unwrap()
+        .value;
+
+    let mut remaining_accounts = PackedAccounts::default();
+    remaining_accounts
+        .add_system_accounts(SystemAccountMetaConfig::new(solana_launchpad::ID))
+        .unwrap();
+    let sale_tree_infos = proof.pack_tree_infos(&mut remaining_accounts);
+    let sale_trees = sale_tree_infos.state_trees.unwrap();
+    let token_trees = token_proof
+        .pack_tree_infos(&mut remaining_accounts)
+        .state_trees
+        .unwrap();
+    let (remaining_metas, _, _) = remaining_accounts.to_account_metas();
+
+    let buyer_record = match (buyer, existing_record) {
+        (None, _) => None,
+        (Some(_), Some((account, current))) => Some(BuyerRecordInput::Existing {
+            current,
+            account_meta: CompressedAccountMeta {
+                tree_info: sale_trees.packed_tree_infos[1],
+                address: account.address.unwrap(),
+                output_state_tree_index: sale_trees.output_tree_index,
+            },
+        }),
+        (Some(_), None) => Some(BuyerRecordInput::New {
+            address_tree_info: sale_tree_infos.address_trees[0],
+            output_state_tree_index: sale_trees.output_tree_index,
+        }),
+    };
+
+    let sale_token_accounts = token_accounts
+        .iter()
+        .zip(token_trees.packed_tree_infos)
+        .map(|(account, packed_tree_info)| TokenAccountMeta {
+            amount: account.token.amount,
+            delegate_index: None,
+            packed_tree_info,
+            lamports: None,
+            tlv: None,
+        })
+        .collect();
+
+    CompressedSaleInputs {
+        sale,
+        account_meta: CompressedAccountMeta {
+            tree_info: sale_trees.packed_tree_infos[0],
+            address: sale_account.address.unwrap(),
+            output_state_tree_index: sale_trees.output_tree_index,
+        },
+        proof: proof.proof,
+        token_proof: token_proof.proof,
+        sale_token_accounts,
+        token_output_tree_index: token_trees.output_tree_index,
+        buyer_record,
+        remaining_accounts: remaining_metas,
+    }
+}
+
+pub fn buy_tokens_compressed_instruction(
+    env: &TestEnv,
+    buyer: &Pubkey,
+    buyer_usdc_account: Pubkey,
+    creator_usdc_account: Pubkey,
+    token_mint: Pubkey,
+    inputs: CompressedSaleInputs,
+    usdc_amount: u64,
+) -> Instruction {
+    buy_tokens_compressed_instruction_with(
+        env,
+        buyer,
+        buyer_usdc_account,
+        creator_usdc_account,
+        token_mint,
+        inputs,
+        usdc_amount,
+        0,
+        None,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn buy_tokens_compressed_instruction_with(
+    env: &TestEnv,
+    buyer: &Pubkey,
+    buyer_usdc_account: Pubkey,
+    creator_usdc_account: Pubkey,
+    token_mint: Pubkey,
+    inputs: CompressedSaleInputs,
+    usdc_amount: u64,
+    min_tokens_out: u64,
+    allowlist: Option<AllowlistProof>,
+) -> Instruction {
+    Instruction {
+        program_id: solana_launchpad::ID,
+        accounts: [
+            solana_launchpad::accounts::BuyTokensCompressed {
+                buyer: *buyer,
+                buyer_usdc_account: Some(buyer_usdc_account),
+                creator_usdc_account: Some(creator_usdc_account),
+                creator: None,
+                fee_recipient: None,
+                fee_recipient_usdc_account: Some(env.fee_recipient_usdc_account),
+                referrer: None,
+                referrer_usdc_account: None,
+                program_usdc_account: Some(env.program_usdc_account),
+                token_mint,
+                app_state: env.app_state,
+                quote_mint_entry: Some(quote_mint_entry_pda(&env.usdc_mint)),
+                program_authority: env.program_authority,
+                sale_authority: sale_authority_pda(&token_mint),
+                vesting_authority: vesting_authority_pda(&token_mint),
+                compressed_token_program: COMPRESSED_TOKEN_PROGRAM_ID,
+                compressed_token_cpi_authority: COMPRESSED_TOKEN_CPI_AUTHORITY,
+                token_program: spl_token::ID,
+                system_program: solana_sdk::system_program::ID,
+            }
+            .to_account_metas(None),
+            inputs.remaining_accounts,
+        ]
+        .concat(),
+        data: solana_launchpad::instruction::BuyTokensCompressed {
+            proof: inputs.proof,
+            current_sale: inputs.sale,
+            account_meta: inputs.account_meta,
+            usdc_amount,
+            min_tokens_out,
+            allowlist,
+            buyer_record: inputs.buyer_record,
+            referrer_record: None,
+            token_proof: inputs.token_proof,
+            sale_token_accounts: inputs.sale_token_accounts,
+            token_output_tree_index: inputs.token_output_tree_index,
+        }
+        .data(),
+    }
+}
+
+/// Buy exactly `token_amount` compressed tokens for at most `max_usdc`
+#[allow(clippy::too_many_arguments)]
+pub fn buy_exact_tokens_compressed_instruction(
+    env: &TestEnv,
+    buyer: &Pubkey,
+    buyer_usdc_account: Pubkey,
+    creator_usdc_account: Pubkey,
+    token_mint: Pubkey,
+    inputs: CompressedSaleInputs,
+    token_amount: u64,
+    max_usdc: u64,
+) -> Instruction {
+    Instruction {
+        program_id: solana_launchpad::ID,
+        accounts: [
+            solana_launchpad::accounts::BuyTokensCompressed {
+                buyer: *buyer,
+                buyer_usdc_account: Some(buyer_usdc_account),
+                creator_usdc_account: Some(creator_usdc_account),
+                creator: None,
+                fee_recipient: None,
+                fee_recipient_usdc_account: Some(env.fee_recipient_usdc_account),
+                referrer: None,
+                referrer_usdc_account: None,
+                program_usdc_account: Some(env.program_usdc_account),
+                token_mint,
+                app_state: env.app_state,
+                quote_mint_entry: Some(quote_mint_entry_pda(&env.usdc_mint)),
+                program_authority: env.program_authority,
+                sale_authority: sale_authority_pda(&token_mint),
+                vesting_authority: vesting_authority_pda(&token_mint),
+                compressed_token_program: COMPRESSED_TOKEN_PROGRAM_ID,
+                compressed_token_cpi_authority: COMPRESSED_TOKEN_CPI_AUTHORITY,
+                token_program: spl_token::ID,
+                system_program: solana_sdk::system_program::ID,
+            }
+            .to_account_metas(None),
+            inputs.remaining_accounts,
+        ]
+        .concat(),
+        data: solana_launchpad::instruction::BuyExactTokensCompressed {
+            proof: inputs.proof,
+            current_sale: inputs.sale,
+            account_meta: inputs.account_meta,
+            token_amount,
+            max_usdc,
+            allowlist: None,
+            buyer_record: inputs.buyer_record,
+            referrer_record: None,
+            token_proof: inputs.token_proof,
+            sale_token_accounts: inputs.sale_token_accounts,
+            token_output_tree_index: inputs.token_output_tree_index,
+        }
+        .data(),
+    }
+}
+
+/// Buy from a compressed SOL sale, paying `lamports` at most straight to the
+/// creator and fee recipient wallets
+pub fn buy_tokens_compressed_sol_instruction(
+    env: &TestEnv,
+    buyer: &Pubkey,
+    creator: Pubkey,
+    token_mint: Pubkey,
+    inputs: CompressedSaleInputs,
+    lamports: u64,
+) -> Instruction {
+    Instruction {
+        program_id: solana_launchpad::ID,
+        accounts: [
+            solana_launchpad::accounts::BuyTokensCompressed {
+                buyer: *buyer,
+                buyer_usdc_account: None,
+                creator_usdc_account: None,
+                creator: Some(creator),
+                fee_recipient: Some(env.owner.pubkey()),
+                fee_recipient_usdc_account: None,
+                referrer: None,
+                referrer_usdc_account: None,
+                program_usdc_account: None,
+                token_mint,
+                app_state: env.app_state,
+                quote_mint_entry: None,
+                program_authority: env.program_authority,
+                sale_authority: sale_authority_pda(&token_mint),
+                vesting_authority: vesting_authority_pda(&token_mint),
+                compressed_token_program: COMPRESSED_TOKEN_PROGRAM_ID,
+                compressed_token_cpi_authority: COMPRESSED_TOKEN_CPI_AUTHORITY,
+                token_program: spl_token::ID,
+                system_program: solana_sdk::system_program::ID,
+            }
+            .to_account_metas(None),
+            inputs.remaining_accounts,
+        ]
+        .concat(),
+        data: solana_launchpad::instruction::BuyTokensCompressed {
+            proof: inputs.proof,
+            current_sale: inputs.sale,
+            account_meta: inputs.account_meta,
+            usdc_amount: lamports,
+            min_tokens_out: 0,
+            allowlist: None,
+            buyer_record: inputs.buyer_record,
+            referrer_record: None,
+            token_proof: inputs.token_proof,
+            sale_token_accounts: inputs.sale_token_accounts,
+            token_output_tree_index: inputs.token_output_tree_index,
+        }
+        .data(),
+    }
+}
+
+/// claim_vested_compressed with inputs from `vesting_claim_inputs`
+pub fn claim_vested_compressed_instruction(
+    buyer: &Pubkey,
+    token_mint: Pubkey,
+    inputs: CompressedSaleInputs,
+) -> Instruction {
+    let Some(BuyerRecordInput::Existing {
+        current,
+        account_meta,
+    }) = inputs.buyer_record
+    else {
+        panic!("buyer has no record to claim");
+    };
+    Instruction {
+        program_id: solana_launchpad::ID,
+        accounts: [
+            solana_launchpad::accounts::ClaimVestedCompressed {
+                buyer: *buyer,
+                token_mint,
+                vesting_authority: vesting_authority_pda(&token_mint),
+                compressed_token_program: COMPRESSED_TOKEN_PROGRAM_ID,
+                compressed_token_cpi_authority: COMPRESSED_TOKEN_CPI_AUTHORITY,
+            }
+            .to_account_metas(None),
+            inputs.remaining_accounts,
+        ]
+        .concat(),
+        data: solana_launchpad::instruction::ClaimVestedCompressed {
+            proof: inputs.proof,
+            current_sale: inputs.sale,
+            sale_account_meta: inputs.account_meta,
+            current,
+            account_meta,
+            token_proof: inputs.token_proof,
+            vesting_token_accounts: inputs.sale_token_accounts,
+            token_output_tree_index: inputs.token_output_tree_index,
+        }
+        .data(),
+    }
+}
+
+pub fn close_sale_compressed_instruction(
+    creator: &Pubkey,
+    token_mint: Pubkey,
+    inputs: CompressedSaleInputs,
+) -> Instruction {
+    Instruction {
+        program_id: solana_launchpad::ID,
+        accounts: [
+            solana_launchpad::accounts::CloseSaleCompressed {
+                creator: *creator,
+                token_mint,
+                sale_authority: sale_authority_pda(&token_mint),
+                compressed_token_program: COMPRESSED_TOKEN_PROGRAM_ID,
+                compressed_token_cpi_authority: COMPRESSED_TOKEN_CPI_AUTHORITY,
+            }
+            .to_account_metas(None),
+            inputs.remaining_accounts,
+        ]
+        .concat(),
+        data: solana_launchpad::instruction::CloseSaleCompressed {
+            proof: inputs.proof,
+            current_sale: inputs.sale,
+            account_meta: inputs.account_meta,
+            token_proof: inputs.token_proof,
+            sale_token_accounts: inputs.sale_token_accounts,
+            token_output_tree_index: inputs.token_output_tree_index,
+        }
+        .data(),
+    }
+}
+
+pub fn finalize_sale_compressed_instruction(
+    payer: &Pubkey,
+    token_mint: Pubkey,
+    inputs: CompressedSaleInputs,
+) -> Instruction {
+    Instruction {
+        program_id: solana_launchpad::ID,
+        accounts: [
+            solana_launchpad::accounts::FinalizeSaleCompressed {
+                payer: *payer,
+                token_mint,
+                sale_authority: sale_authority_pda(&token_mint),
+                compressed_token_program: COMPRESSED_TOKEN_PROGRAM_ID,
+                compressed_token_cpi_authority: COMPRESSED_TOKEN_CPI_AUTHORITY,
+            }
+            .to_account_metas(None),
+            inputs.remaining_accounts,
+        ]
+        .concat(),
+        data: solana_launchpad::instruction::FinalizeSaleCompressed {
+            proof: inputs.proof,
+            current_sale: inputs.sale,
+            account_meta: inputs.account_meta,
+            token_proof: inputs.token_proof,
+            sale_token_accounts: inputs.sale_token_accounts,
+            token_output_tree_index: inputs.token_output_tree_index,
+        }
+        .data(),
+    }
+}
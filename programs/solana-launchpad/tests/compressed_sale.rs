@@ -0,0 +1,392 @@
+#![cfg(feature = "test-sbf")]
+
+mod common;
+
+use common::*;
+use light_program_test::utils::assert::assert_rpc_error;
+use solana_launchpad::ErrorCode;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+
+const SUPPLY: u64 = 1_000_000_000_000;
+const PRICE: u64 = 1_000_000;
+const DAY: i64 = 86_400;
+
+async fn buy(
+    env: &mut TestEnv,
+    buyer: &Keypair,
+    buyer_usdc_account: Pubkey,
+    creator_usdc_account: Pubkey,
+    token_mint: Pubkey,
+    usdc_amount: u64,
+) -> Result<solana_sdk::signature::Signature, light_program_test::RpcError> {
+    let inputs = compressed_sale_inputs(&mut env.rpc, &token_mint, &token_mint).await;
+    let instruction = buy_tokens_compressed_instruction(
+        env,
+        &buyer.pubkey(),
+        buyer_usdc_account,
+        creator_usdc_account,
+        token_mint,
+        inputs,
+        usdc_amount,
+    );
+    send(&mut env.rpc, instruction, buyer, &[]).await
+}
+
+async fn close(
+    env: &mut TestEnv,
+    creator: &Keypair,
+    token_mint: Pubkey,
+) -> Result<solana_sdk::signature::Signature, light_program_test::RpcError> {
+    let inputs = compressed_sale_inputs(&mut env.rpc, &token_mint, &token_mint).await;
+    let instruction = close_sale_compressed_instruction(&creator.pubkey(), token_mint, inputs);
+    send(&mut env.rpc, instruction, creator, &[]).await
+}
+
+#[tokio::test]
+async fn test_compressed_sale_lifecycle() {
+    let mut env = setup().await;
+    let (creator, token_mint, creator_usdc_account) =
+        launch_compressed_sale(&mut env, LaunchArgs::new(SUPPLY, PRICE, 0)).await;
+    let sale_authority = sale_authority_pda(&token_mint);
+
+    let (_, sale) = get_compressed_sale(&mut env.rpc, &token_mint).await;
+    assert_eq!(sale.creator, creator.pubkey());
+    assert_eq!(sale.token_mint, token_mint);
+    assert_eq!(sale.price_per_token, PRICE);
+    assert_eq!(sale.supply_for_sale, SUPPLY);
+    assert_eq!(sale.tokens_sold, 0);
+    assert_eq!(sale.decimals, TOKEN_DECIMALS);
+    assert_eq!(sale.sale_authority, sale_authority);
+    assert_eq!(sale.platform_fee_bps, PLATFORM_FEE_BPS);
+    assert!(sale.active);
+    assert_eq!(
+        compressed_token_balance(&mut env.rpc, &sale_authority, &token_mint).await,
+        SUPPLY
+    );
+
+    // 1 USDC at 1 USDC per token buys one whole token
+    let (buyer, buyer_usdc_account) = create_buyer(&mut env, 10_000_000).await;
+    buy(
+        &mut env,
+        &buyer,
+        buyer_usdc_account,
+        creator_usdc_account,
+        token_mint,
+        1_000_000,
+    )
+    .await
+    .unwrap();
+
+    let tokens_bought = 1_000_000_000;
+    let fee = 1_000_000 * PLATFORM_FEE_BPS as u64 / 10_000;
+    assert_eq!(
+        compressed_token_balance(&mut env.rpc, &buyer.pubkey(), &token_mint).await,
+        tokens_bought
+    );
+    assert_eq!(
+        compressed_token_balance(&mut env.rpc, &sale_authority, &token_mint).await,
+        SUPPLY - tokens_bought
+    );
+    assert_eq!(
+        token_balance(&mut env.rpc, &buyer_usdc_account).await,
+        9_000_000
+    );
+    assert_eq!(
+        token_balance(&mut env.rpc, &env.fee_recipient_usdc_account).await,
+        fee
+    );
+    assert_eq!(
+        token_balance(&mut env.rpc, &creator_usdc_account).await,
+        1_000_000 - fee
+    );
+    let (_, sale) = get_compressed_sale(&mut env.rpc, &token_mint).await;
+    assert_eq!(sale.tokens_sold, tokens_bought);
+
+    // Closing returns the unsold supply to the creator
+    close(&mut env, &creator, token_mint).await.unwrap();
+
+    let (_, sale) = get_compressed_sale(&mut env.rpc, &token_mint).await;
+    assert!(!sale.active);
+    assert_eq!(
+        compressed_token_balance(&mut env.rpc, &creator.pubkey(), &token_mint).await,
+        SUPPLY - tokens_bought
+    );
+    assert_eq!(
+        compressed_token_balance(&mut env.rpc, &sale_authority, &token_mint).await,
+        0
+    );
+
+    let result = buy(
+        &mut env,
+        &buyer,
+        buyer_usdc_account,
+        creator_usdc_account,
+        token_mint,
+        1_000_000,
+    )
+    .await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::SaleNotActive)).unwrap();
+
+    let result = close(&mut env, &creator, token_mint).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::AlreadyClosed)).unwrap();
+}
+
+#[tokio::test]
+async fn test_compressed_buy_charges_fee_at_launch() {
+    let mut env = setup().await;
+    let (_creator, token_mint, creator_usdc_account) =
+        launch_compressed_sale(&mut env, LaunchArgs::new(SUPPLY, PRICE, 0)).await;
+
+    // Raising the fee after launch does not change what the sale's buyers pay
+    let owner = env.owner.insecure_clone();
+    update_fee(&mut env.rpc, &owner, 1_000).await.unwrap();
+
+    let (buyer, buyer_usdc_account) = create_buyer(&mut env, 10_000_000).await;
+    buy(
+        &mut env,
+        &buyer,
+        buyer_usdc_account,
+        creator_usdc_account,
+        token_mint,
+        1_000_000,
+    )
+    .await
+    .unwrap();
+
+    let fee = 1_000_000 * PLATFORM_FEE_BPS as u64 / 10_000;
+    assert_eq!(
+        token_balance(&mut env.rpc, &env.fee_recipient_usdc_account).await,
+        fee
+    );
+    assert_eq!(
+        token_balance(&mut env.rpc, &creator_usdc_account).await,
+        1_000_000 - fee
+    );
+}
+
+#[tokio::test]
+async fn test_close_returns_balance_across_sale_token_accounts() {
+    let mut env = setup().await;
+    let (creator, token_mint, creator_usdc_account) =
+        launch_compressed_sale(&mut env, LaunchArgs::new(SUPPLY, PRICE, 0)).await;
+    let sale_authority = sale_authority_pda(&token_mint);
+    let (buyer, buyer_usdc_account) = create_buyer(&mut env, 10_000_000).await;
+    buy(
+        &mut env,
+        &buyer,
+        buyer_usdc_account,
+        creator_usdc_account,
+        token_mint,
+        2_000_000,
+    )
+    .await
+    .unwrap();
+
+    // Tokens sent back to the sale authority sit in a second account
+    let whole = 1_000_000_000;
+    transfer_compressed(&mut env.rpc, &buyer, &token_mint, &sale_authority, whole)
+        .await
+        .unwrap();
+    let inputs = compressed_sale_inputs(&mut env.rpc, &token_mint, &token_mint).await;
+    assert_eq!(inputs.sale_token_accounts.len(), 2);
+
+    // Leaving out the account with the unsold supply would strand it
+    let mut partial = compressed_sale_inputs(&mut env.rpc, &token_mint, &token_mint).await;
+    partial
+        .sale_token_accounts
+        .retain(|account| account.amount == whole);
+    let instruction = close_sale_compressed_instruction(&creator.pubkey(), token_mint, partial);
+    let result = send(&mut env.rpc, instruction, &creator, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::UnsoldSupplyMismatch)).unwrap();
+
+    let instruction = close_sale_compressed_instruction(&creator.pubkey(), token_mint, inputs);
+    send(&mut env.rpc, instruction, &creator, &[])
+        .await
+        .unwrap();
+    assert_eq!(
+        compressed_token_balance(&mut env.rpc, &creator.pubkey(), &token_mint).await,
+        SUPPLY - whole
+    );
+    assert_eq!(
+        compressed_token_balance(&mut env.rpc, &sale_authority, &token_mint).await,
+        0
+    );
+}
+
+#[tokio::test]
+async fn test_compressed_free_mint() {
+    let mut env = setup().await;
+    let limit = 5_000_000_000;
+    let (_creator, token_mint, creator_usdc_account) =
+        launch_compressed_sale(&mut env, LaunchArgs::new(SUPPLY, 0, limit)).await;
+    let (buyer, buyer_usdc_account) = create_buyer(&mut env, 1_000_000).await;
+
+    let result = buy(
+        &mut env,
+        &buyer,
+        buyer_usdc_account,
+        creator_usdc_account,
+        token_mint,
+        1,
+    )
+    .await;
+    assert_rpc_error(
+        result,
+        0,
+        error_code(ErrorCode::FreeMintRequiresZeroPayment),
+    )
+    .unwrap();
+
+    buy(
+        &mut env,
+        &buyer,
+        buyer_usdc_account,
+        creator_usdc_account,
+        token_mint,
+        0,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        compressed_token_balance(&mut env.rpc, &buyer.pubkey(), &token_mint).await,
+        limit
+    );
+    assert_eq!(
+        token_balance(&mut env.rpc, &buyer_usdc_account).await,
+        1_000_000
+    );
+}
+
+#[tokio::test]
+async fn test_compressed_buy_validation() {
+    let mut env = setup().await;
+    let supply = 3_000_000_000;
+    let limit = 2_000_000_000;
+    let (_creator, token_mint, creator_usdc_account) =
+        launch_compressed_sale(&mut env, LaunchArgs::new(supply, PRICE, limit)).await;
+    let (buyer, buyer_usdc_account) = create_buyer(&mut env, 10_000_000).await;
+
+    let cases = [
+        (0, ErrorCode::AmountMustBePositive),
+        (3_000_000, ErrorCode::ExceedsMintLimit),
+    ];
+    for (usdc_amount, expected) in cases {
+        let result = buy(
+            &mut env,
+            &buyer,
+            buyer_usdc_account,
+            creator_usdc_account,
+            token_mint,
+            usdc_amount,
+        )
+        .await;
+        assert_rpc_error(result, 0, error_code(expected)).unwrap();
+    }
+
+    // 2 of 3 tokens sold, so a second 2 token purchase overruns the supply
+    buy(
+        &mut env,
+        &buyer,
+        buyer_usdc_account,
+        creator_usdc_account,
+        token_mint,
+        2_000_000,
+    )
+    .await
+    .unwrap();
+    let result = buy(
+        &mut env,
+        &buyer,
+        buyer_usdc_account,
+        creator_usdc_account,
+        token_mint,
+        2_000_000,
+    )
+    .await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::InsufficientSupply)).unwrap();
+
+    let (_, sale) = get_compressed_sale(&mut env.rpc, &token_mint).await;
+    assert_eq!(sale.tokens_sold, 2_000_000_000);
+    assert!(sale.active);
+}
+
+#[tokio::test]
+async fn test_compressed_launch_validation() {
+    let mut env = setup().await;
+    let creator = funded_keypair(&mut env.rpc).await;
+    let token_mint = create_compressed_sale_mint(&mut env.rpc, &creator).await;
+    let now = current_timestamp(&env.rpc);
+
+    let cases = [
+        (
+            LaunchArgs {
+                name: String::new(),
+                ..LaunchArgs::new(SUPPLY, PRICE, 0)
+            },
+            ErrorCode::InvalidNameLength,
+        ),
+        (
+            LaunchArgs {
+                symbol: "S".repeat(11),
+                ..LaunchArgs::new(SUPPLY, PRICE, 0)
+            },
+            ErrorCode::InvalidSymbolLength,
+        ),
+        (
+            LaunchArgs {
+                metadata_id: "m".repeat(101),
+                ..LaunchArgs::new(SUPPLY, PRICE, 0)
+            },
+            ErrorCode::MetadataIdTooLong,
+        ),
+        (LaunchArgs::new(0, PRICE, 0), ErrorCode::InvalidSupply),
+        (
+            LaunchArgs::new(SUPPLY, 0, 0),
+            ErrorCode::FreeMintRequiresLimit,
+        ),
+        (LaunchArgs::new(SUPPLY, 999, 0), ErrorCode::PriceTooLow),
+        (
+            LaunchArgs::new(SUPPLY, PRICE, SUPPLY + 1),
+            ErrorCode::LimitExceedsSupply,
+        ),
+        // Soft caps and vested proceeds need a reserve compressed sales lack
+        (
+            LaunchArgs::new(SUPPLY, PRICE, 0)
+                .with_window(None, Some(now + DAY))
+                .with_soft_cap(PRICE),
+            ErrorCode::InvalidSoftCap,
+        ),
+        (
+            LaunchArgs::new(SUPPLY, PRICE, 0).with_proceeds_vesting(now, now, now + DAY),
+            ErrorCode::InvalidProceedsVesting,
+        ),
+    ];
+    for (args, expected) in cases {
+        let result = launch_token_compressed(&mut env.rpc, &creator, token_mint, args).await;
+        assert_rpc_error(result, 0, error_code(expected)).unwrap();
+    }
+}
+
+#[tokio::test]
+async fn test_compressed_launch_rejects_other_address_tree() {
+    let mut env = setup().await;
+    let creator = funded_keypair(&mut env.rpc).await;
+    let token_mint = create_compressed_sale_mint(&mut env.rpc, &creator).await;
+
+    let other_tree = env.rpc.get_state_merkle_tree_account().merkle_tree;
+    let instruction = launch_token_compressed_instruction(
+        &mut env.rpc,
+        &creator.pubkey(),
+        token_mint,
+        LaunchArgs::new(SUPPLY, PRICE, 0),
+        other_tree,
+    )
+    .await
+    .unwrap();
+    let result = send(&mut env.rpc, instruction, &creator, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::InvalidAddressTree)).unwrap();
+}
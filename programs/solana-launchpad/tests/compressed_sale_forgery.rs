@@ -0,0 +1,127 @@
+#![cfg(feature = "test-sbf")]
+
+mod common;
+
+use common::*;
+use light_program_test::utils::assert::assert_rpc_error;
+use solana_launchpad::{CompressedTokenSale, ErrorCode};
+use solana_sdk::signature::Signer;
+
+const SUPPLY: u64 = 1_000_000_000_000;
+const PRICE: u64 = 1_000_000;
+
+#[tokio::test]
+async fn test_buy_rejects_forged_price() {
+    let mut env = setup().await;
+    let (_creator, token_mint, creator_usdc_account) =
+        launch_compressed_sale(&mut env, LaunchArgs::new(SUPPLY, PRICE, 0)).await;
+    let (buyer, buyer_usdc_account) = create_buyer(&mut env, 10_000_000).await;
+
+    let mut inputs = compressed_sale_inputs(&mut env.rpc, &token_mint, &token_mint).await;
+    // Claim tokens cost 1000x less than the launched price
+    inputs.sale.price_per_token = PRICE / 1_000;
+    let instruction = buy_tokens_compressed_instruction(
+        &env,
+        &buyer.pubkey(),
+        buyer_usdc_account,
+        creator_usdc_account,
+        token_mint,
+        inputs,
+        1_000_000,
+    );
+    let result = send(&mut env.rpc, instruction, &buyer, &[]).await;
+    assert!(result.is_err(), "forged price_per_token was accepted");
+
+    assert_eq!(
+        token_balance(&mut env.rpc, &buyer_usdc_account).await,
+        10_000_000
+    );
+    assert_eq!(
+        compressed_token_balance(&mut env.rpc, &buyer.pubkey(), &token_mint).await,
+        0
+    );
+    let (_, sale) = get_compressed_sale(&mut env.rpc, &token_mint).await;
+    assert_eq!(sale.price_per_token, PRICE);
+    assert_eq!(sale.tokens_sold, 0);
+}
+
+#[tokio::test]
+async fn test_buy_rejects_forged_creator() {
+    let mut env = setup().await;
+    let (_creator, token_mint, _creator_usdc_account) =
+        launch_compressed_sale(&mut env, LaunchArgs::new(SUPPLY, PRICE, 0)).await;
+    let (attacker, attacker_usdc_account) = create_buyer(&mut env, 10_000_000).await;
+
+    let mut inputs = compressed_sale_inputs(&mut env.rpc, &token_mint, &token_mint).await;
+    // Route the creator share back to the buyer
+    inputs.sale.creator = attacker.pubkey();
+    let instruction = buy_tokens_compressed_instruction(
+        &env,
+        &attacker.pubkey(),
+        attacker_usdc_account,
+        attacker_usdc_account,
+        token_mint,
+        inputs,
+        1_000_000,
+    );
+    let result = send(&mut env.rpc, instruction, &attacker, &[]).await;
+    assert!(result.is_err(), "forged creator was accepted");
+
+    assert_eq!(
+        token_balance(&mut env.rpc, &attacker_usdc_account).await,
+        10_000_000
+    );
+    let (_, sale) = get_compressed_sale(&mut env.rpc, &token_mint).await;
+    assert_ne!(sale.creator, attacker.pubkey());
+    assert_eq!(sale.tokens_sold, 0);
+}
+
+#[tokio::test]
+async fn test_buy_rejects_sale_from_another_mint() {
+    let mut env = setup().await;
+    let (_creator, token_mint, creator_usdc_account) =
+        launch_compressed_sale(&mut env, LaunchArgs::new(SUPPLY, PRICE, 0)).await;
+    let (_other_creator, other_mint, _) =
+        launch_compressed_sale(&mut env, LaunchArgs::new(SUPPLY, PRICE, 0)).await;
+    let (buyer, buyer_usdc_account) = create_buyer(&mut env, 10_000_000).await;
+
+    // Present the other sale's account as if it belonged to token_mint
+    let mut inputs = compressed_sale_inputs(&mut env.rpc, &other_mint, &token_mint).await;
+    inputs.sale.token_mint = token_mint;
+    assert_ne!(
+        inputs.account_meta.address,
+        CompressedTokenSale::derive_address(&token_mint)
+    );
+    let instruction = buy_tokens_compressed_instruction(
+        &env,
+        &buyer.pubkey(),
+        buyer_usdc_account,
+        creator_usdc_account,
+        token_mint,
+        inputs,
+        1_000_000,
+    );
+    let result = send(&mut env.rpc, instruction, &buyer, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::InvalidSaleAddress)).unwrap();
+}
+
+#[tokio::test]
+async fn test_close_rejects_forged_creator() {
+    let mut env = setup().await;
+    let (_creator, token_mint, _) =
+        launch_compressed_sale(&mut env, LaunchArgs::new(SUPPLY, PRICE, 0)).await;
+    let attacker = funded_keypair(&mut env.rpc).await;
+
+    let mut inputs = compressed_sale_inputs(&mut env.rpc, &token_mint, &token_mint).await;
+    inputs.sale.creator = attacker.pubkey();
+    let instruction = close_sale_compressed_instruction(&attacker.pubkey(), token_mint, inputs);
+    let result = send(&mut env.rpc, instruction, &attacker, &[]).await;
+    assert!(result.is_err(), "forged creator closed the sale");
+
+    assert_eq!(
+        compressed_token_balance(&mut env.rpc, &attacker.pubkey(), &token_mint).await,
+        0
+    );
+    let (_, sale) = get_compressed_sale(&mut env.rpc, &token_mint).await;
+    assert!(sale.active);
+}
@@ -0,0 +1,266 @@
+#![cfg(feature = "test-sbf")]
+
+mod common;
+
+use common::*;
+use light_program_test::utils::assert::assert_rpc_error;
+use solana_launchpad::ErrorCode;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+
+const SUPPLY: u64 = 1_000_000_000_000;
+const START_PRICE: u64 = 2_000_000;
+const FLOOR_PRICE: u64 = 1_000_000;
+const HOUR: i64 = 3_600;
+const WHOLE: u64 = 1_000_000_000;
+
+#[tokio::test]
+async fn test_launch_rejects_invalid_auction() {
+    let mut env = setup().await;
+    let creator = funded_keypair(&mut env.rpc).await;
+    let now = current_timestamp(&env.rpc);
+    let auction = |floor_price| {
+        LaunchArgs::new(SUPPLY, START_PRICE, 0)
+            .with_window(Some(now), Some(now + 10 * HOUR))
+            .with_auction(floor_price, false)
+    };
+
+    let cases = [
+        LaunchArgs::new(SUPPLY, START_PRICE, 0).with_auction(FLOOR_PRICE, false),
+        auction(FLOOR_PRICE).with_window(Some(now), None),
+        auction(FLOOR_PRICE).with_price_slope(1_000),
+        auction(START_PRICE),
+        auction(999),
+    ];
+    for args in cases {
+        let sale = create_standard_sale_mint(&mut env.rpc, &creator, TOKEN_DECIMALS).await;
+        let result = launch_token(&mut env.rpc, &creator, &sale, args.clone()).await;
+        assert_rpc_error(result, 0, error_code(ErrorCode::InvalidAuction)).unwrap();
+
+        let token_mint = create_compressed_sale_mint(&mut env.rpc, &creator).await;
+        let result = launch_token_compressed(&mut env.rpc, &creator, token_mint, args).await;
+        assert_rpc_error(result, 0, error_code(ErrorCode::InvalidAuction)).unwrap();
+    }
+
+    // Compressed sales cannot hold payments for uniform clearing refunds
+    let token_mint = create_compressed_sale_mint(&mut env.rpc, &creator).await;
+    let args = LaunchArgs::new(SUPPLY, START_PRICE, 0)
+        .with_window(Some(now), Some(now + 10 * HOUR))
+        .with_auction(FLOOR_PRICE, true);
+    let result = launch_token_compressed(&mut env.rpc, &creator, token_mint, args).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::InvalidAuction)).unwrap();
+}
+
+/// Launches a standard auction starting now and returns (creator, sale, creator USDC account)
+async fn launch_auction(
+    env: &mut TestEnv,
+    uniform_clearing: bool,
+) -> (Keypair, StandardSale, Pubkey) {
+    let now = current_timestamp(&env.rpc);
+    launch_sale(
+        env,
+        LaunchArgs::new(SUPPLY, START_PRICE, 0)
+            .with_window(Some(now), Some(now + 10 * HOUR))
+            .with_auction(FLOOR_PRICE, uniform_clearing),
+    )
+    .await
+}
+
+#[tokio::test]
+async fn test_standard_auction_price_decays() {
+    let mut env = setup().await;
+    let (_, sale, creator_usdc_account) = launch_auction(&mut env, false).await;
+    let (buyer, accounts) =
+        create_sale_buyer(&mut env, &sale, creator_usdc_account, 10_000_000).await;
+    let start_ts = current_timestamp(&env.rpc);
+
+    let instruction = buy_tokens_instruction(&env, &buyer.pubkey(), &sale, &accounts, 2_000_000);
+    send(&mut env.rpc, instruction, &buyer, &[]).await.unwrap();
+    assert_eq!(
+        token_balance(&mut env.rpc, &accounts.buyer_token_account).await,
+        WHOLE
+    );
+
+    // Halfway through, 1.5 USDC per token
+    warp_to_timestamp(&mut env.rpc, start_ts + 5 * HOUR);
+    let instruction = buy_tokens_instruction(&env, &buyer.pubkey(), &sale, &accounts, 3_000_000);
+    send(&mut env.rpc, instruction, &buyer, &[]).await.unwrap();
+    assert_eq!(
+        token_balance(&mut env.rpc, &accounts.buyer_token_account).await,
+        3 * WHOLE
+    );
+
+    // Without uniform clearing the creator is paid at once
+    let fee = 5_000_000 * PLATFORM_FEE_BPS as u64 / 10_000;
+    assert_eq!(
+        token_balance(&mut env.rpc, &creator_usdc_account).await,
+        5_000_000 - fee
+    );
+    let token_sale = get_token_sale(&mut env.rpc, &sale.token_mint).await;
+    assert_eq!(token_sale.clearing_price, 1_500_000);
+    assert_eq!(token_sale.usdc_reserve, 0);
+}
+
+#[tokio::test]
+async fn test_uniform_clearing_refunds_down_to_last_price() {
+    let mut env = setup().await;
+    let owner = env.owner.insecure_clone();
+    let (creator, sale, creator_usdc_account) = launch_auction(&mut env, true).await;
+    let (early, early_accounts) =
+        create_sale_buyer(&mut env, &sale, creator_usdc_account, 10_000_000).await;
+    let (late, late_accounts) =
+        create_sale_buyer(&mut env, &sale, creator_usdc_account, 10_000_000).await;
+    let start_ts = current_timestamp(&env.rpc);
+
+    // Uniform clearing tracks what each wallet paid
+    let instruction =
+        buy_tokens_instruction(&env, &early.pubkey(), &sale, &early_accounts, 4_000_000);
+    let result = send(&mut env.rpc, instruction, &early, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::BuyerRecordRequired)).unwrap();
+
+    // 2 tokens at 2 USDC, then 2 tokens at 1.5 USDC
+    buy_with_record(&mut env, &early, &sale, &early_accounts, 4_000_000)
+        .await
+        .unwrap();
+    warp_to_timestamp(&mut env.rpc, start_ts + 5 * HOUR);
+    buy_with_record(&mut env, &late, &sale, &late_accounts, 3_000_000)
+        .await
+        .unwrap();
+
+    // Payments are escrowed until the sale closes
+    assert_eq!(token_balance(&mut env.rpc, &creator_usdc_account).await, 0);
+    assert_eq!(
+        token_balance(&mut env.rpc, &env.fee_recipient_usdc_account).await,
+        0
+    );
+    let token_sale = get_token_sale(&mut env.rpc, &sale.token_mint).await;
+    assert_eq!(token_sale.usdc_reserve, 7_000_000);
+    assert_eq!(token_sale.clearing_price, 1_500_000);
+    let result = claim_refund(
+        &mut env,
+        RefundKind::Auction,
+        &early,
+        &sale,
+        &early_accounts,
+    )
+    .await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::SaleStillActive)).unwrap();
+
+    let creator_token_account =
+        create_token_account(&mut env.rpc, &owner, &sale.token_mint, &creator.pubkey()).await;
+    let instruction = close_sale_instruction(&creator.pubkey(), &sale, creator_token_account);
+    send(&mut env.rpc, instruction, &creator, &[])
+        .await
+        .unwrap();
+
+    // The creator settles 4 tokens at 1.5 USDC, less the platform fee
+    let instruction =
+        claim_reserve_instruction(&env, &creator.pubkey(), &sale, creator_usdc_account);
+    send(&mut env.rpc, instruction, &creator, &[])
+        .await
+        .unwrap();
+    let fee = 6_000_000 * PLATFORM_FEE_BPS as u64 / 10_000;
+    assert_eq!(
+        token_balance(&mut env.rpc, &creator_usdc_account).await,
+        6_000_000 - fee
+    );
+    assert_eq!(
+        token_balance(&mut env.rpc, &env.fee_recipient_usdc_account).await,
+        fee
+    );
+    let instruction =
+        claim_reserve_instruction(&env, &creator.pubkey(), &sale, creator_usdc_account);
+    let result = send(&mut env.rpc, instruction, &creator, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::AlreadyClaimed)).unwrap();
+
+    // The early buyer gets back 1 USDC, once; the late buyer paid the clearing price
+    claim_refund(
+        &mut env,
+        RefundKind::Auction,
+        &early,
+        &sale,
+        &early_accounts,
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        token_balance(&mut env.rpc, &early_accounts.buyer_usdc_account).await,
+        10_000_000 - 3_000_000
+    );
+    let (_, record) = get_buyer_record(&mut env.rpc, &sale.token_mint, &early.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(record.usdc_paid, 3_000_000);
+    for (buyer, accounts) in [(&early, &early_accounts), (&late, &late_accounts)] {
+        let result = claim_refund(&mut env, RefundKind::Auction, buyer, &sale, accounts).await;
+        assert_rpc_error(result, 0, error_code(ErrorCode::NoRefundDue)).unwrap();
+    }
+    assert_eq!(
+        get_token_sale(&mut env.rpc, &sale.token_mint)
+            .await
+            .usdc_reserve,
+        0
+    );
+}
+
+#[tokio::test]
+async fn test_fixed_price_sale_has_no_auction_refund() {
+    let mut env = setup().await;
+    let (_, sale, creator_usdc_account) = launch_sale(
+        &mut env,
+        LaunchArgs::new(SUPPLY, START_PRICE, 0).with_max_per_wallet(10 * WHOLE),
+    )
+    .await;
+    let (buyer, accounts) =
+        create_sale_buyer(&mut env, &sale, creator_usdc_account, 10_000_000).await;
+    buy_with_record(&mut env, &buyer, &sale, &accounts, 2_000_000)
+        .await
+        .unwrap();
+
+    let result = claim_refund(&mut env, RefundKind::Auction, &buyer, &sale, &accounts).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::NoAuctionRefund)).unwrap();
+}
+
+#[tokio::test]
+async fn test_compressed_auction_price_decays() {
+    let mut env = setup().await;
+    let owner = env.owner.insecure_clone();
+    let creator = funded_keypair(&mut env.rpc).await;
+    let now = current_timestamp(&env.rpc);
+    let token_mint = create_compressed_sale_mint(&mut env.rpc, &creator).await;
+    launch_token_compressed(
+        &mut env.rpc,
+        &creator,
+        token_mint,
+        LaunchArgs::new(SUPPLY, START_PRICE, 0)
+            .with_window(Some(now), Some(now + 10 * HOUR))
+            .with_auction(FLOOR_PRICE, false),
+    )
+    .await
+    .unwrap();
+    let creator_usdc_account =
+        create_token_account(&mut env.rpc, &owner, &env.usdc_mint, &creator.pubkey()).await;
+    let (buyer, buyer_usdc_account) = create_buyer(&mut env, 10_000_000).await;
+    let (_, sale) = get_compressed_sale(&mut env.rpc, &token_mint).await;
+    assert_eq!(sale.auction.unwrap().floor_price, FLOOR_PRICE);
+
+    // Halfway through, 1.5 USDC per token
+    warp_to_timestamp(&mut env.rpc, now + 5 * HOUR);
+    let inputs = compressed_sale_inputs(&mut env.rpc, &token_mint, &token_mint).await;
+    let instruction = buy_tokens_compressed_instruction(
+        &env,
+        &buyer.pubkey(),
+        buyer_usdc_account,
+        creator_usdc_account,
+        token_mint,
+        inputs,
+        3_000_000,
+    );
+    send(&mut env.rpc, instruction, &buyer, &[]).await.unwrap();
+    assert_eq!(
+        compressed_token_balance(&mut env.rpc, &buyer.pubkey(), &token_mint).await,
+        2 * WHOLE
+    );
+}
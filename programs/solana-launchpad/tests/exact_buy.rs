@@ -0,0 +1,212 @@
+#![cfg(feature = "test-sbf")]
+
+mod common;
+
+use common::*;
+use light_program_test::utils::assert::assert_rpc_error;
+use solana_launchpad::ErrorCode;
+use solana_sdk::signature::Signer;
+
+const SUPPLY: u64 = 1_000_000_000_000;
+const PRICE: u64 = 1_000_000;
+/// 0.001 USDC more per whole token sold
+const SLOPE: u64 = 1_000;
+const WHOLE: u64 = 1_000_000_000;
+
+#[tokio::test]
+async fn test_buy_exact_tokens_at_fixed_price() {
+    let mut env = setup().await;
+    let (sale, buyer, accounts) =
+        launch_sale_with_buyer(&mut env, LaunchArgs::new(SUPPLY, PRICE, 0), 10_000_000).await;
+
+    // 3 tokens cost 3 USDC
+    let instruction = buy_exact_tokens_instruction(
+        &env,
+        &buyer.pubkey(),
+        &sale,
+        &accounts,
+        3 * WHOLE,
+        2_999_999,
+    );
+    let result = send(&mut env.rpc, instruction, &buyer, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::MaxUsdcExceeded)).unwrap();
+
+    let instruction = buy_exact_tokens_instruction(
+        &env,
+        &buyer.pubkey(),
+        &sale,
+        &accounts,
+        3 * WHOLE,
+        5_000_000,
+    );
+    send(&mut env.rpc, instruction, &buyer, &[]).await.unwrap();
+
+    let fee = 3_000_000 * PLATFORM_FEE_BPS as u64 / 10_000;
+    assert_eq!(
+        token_balance(&mut env.rpc, &accounts.buyer_token_account).await,
+        3 * WHOLE
+    );
+    assert_eq!(
+        token_balance(&mut env.rpc, &accounts.buyer_usdc_account).await,
+        7_000_000
+    );
+    assert_eq!(
+        token_balance(&mut env.rpc, &accounts.creator_usdc_account).await,
+        3_000_000 - fee
+    );
+    assert_eq!(
+        token_balance(&mut env.rpc, &env.fee_recipient_usdc_account).await,
+        fee
+    );
+    let token_sale = get_token_sale(&mut env.rpc, &sale.token_mint).await;
+    assert_eq!(token_sale.tokens_sold, 3 * WHOLE);
+    assert_eq!(token_sale.usdc_raised, 3_000_000);
+}
+
+#[tokio::test]
+async fn test_buy_exact_tokens_on_curve() {
+    let mut env = setup().await;
+    let end_ts = current_timestamp(&env.rpc) + 3_600;
+    let (sale, buyer, accounts) = launch_sale_with_buyer(
+        &mut env,
+        LaunchArgs::new(SUPPLY, PRICE, 0)
+            .with_price_slope(SLOPE)
+            .with_window(None, Some(end_ts)),
+        10_000_000,
+    )
+    .await;
+    // 5 tokens from 1 USDC rising to 1.005 USDC
+    let cost = 5_012_500;
+
+    let instruction =
+        buy_exact_tokens_instruction(&env, &buyer.pubkey(), &sale, &accounts, 5 * WHOLE, cost - 1);
+    let result = send(&mut env.rpc, instruction, &buyer, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::MaxUsdcExceeded)).unwrap();
+
+    let instruction =
+        buy_exact_tokens_instruction(&env, &buyer.pubkey(), &sale, &accounts, 5 * WHOLE, cost);
+    send(&mut env.rpc, instruction, &buyer, &[]).await.unwrap();
+    assert_eq!(
+        token_balance(&mut env.rpc, &accounts.buyer_token_account).await,
+        5 * WHOLE
+    );
+    assert_eq!(
+        token_balance(&mut env.rpc, &accounts.buyer_usdc_account).await,
+        10_000_000 - cost
+    );
+}
+
+#[tokio::test]
+async fn test_buy_exact_tokens_respects_limits() {
+    let mut env = setup().await;
+    let (sale, buyer, accounts) = launch_sale_with_buyer(
+        &mut env,
+        LaunchArgs::new(SUPPLY, PRICE, 2 * WHOLE),
+        10_000_000,
+    )
+    .await;
+
+    let instruction =
+        buy_exact_tokens_instruction(&env, &buyer.pubkey(), &sale, &accounts, 0, 1_000_000);
+    let result = send(&mut env.rpc, instruction, &buyer, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::AmountMustBePositive)).unwrap();
+
+    let instruction = buy_exact_tokens_instruction(
+        &env,
+        &buyer.pubkey(),
+        &sale,
+        &accounts,
+        3 * WHOLE,
+        5_000_000,
+    );
+    let result = send(&mut env.rpc, instruction, &buyer, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::ExceedsMintLimit)).unwrap();
+
+    // Exact buys are never partially filled at the hard cap
+    let mut env = setup().await;
+    let (sale, buyer, accounts) = launch_sale_with_buyer(
+        &mut env,
+        LaunchArgs::new(SUPPLY, PRICE, 0).with_hard_cap(2_000_000),
+        10_000_000,
+    )
+    .await;
+    let instruction = buy_exact_tokens_instruction(
+        &env,
+        &buyer.pubkey(),
+        &sale,
+        &accounts,
+        3 * WHOLE,
+        5_000_000,
+    );
+    let result = send(&mut env.rpc, instruction, &buyer, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::ExceedsHardCap)).unwrap();
+
+    let instruction = buy_exact_tokens_instruction(
+        &env,
+        &buyer.pubkey(),
+        &sale,
+        &accounts,
+        2 * WHOLE,
+        5_000_000,
+    );
+    send(&mut env.rpc, instruction, &buyer, &[]).await.unwrap();
+    assert!(!get_token_sale(&mut env.rpc, &sale.token_mint).await.active);
+}
+
+#[tokio::test]
+async fn test_buy_exact_compressed_tokens() {
+    let mut env = setup().await;
+    let owner = env.owner.insecure_clone();
+    let creator = funded_keypair(&mut env.rpc).await;
+    let token_mint = create_compressed_sale_mint(&mut env.rpc, &creator).await;
+    launch_token_compressed(
+        &mut env.rpc,
+        &creator,
+        token_mint,
+        LaunchArgs::new(SUPPLY, PRICE, 0),
+    )
+    .await
+    .unwrap();
+    let creator_usdc_account =
+        create_token_account(&mut env.rpc, &owner, &env.usdc_mint, &creator.pubkey()).await;
+    let (buyer, buyer_usdc_account) = create_buyer(&mut env, 10_000_000).await;
+
+    let inputs = compressed_sale_inputs(&mut env.rpc, &token_mint, &token_mint).await;
+    let instruction = buy_exact_tokens_compressed_instruction(
+        &env,
+        &buyer.pubkey(),
+        buyer_usdc_account,
+        creator_usdc_account,
+        token_mint,
+        inputs,
+        2 * WHOLE,
+        1_999_999,
+    );
+    let result = send(&mut env.rpc, instruction, &buyer, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::MaxUsdcExceeded)).unwrap();
+
+    let inputs = compressed_sale_inputs(&mut env.rpc, &token_mint, &token_mint).await;
+    let instruction = buy_exact_tokens_compressed_instruction(
+        &env,
+        &buyer.pubkey(),
+        buyer_usdc_account,
+        creator_usdc_account,
+        token_mint,
+        inputs,
+        2 * WHOLE,
+        2_000_000,
+    );
+    send(&mut env.rpc, instruction, &buyer, &[]).await.unwrap();
+
+    assert_eq!(
+        compressed_token_balance(&mut env.rpc, &buyer.pubkey(), &token_mint).await,
+        2 * WHOLE
+    );
+    assert_eq!(
+        token_balance(&mut env.rpc, &buyer_usdc_account).await,
+        8_000_000
+    );
+    let (_, sale) = get_compressed_sale(&mut env.rpc, &token_mint).await;
+    assert_eq!(sale.tokens_sold, 2 * WHOLE);
+    assert_eq!(sale.usdc_raised, 2_000_000);
+}
@@ -0,0 +1,168 @@
+#![cfg(feature = "test-sbf")]
+
+mod common;
+
+use common::*;
+use light_program_test::utils::assert::assert_rpc_error;
+use solana_launchpad::ErrorCode;
+use solana_sdk::signature::Signer;
+
+const SUPPLY: u64 = 1_000_000_000_000;
+const PRICE: u64 = 1_000_000;
+const HARD_CAP: u64 = 5_000_000;
+const WHOLE: u64 = 1_000_000_000;
+
+#[tokio::test]
+async fn test_launch_rejects_invalid_hard_cap() {
+    let mut env = setup().await;
+    let creator = funded_keypair(&mut env.rpc).await;
+    let now = current_timestamp(&env.rpc);
+
+    let args = LaunchArgs::new(SUPPLY, 0, WHOLE).with_hard_cap(HARD_CAP);
+    let sale = create_standard_sale_mint(&mut env.rpc, &creator, TOKEN_DECIMALS).await;
+    let result = launch_token(&mut env.rpc, &creator, &sale, args.clone()).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::InvalidHardCap)).unwrap();
+    let token_mint = create_compressed_sale_mint(&mut env.rpc, &creator).await;
+    let result = launch_token_compressed(&mut env.rpc, &creator, token_mint, args).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::InvalidHardCap)).unwrap();
+
+    // The hard cap cannot be below the soft cap
+    let sale = create_standard_sale_mint(&mut env.rpc, &creator, TOKEN_DECIMALS).await;
+    let args = LaunchArgs::new(SUPPLY, PRICE, 0)
+        .with_window(None, Some(now + 3_600))
+        .with_soft_cap(HARD_CAP + 1)
+        .with_hard_cap(HARD_CAP);
+    let result = launch_token(&mut env.rpc, &creator, &sale, args).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::InvalidHardCap)).unwrap();
+}
+
+#[tokio::test]
+async fn test_standard_sale_fills_up_to_hard_cap() {
+    let mut env = setup().await;
+    let (_, sale, creator_usdc_account) = launch_sale(
+        &mut env,
+        LaunchArgs::new(SUPPLY, PRICE, 0).with_hard_cap(HARD_CAP),
+    )
+    .await;
+    let (buyer, accounts) =
+        create_sale_buyer(&mut env, &sale, creator_usdc_account, 10_000_000).await;
+
+    let instruction = buy_tokens_instruction(&env, &buyer.pubkey(), &sale, &accounts, 3_000_000);
+    send(&mut env.rpc, instruction, &buyer, &[]).await.unwrap();
+
+    // Only 2 USDC remain under the cap, which is less than the buyer asked for
+    let instruction = buy_tokens_instruction_with(
+        &env,
+        &buyer.pubkey(),
+        &sale,
+        &accounts,
+        3_000_000,
+        3 * WHOLE,
+        None,
+        None,
+    );
+    let result = send(&mut env.rpc, instruction, &buyer, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::SlippageExceeded)).unwrap();
+
+    let instruction = buy_tokens_instruction(&env, &buyer.pubkey(), &sale, &accounts, 3_000_000);
+    send(&mut env.rpc, instruction, &buyer, &[]).await.unwrap();
+    assert_eq!(
+        token_balance(&mut env.rpc, &accounts.buyer_token_account).await,
+        5 * WHOLE
+    );
+    assert_eq!(
+        token_balance(&mut env.rpc, &accounts.buyer_usdc_account).await,
+        10_000_000 - HARD_CAP
+    );
+    let token_sale = get_token_sale(&mut env.rpc, &sale.token_mint).await;
+    assert_eq!(token_sale.usdc_raised, HARD_CAP);
+    assert!(!token_sale.active);
+
+    let instruction = buy_tokens_instruction(&env, &buyer.pubkey(), &sale, &accounts, 1_000_000);
+    let result = send(&mut env.rpc, instruction, &buyer, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::SaleNotActive)).unwrap();
+}
+
+#[tokio::test]
+async fn test_sale_closes_when_remaining_cap_buys_nothing() {
+    let mut env = setup().await;
+    let owner = env.owner.insecure_clone();
+    let creator = funded_keypair(&mut env.rpc).await;
+    // Whole-token mint at 1 USDC, so the last 0.5 USDC under the cap buys nothing
+    let sale = create_standard_sale_mint(&mut env.rpc, &creator, 0).await;
+    launch_token(
+        &mut env.rpc,
+        &creator,
+        &sale,
+        LaunchArgs::new(1_000, PRICE, 0).with_hard_cap(2_500_000),
+    )
+    .await
+    .unwrap();
+    let creator_usdc_account =
+        create_token_account(&mut env.rpc, &owner, &env.usdc_mint, &creator.pubkey()).await;
+    let (buyer, accounts) =
+        create_sale_buyer(&mut env, &sale, creator_usdc_account, 10_000_000).await;
+
+    let instruction = buy_tokens_instruction(&env, &buyer.pubkey(), &sale, &accounts, 10_000_000);
+    send(&mut env.rpc, instruction, &buyer, &[]).await.unwrap();
+    assert_eq!(
+        token_balance(&mut env.rpc, &accounts.buyer_token_account).await,
+        2
+    );
+    assert_eq!(
+        token_balance(&mut env.rpc, &accounts.buyer_usdc_account).await,
+        8_000_000
+    );
+    let token_sale = get_token_sale(&mut env.rpc, &sale.token_mint).await;
+    assert_eq!(token_sale.usdc_raised, 2_000_000);
+    assert!(!token_sale.active);
+
+    let instruction = buy_tokens_instruction(&env, &buyer.pubkey(), &sale, &accounts, 1_000_000);
+    let result = send(&mut env.rpc, instruction, &buyer, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::SaleNotActive)).unwrap();
+}
+
+#[tokio::test]
+async fn test_compressed_sale_fills_up_to_hard_cap() {
+    let mut env = setup().await;
+    let owner = env.owner.insecure_clone();
+    let creator = funded_keypair(&mut env.rpc).await;
+    let token_mint = create_compressed_sale_mint(&mut env.rpc, &creator).await;
+    launch_token_compressed(
+        &mut env.rpc,
+        &creator,
+        token_mint,
+        LaunchArgs::new(SUPPLY, PRICE, 0).with_hard_cap(HARD_CAP),
+    )
+    .await
+    .unwrap();
+    let creator_usdc_account =
+        create_token_account(&mut env.rpc, &owner, &env.usdc_mint, &creator.pubkey()).await;
+    let (buyer, buyer_usdc_account) = create_buyer(&mut env, 10_000_000).await;
+
+    for usdc_amount in [3_000_000, 3_000_000] {
+        let inputs = compressed_sale_inputs(&mut env.rpc, &token_mint, &token_mint).await;
+        let instruction = buy_tokens_compressed_instruction(
+            &env,
+            &buyer.pubkey(),
+            buyer_usdc_account,
+            creator_usdc_account,
+            token_mint,
+            inputs,
+            usdc_amount,
+        );
+        send(&mut env.rpc, instruction, &buyer, &[]).await.unwrap();
+    }
+
+    assert_eq!(
+        compressed_token_balance(&mut env.rpc, &buyer.pubkey(), &token_mint).await,
+        5 * WHOLE
+    );
+    assert_eq!(
+        token_balance(&mut env.rpc, &buyer_usdc_account).await,
+        10_000_000 - HARD_CAP
+    );
+    let (_, sale) = get_compressed_sale(&mut env.rpc, &token_mint).await;
+    assert_eq!(sale.usdc_raised, HARD_CAP);
+    assert!(!sale.active);
+}
@@ -0,0 +1,163 @@
+#![cfg(feature = "test-sbf")]
+
+mod common;
+
+use common::*;
+use light_program_test::utils::assert::assert_rpc_error;
+use solana_launchpad::ErrorCode;
+use solana_sdk::signature::Signer;
+
+const SUPPLY: u64 = 1_000_000_000_000;
+const PRICE: u64 = 1_000_000;
+
+#[tokio::test]
+async fn test_pause_access_control() {
+    let mut env = setup().await;
+    let owner = env.owner.insecure_clone();
+    let attacker = funded_keypair(&mut env.rpc).await;
+
+    let result = set_paused(&mut env.rpc, &attacker, true).await;
+    assert_rpc_error(
+        result,
+        0,
+        anchor_lang::error::ErrorCode::ConstraintHasOne.into(),
+    )
+    .unwrap();
+    assert!(!get_app_state(&mut env.rpc).await.paused);
+
+    set_paused(&mut env.rpc, &owner, true).await.unwrap();
+    assert!(get_app_state(&mut env.rpc).await.paused);
+
+    let result = set_paused(&mut env.rpc, &attacker, false).await;
+    assert_rpc_error(
+        result,
+        0,
+        anchor_lang::error::ErrorCode::ConstraintHasOne.into(),
+    )
+    .unwrap();
+
+    set_paused(&mut env.rpc, &owner, false).await.unwrap();
+    assert!(!get_app_state(&mut env.rpc).await.paused);
+}
+
+#[tokio::test]
+async fn test_pause_blocks_standard_launch_and_buy() {
+    let mut env = setup().await;
+    let owner = env.owner.insecure_clone();
+    let (creator, sale, creator_usdc_account) =
+        launch_sale(&mut env, LaunchArgs::new(SUPPLY, PRICE, 0)).await;
+    let (buyer, accounts) =
+        create_sale_buyer(&mut env, &sale, creator_usdc_account, 10_000_000).await;
+
+    set_paused(&mut env.rpc, &owner, true).await.unwrap();
+
+    let other_sale = create_standard_sale_mint(&mut env.rpc, &creator, TOKEN_DECIMALS).await;
+    let result = launch_token(
+        &mut env.rpc,
+        &creator,
+        &other_sale,
+        LaunchArgs::new(SUPPLY, PRICE, 0),
+    )
+    .await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::ProgramPaused)).unwrap();
+
+    let instruction = buy_tokens_instruction(&env, &buyer.pubkey(), &sale, &accounts, 1_000_000);
+    let result = send(&mut env.rpc, instruction, &buyer, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::ProgramPaused)).unwrap();
+
+    // Buys resume once unpaused
+    set_paused(&mut env.rpc, &owner, false).await.unwrap();
+    let instruction = buy_tokens_instruction(&env, &buyer.pubkey(), &sale, &accounts, 1_000_000);
+    send(&mut env.rpc, instruction, &buyer, &[]).await.unwrap();
+    assert_eq!(
+        token_balance(&mut env.rpc, &accounts.buyer_token_account).await,
+        1_000_000_000
+    );
+}
+
+#[tokio::test]
+async fn test_pause_allows_standard_close() {
+    let mut env = setup().await;
+    let owner = env.owner.insecure_clone();
+    let creator = funded_keypair(&mut env.rpc).await;
+    let sale = create_standard_sale_mint(&mut env.rpc, &creator, TOKEN_DECIMALS).await;
+    launch_token(
+        &mut env.rpc,
+        &creator,
+        &sale,
+        LaunchArgs::new(SUPPLY, PRICE, 0),
+    )
+    .await
+    .unwrap();
+    let creator_token_account =
+        create_token_account(&mut env.rpc, &owner, &sale.token_mint, &creator.pubkey()).await;
+
+    set_paused(&mut env.rpc, &owner, true).await.unwrap();
+
+    let instruction = close_sale_instruction(&creator.pubkey(), &sale, creator_token_account);
+    send(&mut env.rpc, instruction, &creator, &[])
+        .await
+        .unwrap();
+    assert!(!get_token_sale(&mut env.rpc, &sale.token_mint).await.active);
+    assert_eq!(
+        token_balance(&mut env.rpc, &creator_token_account).await,
+        SUPPLY
+    );
+}
+
+#[tokio::test]
+async fn test_pause_blocks_compressed_launch_and_buy_but_not_close() {
+    let mut env = setup().await;
+    let owner = env.owner.insecure_clone();
+    let creator = funded_keypair(&mut env.rpc).await;
+    let token_mint = create_compressed_sale_mint(&mut env.rpc, &creator).await;
+    launch_token_compressed(
+        &mut env.rpc,
+        &creator,
+        token_mint,
+        LaunchArgs::new(SUPPLY, PRICE, 0),
+    )
+    .await
+    .unwrap();
+    let creator_usdc_account =
+        create_token_account(&mut env.rpc, &owner, &env.usdc_mint, &creator.pubkey()).await;
+    let (buyer, buyer_usdc_account) = create_buyer(&mut env, 10_000_000).await;
+
+    set_paused(&mut env.rpc, &owner, true).await.unwrap();
+
+    let other_mint = create_compressed_sale_mint(&mut env.rpc, &creator).await;
+    let result = launch_token_compressed(
+        &mut env.rpc,
+        &creator,
+        other_mint,
+        LaunchArgs::new(SUPPLY, PRICE, 0),
+    )
+    .await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::ProgramPaused)).unwrap();
+
+    let inputs = compressed_sale_inputs(&mut env.rpc, &token_mint, &token_mint).await;
+    let instruction = buy_tokens_compressed_instruction(
+        &env,
+        &buyer.pubkey(),
+        buyer_usdc_account,
+        creator_usdc_account,
+        token_mint,
+        inputs,
+        1_000_000,
+    );
+    let result = send(&mut env.rpc, instruction, &buyer, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::ProgramPaused)).unwrap();
+
+    let inputs = compressed_sale_inputs(&mut env.rpc, &token_mint, &token_mint).await;
+    let instruction = close_sale_compressed_instruction(&creator.pubkey(), token_mint, inputs);
+    send(&mut env.rpc, instruction, &creator, &[])
+        .await
+        .unwrap();
+
+    let (_, sale) = get_compressed_sale(&mut env.rpc, &token_mint).await;
+    assert!(!sale.active);
+    assert_eq!(
+        compressed_token_balance(&mut env.rpc, &creator.pubkey(), &token_mint).await,
+        SUPPLY
+    );
+}
@@ -0,0 +1,138 @@
+#![cfg(feature = "test-sbf")]
+
+mod common;
+
+use common::*;
+use light_program_test::utils::assert::assert_rpc_error;
+use solana_launchpad::{ErrorCode, QuoteCurrency};
+use solana_sdk::signature::Signer;
+
+const SUPPLY: u64 = 1_000_000_000_000;
+const PRICE: u64 = 1_000_000;
+const DAY: i64 = 86_400;
+
+#[tokio::test]
+async fn test_launch_rejects_invalid_proceeds_vesting() {
+    let mut env = setup().await;
+    let creator = funded_keypair(&mut env.rpc).await;
+    let now = current_timestamp(&env.rpc);
+    let vested = |args: LaunchArgs| args.with_proceeds_vesting(now, now, now + DAY);
+
+    let cases = [
+        LaunchArgs::new(SUPPLY, PRICE, 0).with_proceeds_vesting(now, now + 2 * DAY, now + DAY),
+        vested(LaunchArgs::new(SUPPLY, 0, 1_000_000_000)),
+        vested(LaunchArgs::new(SUPPLY, PRICE, 0).with_price_slope(1_000)),
+        vested(
+            LaunchArgs::new(SUPPLY, PRICE, 0)
+                .with_window(None, Some(now + DAY))
+                .with_soft_cap(PRICE),
+        ),
+        vested(LaunchArgs::new(SUPPLY, PRICE, 0).with_quote_currency(QuoteCurrency::Sol)),
+    ];
+    for args in cases {
+        let sale = create_standard_sale_mint(&mut env.rpc, &creator, TOKEN_DECIMALS).await;
+        let result = launch_token(&mut env.rpc, &creator, &sale, args).await;
+        assert_rpc_error(result, 0, error_code(ErrorCode::InvalidProceedsVesting)).unwrap();
+    }
+}
+
+#[tokio::test]
+async fn test_creator_share_vests_in_escrow() {
+    let mut env = setup().await;
+    let owner = env.owner.insecure_clone();
+    let creator = funded_keypair(&mut env.rpc).await;
+    let start_ts = current_timestamp(&env.rpc);
+    let sale = create_standard_sale_mint(&mut env.rpc, &creator, TOKEN_DECIMALS).await;
+    launch_token(
+        &mut env.rpc,
+        &creator,
+        &sale,
+        LaunchArgs::new(SUPPLY, PRICE, 0).with_proceeds_vesting(
+            start_ts,
+            start_ts + DAY,
+            start_ts + 4 * DAY,
+        ),
+    )
+    .await
+    .unwrap();
+    let creator_usdc_account =
+        create_token_account(&mut env.rpc, &owner, &env.usdc_mint, &creator.pubkey()).await;
+    let (buyer, accounts) =
+        create_sale_buyer(&mut env, &sale, creator_usdc_account, 10_000_000).await;
+    let instruction = buy_tokens_instruction(&env, &buyer.pubkey(), &sale, &accounts, 4_000_000);
+    send(&mut env.rpc, instruction, &buyer, &[]).await.unwrap();
+
+    // The platform fee is paid out; the creator share stays with the authority PDA
+    let fee = 4_000_000 * PLATFORM_FEE_BPS as u64 / 10_000;
+    let creator_share = 4_000_000 - fee;
+    assert_eq!(
+        token_balance(&mut env.rpc, &env.fee_recipient_usdc_account).await,
+        fee
+    );
+    assert_eq!(token_balance(&mut env.rpc, &creator_usdc_account).await, 0);
+    assert_eq!(
+        token_balance(&mut env.rpc, &sale_escrow_pda(&sale.token_mint)).await,
+        creator_share
+    );
+    let token_sale = get_token_sale(&mut env.rpc, &sale.token_mint).await;
+    assert_eq!(token_sale.proceeds_escrow, creator_share);
+
+    let instruction =
+        withdraw_proceeds_instruction(&env, &creator.pubkey(), &sale, creator_usdc_account);
+    let result = send(&mut env.rpc, instruction, &creator, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::NothingVested)).unwrap();
+
+    // Only the creator can withdraw
+    let attacker = funded_keypair(&mut env.rpc).await;
+    let attacker_usdc_account =
+        create_token_account(&mut env.rpc, &owner, &env.usdc_mint, &attacker.pubkey()).await;
+    warp_to_timestamp(&mut env.rpc, start_ts + 2 * DAY);
+    let instruction =
+        withdraw_proceeds_instruction(&env, &attacker.pubkey(), &sale, attacker_usdc_account);
+    let result = send(&mut env.rpc, instruction, &attacker, &[]).await;
+    assert_rpc_error(
+        result,
+        0,
+        anchor_lang::error::ErrorCode::ConstraintHasOne.into(),
+    )
+    .unwrap();
+
+    let instruction =
+        withdraw_proceeds_instruction(&env, &creator.pubkey(), &sale, creator_usdc_account);
+    send(&mut env.rpc, instruction, &creator, &[])
+        .await
+        .unwrap();
+    assert_eq!(
+        token_balance(&mut env.rpc, &creator_usdc_account).await,
+        creator_share / 2
+    );
+
+    warp_to_timestamp(&mut env.rpc, start_ts + 4 * DAY);
+    let instruction =
+        withdraw_proceeds_instruction(&env, &creator.pubkey(), &sale, creator_usdc_account);
+    send(&mut env.rpc, instruction, &creator, &[])
+        .await
+        .unwrap();
+    assert_eq!(
+        token_balance(&mut env.rpc, &creator_usdc_account).await,
+        creator_share
+    );
+    assert_eq!(
+        token_balance(&mut env.rpc, &sale_escrow_pda(&sale.token_mint)).await,
+        0
+    );
+    let token_sale = get_token_sale(&mut env.rpc, &sale.token_mint).await;
+    assert_eq!(token_sale.proceeds_withdrawn, creator_share);
+}
+
+#[tokio::test]
+async fn test_withdraw_requires_proceeds_vesting() {
+    let mut env = setup().await;
+    let (creator, sale, creator_usdc_account) =
+        launch_sale(&mut env, LaunchArgs::new(SUPPLY, PRICE, 0)).await;
+
+    let instruction =
+        withdraw_proceeds_instruction(&env, &creator.pubkey(), &sale, creator_usdc_account);
+    let result = send(&mut env.rpc, instruction, &creator, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::NoVesting)).unwrap();
+}
@@ -0,0 +1,210 @@
+#![cfg(feature = "test-sbf")]
+
+mod common;
+
+use common::*;
+use light_program_test::utils::assert::assert_rpc_error;
+use solana_launchpad::ErrorCode;
+use solana_sdk::signature::Signer;
+
+const SUPPLY: u64 = 1_000_000_000_000;
+const PRICE: u64 = 1_000_000;
+const WHOLE: u64 = 1_000_000_000;
+/// USDT trades with a higher floor than USDC in these tests
+const USDT_MIN_PRICE: u64 = 10_000;
+
+#[tokio::test]
+async fn test_quote_mint_registry() {
+    let mut env = setup().await;
+    let owner = env.owner.insecure_clone();
+    let attacker = funded_keypair(&mut env.rpc).await;
+
+    // initialize registers the launchpad's USDC mint
+    let entry = get_quote_mint_entry(&mut env.rpc, &env.usdc_mint)
+        .await
+        .unwrap();
+    assert_eq!(entry.mint, env.usdc_mint);
+    assert_eq!(entry.decimals, USDC_DECIMALS);
+    assert_eq!(entry.min_price, USDC_MIN_PRICE);
+
+    let usdt_mint = create_mint(&mut env.rpc, &owner, &owner.pubkey(), 8).await;
+    let result = add_quote_mint(&mut env.rpc, &attacker, usdt_mint, USDT_MIN_PRICE).await;
+    assert_rpc_error(
+        result,
+        0,
+        anchor_lang::error::ErrorCode::ConstraintHasOne.into(),
+    )
+    .unwrap();
+    let result = add_quote_mint(&mut env.rpc, &owner, usdt_mint, 0).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::InvalidMinPrice)).unwrap();
+    add_quote_mint(&mut env.rpc, &owner, usdt_mint, USDT_MIN_PRICE)
+        .await
+        .unwrap();
+    let entry = get_quote_mint_entry(&mut env.rpc, &usdt_mint)
+        .await
+        .unwrap();
+    assert_eq!(entry.decimals, 8);
+
+    let result = update_quote_mint(&mut env.rpc, &attacker, usdt_mint, 1).await;
+    assert_rpc_error(
+        result,
+        0,
+        anchor_lang::error::ErrorCode::ConstraintHasOne.into(),
+    )
+    .unwrap();
+    update_quote_mint(&mut env.rpc, &owner, usdt_mint, 20_000)
+        .await
+        .unwrap();
+    let entry = get_quote_mint_entry(&mut env.rpc, &usdt_mint)
+        .await
+        .unwrap();
+    assert_eq!(entry.min_price, 20_000);
+
+    let result = remove_quote_mint(&mut env.rpc, &attacker, usdt_mint).await;
+    assert_rpc_error(
+        result,
+        0,
+        anchor_lang::error::ErrorCode::ConstraintHasOne.into(),
+    )
+    .unwrap();
+    remove_quote_mint(&mut env.rpc, &owner, usdt_mint)
+        .await
+        .unwrap();
+    assert!(get_quote_mint_entry(&mut env.rpc, &usdt_mint)
+        .await
+        .is_none());
+}
+
+#[tokio::test]
+async fn test_launch_requires_registered_quote_mint() {
+    let mut env = setup().await;
+    let owner = env.owner.insecure_clone();
+    let creator = funded_keypair(&mut env.rpc).await;
+    let sale = create_standard_sale_mint(&mut env.rpc, &creator, TOKEN_DECIMALS).await;
+    let unknown_mint = create_mint(&mut env.rpc, &owner, &owner.pubkey(), 6).await;
+
+    let result = launch_token(
+        &mut env.rpc,
+        &creator,
+        &sale,
+        LaunchArgs::new(SUPPLY, PRICE, 0).with_quote_mint(unknown_mint),
+    )
+    .await;
+    assert_rpc_error(
+        result,
+        0,
+        anchor_lang::error::ErrorCode::AccountNotInitialized.into(),
+    )
+    .unwrap();
+
+    // Prices are floored by the chosen mint's minimum
+    update_quote_mint(&mut env.rpc, &owner, env.usdc_mint, 5_000)
+        .await
+        .unwrap();
+    let result = launch_token(
+        &mut env.rpc,
+        &creator,
+        &sale,
+        LaunchArgs::new(SUPPLY, 4_999, 0),
+    )
+    .await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::PriceTooLow)).unwrap();
+
+    launch_token(
+        &mut env.rpc,
+        &creator,
+        &sale,
+        LaunchArgs::new(SUPPLY, 5_000, 0),
+    )
+    .await
+    .unwrap();
+    let token_sale = get_token_sale(&mut env.rpc, &sale.token_mint).await;
+    assert_eq!(token_sale.quote_mint, env.usdc_mint);
+}
+
+#[tokio::test]
+async fn test_sale_priced_in_second_quote_mint() {
+    let mut env = setup().await;
+    let owner = env.owner.insecure_clone();
+    let usdc_mint = env.usdc_mint;
+    let usdt_mint = create_mint(&mut env.rpc, &owner, &owner.pubkey(), 6).await;
+    use_quote_mint(&mut env, usdt_mint, USDT_MIN_PRICE).await;
+
+    let creator = funded_keypair(&mut env.rpc).await;
+    let sale = create_standard_sale_mint(&mut env.rpc, &creator, TOKEN_DECIMALS).await;
+    let result = launch_token(
+        &mut env.rpc,
+        &creator,
+        &sale,
+        LaunchArgs::new(SUPPLY, USDC_MIN_PRICE, 0).with_quote_mint(usdt_mint),
+    )
+    .await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::PriceTooLow)).unwrap();
+    launch_token(
+        &mut env.rpc,
+        &creator,
+        &sale,
+        LaunchArgs::new(SUPPLY, PRICE, 0).with_quote_mint(usdt_mint),
+    )
+    .await
+    .unwrap();
+    let token_sale = get_token_sale(&mut env.rpc, &sale.token_mint).await;
+    assert_eq!(token_sale.quote_mint, usdt_mint);
+    assert_eq!(token_sale.quote_decimals, 6);
+
+    let creator_usdt_account =
+        create_token_account(&mut env.rpc, &owner, &usdt_mint, &creator.pubkey()).await;
+    let (buyer, buyer_usdt_account) = create_buyer(&mut env, 10_000_000).await;
+    let buyer_token_account =
+        create_token_account(&mut env.rpc, &owner, &sale.token_mint, &buyer.pubkey()).await;
+
+    // USDC is registered too, but is not this sale's quote mint
+    let creator_usdc_account =
+        create_token_account(&mut env.rpc, &owner, &usdc_mint, &creator.pubkey()).await;
+    let accounts = BuyAccounts {
+        buyer_usdc_account: buyer_usdt_account,
+        buyer_token_account,
+        creator_usdc_account,
+    };
+    let instruction = buy_tokens_instruction(&env, &buyer.pubkey(), &sale, &accounts, 2_000_000);
+    let result = send(&mut env.rpc, instruction, &buyer, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::InvalidMint)).unwrap();
+
+    let accounts = BuyAccounts {
+        creator_usdc_account: creator_usdt_account,
+        ..accounts
+    };
+    let instruction = buy_tokens_instruction(&env, &buyer.pubkey(), &sale, &accounts, 2_000_000);
+    send(&mut env.rpc, instruction, &buyer, &[]).await.unwrap();
+
+    let fee = 2_000_000 * PLATFORM_FEE_BPS as u64 / 10_000;
+    assert_eq!(
+        token_balance(&mut env.rpc, &buyer_token_account).await,
+        2 * WHOLE
+    );
+    assert_eq!(
+        token_balance(&mut env.rpc, &buyer_usdt_account).await,
+        8_000_000
+    );
+    assert_eq!(
+        token_balance(&mut env.rpc, &creator_usdt_account).await,
+        2_000_000 - fee
+    );
+    assert_eq!(
+        token_balance(&mut env.rpc, &env.fee_recipient_usdc_account).await,
+        fee
+    );
+
+    // Once delisted, the mint no longer buys anything
+    remove_quote_mint(&mut env.rpc, &owner, usdt_mint)
+        .await
+        .unwrap();
+    let instruction = buy_tokens_instruction(&env, &buyer.pubkey(), &sale, &accounts, 1_000_000);
+    let result = send(&mut env.rpc, instruction, &buyer, &[]).await;
+    assert_rpc_error(
+        result,
+        0,
+        anchor_lang::error::ErrorCode::AccountNotInitialized.into(),
+    )
+    .unwrap();
+}
@@ -0,0 +1,176 @@
+#![cfg(feature = "test-sbf")]
+
+mod common;
+
+use common::*;
+use light_program_test::utils::assert::assert_rpc_error;
+use solana_launchpad::ErrorCode;
+use solana_sdk::signature::Signer;
+
+const SUPPLY: u64 = 1_000_000_000_000;
+const PRICE: u64 = 1_000_000;
+const DAY: i64 = 86_400;
+/// Referrers earn a fifth of the platform fee in these tests
+const REFERRAL_FEE_BPS: u16 = 2_000;
+
+#[tokio::test]
+async fn test_update_referral_fee() {
+    let mut env = setup().await;
+    let owner = env.owner.insecure_clone();
+    let attacker = funded_keypair(&mut env.rpc).await;
+    assert_eq!(get_app_state(&mut env.rpc).await.referral_fee_bps, 0);
+
+    let result = update_referral_fee(&mut env.rpc, &attacker, REFERRAL_FEE_BPS).await;
+    assert_rpc_error(
+        result,
+        0,
+        anchor_lang::error::ErrorCode::ConstraintHasOne.into(),
+    )
+    .unwrap();
+    let result = update_referral_fee(&mut env.rpc, &owner, 10_001).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::InvalidReferralFee)).unwrap();
+
+    update_referral_fee(&mut env.rpc, &owner, REFERRAL_FEE_BPS)
+        .await
+        .unwrap();
+    assert_eq!(
+        get_app_state(&mut env.rpc).await.referral_fee_bps,
+        REFERRAL_FEE_BPS
+    );
+}
+
+#[tokio::test]
+async fn test_referrer_earns_share_of_platform_fee() {
+    let mut env = setup().await;
+    let owner = env.owner.insecure_clone();
+    update_referral_fee(&mut env.rpc, &owner, REFERRAL_FEE_BPS)
+        .await
+        .unwrap();
+    let (creator, sale, creator_usdc_account) =
+        launch_sale(&mut env, LaunchArgs::new(SUPPLY, PRICE, 0)).await;
+    let referrer = funded_keypair(&mut env.rpc).await;
+    let referrer_usdc_account =
+        create_token_account(&mut env.rpc, &owner, &env.usdc_mint, &referrer.pubkey()).await;
+    let (buyer, accounts) =
+        create_sale_buyer(&mut env, &sale, creator_usdc_account, 10_000_000).await;
+
+    for _ in 0..2 {
+        let inputs = referral_inputs(
+            &mut env.rpc,
+            &sale.token_mint,
+            None,
+            &referrer.pubkey(),
+            &env.usdc_mint,
+        )
+        .await;
+        let instruction = buy_referred_tokens_instruction(
+            &env,
+            &buyer.pubkey(),
+            &sale,
+            &accounts,
+            2_000_000,
+            &referrer.pubkey(),
+            referrer_usdc_account,
+            inputs,
+        );
+        send(&mut env.rpc, instruction, &buyer, &[]).await.unwrap();
+    }
+
+    // The referral share comes out of the platform fee, not the creator share
+    let fee = 4_000_000 * PLATFORM_FEE_BPS as u64 / 10_000;
+    let referral_fee = fee * REFERRAL_FEE_BPS as u64 / 10_000;
+    assert_eq!(
+        token_balance(&mut env.rpc, &referrer_usdc_account).await,
+        referral_fee
+    );
+    assert_eq!(
+        token_balance(&mut env.rpc, &env.fee_recipient_usdc_account).await,
+        fee - referral_fee
+    );
+    assert_eq!(
+        token_balance(&mut env.rpc, &creator_usdc_account).await,
+        4_000_000 - fee
+    );
+
+    let (_, record) = get_referrer_record(&mut env.rpc, &referrer.pubkey(), &env.usdc_mint)
+        .await
+        .unwrap();
+    assert_eq!(record.referrer, referrer.pubkey());
+    assert_eq!(record.quote_mint, env.usdc_mint);
+    assert_eq!(record.purchases, 2);
+    assert_eq!(record.usdc_volume, 4_000_000);
+    assert_eq!(record.fees_earned, referral_fee);
+}
+
+#[tokio::test]
+async fn test_referral_rejected_for_self_and_refundable_sales() {
+    let mut env = setup().await;
+    let owner = env.owner.insecure_clone();
+    update_referral_fee(&mut env.rpc, &owner, REFERRAL_FEE_BPS)
+        .await
+        .unwrap();
+    let creator = funded_keypair(&mut env.rpc).await;
+    let now = current_timestamp(&env.rpc);
+    let sale = create_standard_sale_mint(&mut env.rpc, &creator, TOKEN_DECIMALS).await;
+    launch_token(
+        &mut env.rpc,
+        &creator,
+        &sale,
+        LaunchArgs::new(SUPPLY, PRICE, 0)
+            .with_window(None, Some(now + DAY))
+            .with_soft_cap(PRICE),
+    )
+    .await
+    .unwrap();
+    let creator_usdc_account =
+        create_token_account(&mut env.rpc, &owner, &env.usdc_mint, &creator.pubkey()).await;
+    let (buyer, accounts) =
+        create_sale_buyer(&mut env, &sale, creator_usdc_account, 10_000_000).await;
+
+    // Buyers cannot refer themselves
+    let inputs = referral_inputs(
+        &mut env.rpc,
+        &sale.token_mint,
+        Some(&buyer.pubkey()),
+        &buyer.pubkey(),
+        &env.usdc_mint,
+    )
+    .await;
+    let instruction = buy_referred_tokens_instruction(
+        &env,
+        &buyer.pubkey(),
+        &sale,
+        &accounts,
+        1_000_000,
+        &buyer.pubkey(),
+        accounts.buyer_usdc_account,
+        inputs,
+    );
+    let result = send(&mut env.rpc, instruction, &buyer, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::SelfReferral)).unwrap();
+
+    // Soft-cap sales only take the platform fee at settlement
+    let referrer = funded_keypair(&mut env.rpc).await;
+    let referrer_usdc_account =
+        create_token_account(&mut env.rpc, &owner, &env.usdc_mint, &referrer.pubkey()).await;
+    let inputs = referral_inputs(
+        &mut env.rpc,
+        &sale.token_mint,
+        Some(&buyer.pubkey()),
+        &referrer.pubkey(),
+        &env.usdc_mint,
+    )
+    .await;
+    let instruction = buy_referred_tokens_instruction(
+        &env,
+        &buyer.pubkey(),
+        &sale,
+        &accounts,
+        1_000_000,
+        &referrer.pubkey(),
+        referrer_usdc_account,
+        inputs,
+    );
+    let result = send(&mut env.rpc, instruction, &buyer, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::ReferralUnavailable)).unwrap();
+}
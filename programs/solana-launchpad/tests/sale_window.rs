@@ -0,0 +1,282 @@
+#![cfg(feature = "test-sbf")]
+
+mod common;
+
+use common::*;
+use light_program_test::utils::assert::assert_rpc_error;
+use solana_launchpad::ErrorCode;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+
+const SUPPLY: u64 = 1_000_000_000_000;
+const PRICE: u64 = 1_000_000;
+const HOUR: i64 = 3_600;
+
+/// Launches a standard sale with the given window and returns
+/// (creator, sale, creator_token_account, buyer, buy accounts)
+async fn launch_windowed_sale(
+    env: &mut TestEnv,
+    start_ts: Option<i64>,
+    end_ts: Option<i64>,
+) -> (Keypair, StandardSale, Pubkey, Keypair, BuyAccounts) {
+    let (creator, sale, creator_usdc_account) = launch_sale(
+        env,
+        LaunchArgs::new(SUPPLY, PRICE, 0).with_window(start_ts, end_ts),
+    )
+    .await;
+    let owner = env.owner.insecure_clone();
+    let creator_token_account =
+        create_token_account(&mut env.rpc, &owner, &sale.token_mint, &creator.pubkey()).await;
+    let (buyer, accounts) = create_sale_buyer(env, &sale, creator_usdc_account, 10_000_000).await;
+    (creator, sale, creator_token_account, buyer, accounts)
+}
+
+#[tokio::test]
+async fn test_launch_rejects_invalid_window() {
+    let mut env = setup().await;
+    let creator = funded_keypair(&mut env.rpc).await;
+    let now = current_timestamp(&env.rpc);
+
+    let cases = [
+        (Some(now - 2 * HOUR), Some(now - HOUR)),
+        (None, Some(now)),
+        (Some(now + 2 * HOUR), Some(now + HOUR)),
+        (Some(now + HOUR), Some(now + HOUR)),
+    ];
+    for (start_ts, end_ts) in cases {
+        let sale = create_standard_sale_mint(&mut env.rpc, &creator, TOKEN_DECIMALS).await;
+        let result = launch_token(
+            &mut env.rpc,
+            &creator,
+            &sale,
+            LaunchArgs::new(SUPPLY, PRICE, 0).with_window(start_ts, end_ts),
+        )
+        .await;
+        assert_rpc_error(result, 0, error_code(ErrorCode::InvalidSaleWindow)).unwrap();
+
+        let token_mint = create_compressed_sale_mint(&mut env.rpc, &creator).await;
+        let result = launch_token_compressed(
+            &mut env.rpc,
+            &creator,
+            token_mint,
+            LaunchArgs::new(SUPPLY, PRICE, 0).with_window(start_ts, end_ts),
+        )
+        .await;
+        assert_rpc_error(result, 0, error_code(ErrorCode::InvalidSaleWindow)).unwrap();
+    }
+}
+
+#[tokio::test]
+async fn test_standard_sale_window() {
+    let mut env = setup().await;
+    let now = current_timestamp(&env.rpc);
+    let start_ts = now + HOUR;
+    let end_ts = now + 2 * HOUR;
+    let (_creator, sale, creator_token_account, buyer, accounts) =
+        launch_windowed_sale(&mut env, Some(start_ts), Some(end_ts)).await;
+
+    let token_sale = get_token_sale(&mut env.rpc, &sale.token_mint).await;
+    assert_eq!(token_sale.start_ts, Some(start_ts));
+    assert_eq!(token_sale.end_ts, Some(end_ts));
+
+    let instruction = buy_tokens_instruction(&env, &buyer.pubkey(), &sale, &accounts, 1_000_000);
+    let result = send(&mut env.rpc, instruction, &buyer, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::SaleNotStarted)).unwrap();
+
+    warp_to_timestamp(&mut env.rpc, start_ts);
+    let instruction = buy_tokens_instruction(&env, &buyer.pubkey(), &sale, &accounts, 1_000_000);
+    send(&mut env.rpc, instruction, &buyer, &[]).await.unwrap();
+
+    // Finalizing is only possible once the sale has ended
+    let instruction = finalize_sale_instruction(
+        &env,
+        &sale,
+        creator_token_account,
+        accounts.creator_usdc_account,
+    );
+    let result = send(&mut env.rpc, instruction, &buyer, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::SaleNotEnded)).unwrap();
+
+    warp_to_timestamp(&mut env.rpc, end_ts);
+    let instruction = buy_tokens_instruction(&env, &buyer.pubkey(), &sale, &accounts, 1_000_000);
+    let result = send(&mut env.rpc, instruction, &buyer, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::SaleEnded)).unwrap();
+    assert_eq!(
+        token_balance(&mut env.rpc, &accounts.buyer_token_account).await,
+        1_000_000_000
+    );
+}
+
+#[tokio::test]
+async fn test_anyone_can_finalize_ended_sale() {
+    let mut env = setup().await;
+    let now = current_timestamp(&env.rpc);
+    let end_ts = now + HOUR;
+    let (creator, sale, creator_token_account, buyer, accounts) =
+        launch_windowed_sale(&mut env, None, Some(end_ts)).await;
+
+    let instruction = buy_tokens_instruction(&env, &buyer.pubkey(), &sale, &accounts, 1_000_000);
+    send(&mut env.rpc, instruction, &buyer, &[]).await.unwrap();
+
+    warp_to_timestamp(&mut env.rpc, end_ts);
+
+    // Unsold tokens can only go back to the creator
+    let instruction = finalize_sale_instruction(
+        &env,
+        &sale,
+        accounts.buyer_token_account,
+        accounts.creator_usdc_account,
+    );
+    let result = send(&mut env.rpc, instruction, &buyer, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::InvalidTokenAccountOwner)).unwrap();
+
+    let keeper = funded_keypair(&mut env.rpc).await;
+    let instruction = finalize_sale_instruction(
+        &env,
+        &sale,
+        creator_token_account,
+        accounts.creator_usdc_account,
+    );
+    send(&mut env.rpc, instruction, &keeper, &[]).await.unwrap();
+
+    assert!(!get_token_sale(&mut env.rpc, &sale.token_mint).await.active);
+    assert_eq!(
+        token_balance(&mut env.rpc, &creator_token_account).await,
+        SUPPLY - 1_000_000_000
+    );
+
+    let instruction = finalize_sale_instruction(
+        &env,
+        &sale,
+        creator_token_account,
+        accounts.creator_usdc_account,
+    );
+    let result = send(&mut env.rpc, instruction, &keeper, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::AlreadyClosed)).unwrap();
+
+    // The creator can no longer close it either
+    let instruction = close_sale_instruction(&creator.pubkey(), &sale, creator_token_account);
+    let result = send(&mut env.rpc, instruction, &creator, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::AlreadyClosed)).unwrap();
+}
+
+#[tokio::test]
+async fn test_finalize_requires_end_ts() {
+    let mut env = setup().await;
+    let (_creator, sale, creator_token_account, buyer, accounts) =
+        launch_windowed_sale(&mut env, None, None).await;
+
+    let instruction = finalize_sale_instruction(
+        &env,
+        &sale,
+        creator_token_account,
+        accounts.creator_usdc_account,
+    );
+    let result = send(&mut env.rpc, instruction, &buyer, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::SaleNotEnded)).unwrap();
+    assert!(get_token_sale(&mut env.rpc, &sale.token_mint).await.active);
+}
+
+#[tokio::test]
+async fn test_compressed_sale_window() {
+    let mut env = setup().await;
+    let owner = env.owner.insecure_clone();
+    let now = current_timestamp(&env.rpc);
+    let start_ts = now + HOUR;
+    let end_ts = now + 2 * HOUR;
+
+    let creator = funded_keypair(&mut env.rpc).await;
+    let token_mint = create_compressed_sale_mint(&mut env.rpc, &creator).await;
+    launch_token_compressed(
+        &mut env.rpc,
+        &creator,
+        token_mint,
+        LaunchArgs::new(SUPPLY, PRICE, 0).with_window(Some(start_ts), Some(end_ts)),
+    )
+    .await
+    .unwrap();
+    let creator_usdc_account =
+        create_token_account(&mut env.rpc, &owner, &env.usdc_mint, &creator.pubkey()).await;
+    let (buyer, buyer_usdc_account) = create_buyer(&mut env, 10_000_000).await;
+
+    let (_, sale) = get_compressed_sale(&mut env.rpc, &token_mint).await;
+    assert_eq!(sale.start_ts, Some(start_ts));
+    assert_eq!(sale.end_ts, Some(end_ts));
+
+    let cases = [
+        (now, Some(ErrorCode::SaleNotStarted)),
+        (start_ts, None),
+        (end_ts, Some(ErrorCode::SaleEnded)),
+    ];
+    for (timestamp, expected) in cases {
+        warp_to_timestamp(&mut env.rpc, timestamp);
+        let inputs = compressed_sale_inputs(&mut env.rpc, &token_mint, &token_mint).await;
+        let instruction = buy_tokens_compressed_instruction(
+            &env,
+            &buyer.pubkey(),
+            buyer_usdc_account,
+            creator_usdc_account,
+            token_mint,
+            inputs,
+            1_000_000,
+        );
+        let result = send(&mut env.rpc, instruction, &buyer, &[]).await;
+        match expected {
+            Some(error) => assert_rpc_error(result, 0, error_code(error)).unwrap(),
+            None => {
+                result.unwrap();
+            }
+        }
+    }
+
+    // A third party finalizes and the remainder goes to the creator. Leaving
+    // out sale token accounts would strand them, so it is rejected.
+    let keeper = funded_keypair(&mut env.rpc).await;
+    let mut inputs = compressed_sale_inputs(&mut env.rpc, &token_mint, &token_mint).await;
+    inputs.sale_token_accounts.clear();
+    let instruction = finalize_sale_compressed_instruction(&keeper.pubkey(), token_mint, inputs);
+    let result = send(&mut env.rpc, instruction, &keeper, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::UnsoldSupplyMismatch)).unwrap();
+
+    let inputs = compressed_sale_inputs(&mut env.rpc, &token_mint, &token_mint).await;
+    let instruction = finalize_sale_compressed_instruction(&keeper.pubkey(), token_mint, inputs);
+    send(&mut env.rpc, instruction, &keeper, &[]).await.unwrap();
+
+    let (_, sale) = get_compressed_sale(&mut env.rpc, &token_mint).await;
+    assert!(!sale.active);
+    assert_eq!(
+        compressed_token_balance(&mut env.rpc, &creator.pubkey(), &token_mint).await,
+        SUPPLY - 1_000_000_000
+    );
+    assert_eq!(
+        compressed_token_balance(&mut env.rpc, &keeper.pubkey(), &token_mint).await,
+        0
+    );
+}
+
+#[tokio::test]
+async fn test_compressed_finalize_before_end_fails() {
+    let mut env = setup().await;
+    let now = current_timestamp(&env.rpc);
+    let creator = funded_keypair(&mut env.rpc).await;
+    let token_mint = create_compressed_sale_mint(&mut env.rpc, &creator).await;
+    launch_token_compressed(
+        &mut env.rpc,
+        &creator,
+        token_mint,
+        LaunchArgs::new(SUPPLY, PRICE, 0).with_window(None, Some(now + HOUR)),
+    )
+    .await
+    .unwrap();
+    let keeper = funded_keypair(&mut env.rpc).await;
+
+    let inputs = compressed_sale_inputs(&mut env.rpc, &token_mint, &token_mint).await;
+    let instruction = finalize_sale_compressed_instruction(&keeper.pubkey(), token_mint, inputs);
+    let result = send(&mut env.rpc, instruction, &keeper, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::SaleNotEnded)).unwrap();
+
+    let (_, sale) = get_compressed_sale(&mut env.rpc, &token_mint).await;
+    assert!(sale.active);
+}
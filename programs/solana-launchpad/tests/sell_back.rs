@@ -0,0 +1,308 @@
+#![cfg(feature = "test-sbf")]
+
+mod common;
+
+use common::*;
+use light_program_test::utils::assert::assert_rpc_error;
+use solana_launchpad::ErrorCode;
+use solana_sdk::signature::{Keypair, Signer};
+
+const SUPPLY: u64 = 1_000_000_000_000;
+const PRICE: u64 = 1_000_000;
+const SLOPE: u64 = 1_000;
+const WHOLE: u64 = 1_000_000_000;
+const DAY: i64 = 86_400;
+
+/// Launches a curve sale ending in a day and buys 10 whole tokens for 10.05 USDC
+async fn curve_sale_with_holder(
+    env: &mut TestEnv,
+) -> (Keypair, StandardSale, Keypair, BuyAccounts) {
+    let end_ts = current_timestamp(&env.rpc) + DAY;
+    let (creator, sale, creator_usdc_account) = launch_sale(
+        env,
+        LaunchArgs::new(SUPPLY, PRICE, 0)
+            .with_price_slope(SLOPE)
+            .with_window(None, Some(end_ts)),
+    )
+    .await;
+    let (buyer, accounts) = create_sale_buyer(env, &sale, creator_usdc_account, 100_000_000).await;
+    let instruction = buy_tokens_instruction(env, &buyer.pubkey(), &sale, &accounts, 10_050_000);
+    send(&mut env.rpc, instruction, &buyer, &[]).await.unwrap();
+    (creator, sale, buyer, accounts)
+}
+
+#[tokio::test]
+async fn test_sell_back_pays_from_reserve() {
+    let mut env = setup().await;
+    let owner = env.owner.insecure_clone();
+    let (creator, sale, seller, accounts) = curve_sale_with_holder(&mut env).await;
+    let fee = 10_050_000 * PLATFORM_FEE_BPS as u64 / 10_000;
+    let reserve = 10_050_000 - fee;
+    assert_eq!(
+        get_token_sale(&mut env.rpc, &sale.token_mint)
+            .await
+            .usdc_reserve,
+        reserve
+    );
+
+    // Tokens 5..10 span 5.0375 USDC under the curve, less the 5% fee
+    let expected = 4_785_625;
+    let cases = [
+        (11 * WHOLE, 0, ErrorCode::ExceedsTokensSold),
+        (5 * WHOLE, expected + 1, ErrorCode::SlippageExceeded),
+    ];
+    for (token_amount, min_usdc_out, error) in cases {
+        let instruction = sell_tokens_instruction(
+            &env,
+            &seller.pubkey(),
+            &sale,
+            accounts.buyer_usdc_account,
+            accounts.buyer_token_account,
+            token_amount,
+            min_usdc_out,
+        );
+        let result = send(&mut env.rpc, instruction, &seller, &[]).await;
+        assert_rpc_error(result, 0, error_code(error)).unwrap();
+    }
+
+    let instruction = sell_tokens_instruction(
+        &env,
+        &seller.pubkey(),
+        &sale,
+        accounts.buyer_usdc_account,
+        accounts.buyer_token_account,
+        5 * WHOLE,
+        expected,
+    );
+    send(&mut env.rpc, instruction, &seller, &[]).await.unwrap();
+
+    assert_eq!(
+        token_balance(&mut env.rpc, &accounts.buyer_token_account).await,
+        5 * WHOLE
+    );
+    assert_eq!(
+        token_balance(&mut env.rpc, &accounts.buyer_usdc_account).await,
+        100_000_000 - 10_050_000 + expected
+    );
+    assert_eq!(
+        token_balance(&mut env.rpc, &sale.sale_token_account).await,
+        SUPPLY - 5 * WHOLE
+    );
+    let token_sale = get_token_sale(&mut env.rpc, &sale.token_mint).await;
+    assert_eq!(token_sale.tokens_sold, 5 * WHOLE);
+    assert_eq!(token_sale.usdc_reserve, reserve - expected);
+
+    // The reserve stays locked while the sale runs
+    let instruction = claim_reserve_instruction(
+        &env,
+        &creator.pubkey(),
+        &sale,
+        accounts.creator_usdc_account,
+    );
+    let result = send(&mut env.rpc, instruction, &creator, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::SaleStillActive)).unwrap();
+
+    // Tokens 0..5 span 5.0125 USDC; less the fee that is all the reserve has left
+    let instruction = sell_tokens_instruction(
+        &env,
+        &seller.pubkey(),
+        &sale,
+        accounts.buyer_usdc_account,
+        accounts.buyer_token_account,
+        5 * WHOLE,
+        reserve - expected,
+    );
+    send(&mut env.rpc, instruction, &seller, &[]).await.unwrap();
+
+    let creator_token_account =
+        create_token_account(&mut env.rpc, &owner, &sale.token_mint, &creator.pubkey()).await;
+    let instruction = close_sale_instruction(&creator.pubkey(), &sale, creator_token_account);
+    send(&mut env.rpc, instruction, &creator, &[])
+        .await
+        .unwrap();
+    assert_eq!(
+        token_balance(&mut env.rpc, &creator_token_account).await,
+        SUPPLY
+    );
+
+    let instruction = claim_reserve_instruction(
+        &env,
+        &creator.pubkey(),
+        &sale,
+        accounts.creator_usdc_account,
+    );
+    send(&mut env.rpc, instruction, &creator, &[])
+        .await
+        .unwrap();
+    assert_eq!(
+        token_balance(&mut env.rpc, &accounts.creator_usdc_account).await,
+        0
+    );
+    assert_eq!(
+        get_token_sale(&mut env.rpc, &sale.token_mint)
+            .await
+            .usdc_reserve,
+        0
+    );
+
+    // No sell-backs once the sale has closed
+    let instruction = sell_tokens_instruction(
+        &env,
+        &seller.pubkey(),
+        &sale,
+        accounts.buyer_usdc_account,
+        accounts.buyer_token_account,
+        WHOLE,
+        0,
+    );
+    let result = send(&mut env.rpc, instruction, &seller, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::SaleNotActive)).unwrap();
+}
+
+#[tokio::test]
+async fn test_creator_cannot_drain_reserve_with_holders() {
+    let mut env = setup().await;
+    let owner = env.owner.insecure_clone();
+    let (creator, sale, holder, accounts) = curve_sale_with_holder(&mut env).await;
+    let reserve = 10_050_000 - 10_050_000 * PLATFORM_FEE_BPS as u64 / 10_000;
+
+    let creator_token_account =
+        create_token_account(&mut env.rpc, &owner, &sale.token_mint, &creator.pubkey()).await;
+    let instruction = close_sale_instruction(&creator.pubkey(), &sale, creator_token_account);
+    let result = send(&mut env.rpc, instruction, &creator, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::CurveHasHolders)).unwrap();
+
+    let instruction = claim_reserve_instruction(
+        &env,
+        &creator.pubkey(),
+        &sale,
+        accounts.creator_usdc_account,
+    );
+    let result = send(&mut env.rpc, instruction, &creator, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::SaleStillActive)).unwrap();
+
+    // The holder can still sell back out of the untouched reserve
+    let instruction = sell_tokens_instruction(
+        &env,
+        &holder.pubkey(),
+        &sale,
+        accounts.buyer_usdc_account,
+        accounts.buyer_token_account,
+        5 * WHOLE,
+        0,
+    );
+    send(&mut env.rpc, instruction, &holder, &[]).await.unwrap();
+    let token_sale = get_token_sale(&mut env.rpc, &sale.token_mint).await;
+    let refunded = reserve - token_sale.usdc_reserve;
+    assert_eq!(
+        token_balance(&mut env.rpc, &accounts.buyer_usdc_account).await,
+        100_000_000 - 10_050_000 + refunded
+    );
+    assert_eq!(
+        token_balance(&mut env.rpc, &accounts.creator_usdc_account).await,
+        0
+    );
+
+    // Once the sale ends, a holder who never sells cannot lock the reserve
+    warp_to_timestamp(&mut env.rpc, token_sale.end_ts.unwrap());
+    let instruction = finalize_sale_instruction(
+        &env,
+        &sale,
+        creator_token_account,
+        accounts.creator_usdc_account,
+    );
+    send(&mut env.rpc, instruction, &creator, &[])
+        .await
+        .unwrap();
+    assert_eq!(
+        token_balance(&mut env.rpc, &creator_token_account).await,
+        SUPPLY - 5 * WHOLE
+    );
+    let instruction = claim_reserve_instruction(
+        &env,
+        &creator.pubkey(),
+        &sale,
+        accounts.creator_usdc_account,
+    );
+    send(&mut env.rpc, instruction, &creator, &[])
+        .await
+        .unwrap();
+    assert_eq!(
+        token_balance(&mut env.rpc, &accounts.creator_usdc_account).await,
+        token_sale.usdc_reserve
+    );
+}
+
+#[tokio::test]
+async fn test_sell_back_uses_fee_from_launch() {
+    let mut env = setup().await;
+    let owner = env.owner.insecure_clone();
+    let (_, sale, seller, accounts) = curve_sale_with_holder(&mut env).await;
+    let reserve = 10_050_000 - 10_050_000 * PLATFORM_FEE_BPS as u64 / 10_000;
+    assert_eq!(
+        get_token_sale(&mut env.rpc, &sale.token_mint)
+            .await
+            .platform_fee_bps,
+        PLATFORM_FEE_BPS
+    );
+
+    // Raising the fee later does not shrink what the reserve pays back
+    update_fee(&mut env.rpc, &owner, 1_000).await.unwrap();
+    let instruction = sell_tokens_instruction(
+        &env,
+        &seller.pubkey(),
+        &sale,
+        accounts.buyer_usdc_account,
+        accounts.buyer_token_account,
+        10 * WHOLE,
+        reserve,
+    );
+    send(&mut env.rpc, instruction, &seller, &[]).await.unwrap();
+    assert_eq!(
+        token_balance(&mut env.rpc, &accounts.buyer_usdc_account).await,
+        100_000_000 - 10_050_000 + reserve
+    );
+}
+
+#[tokio::test]
+async fn test_fixed_price_sale_cannot_sell_back() {
+    let mut env = setup().await;
+    let (_, sale, creator_usdc_account) =
+        launch_sale(&mut env, LaunchArgs::new(SUPPLY, PRICE, 0)).await;
+    let (buyer, accounts) =
+        create_sale_buyer(&mut env, &sale, creator_usdc_account, 10_000_000).await;
+    let instruction = buy_tokens_instruction(&env, &buyer.pubkey(), &sale, &accounts, 1_000_000);
+    send(&mut env.rpc, instruction, &buyer, &[]).await.unwrap();
+
+    let instruction = sell_tokens_instruction(
+        &env,
+        &buyer.pubkey(),
+        &sale,
+        accounts.buyer_usdc_account,
+        accounts.buyer_token_account,
+        WHOLE,
+        0,
+    );
+    let result = send(&mut env.rpc, instruction, &buyer, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::SellNotSupported)).unwrap();
+}
+
+#[tokio::test]
+async fn test_sell_back_blocked_while_paused() {
+    let mut env = setup().await;
+    let owner = env.owner.insecure_clone();
+    let (_, sale, seller, accounts) = curve_sale_with_holder(&mut env).await;
+
+    set_paused(&mut env.rpc, &owner, true).await.unwrap();
+    let instruction = sell_tokens_instruction(
+        &env,
+        &seller.pubkey(),
+        &sale,
+        accounts.buyer_usdc_account,
+        accounts.buyer_token_account,
+        WHOLE,
+        0,
+    );
+    let result = send(&mut env.rpc, instruction, &seller, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::ProgramPaused)).unwrap();
+}
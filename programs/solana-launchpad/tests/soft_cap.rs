@@ -0,0 +1,238 @@
+#![cfg(feature = "test-sbf")]
+
+mod common;
+
+use common::*;
+use light_program_test::utils::assert::assert_rpc_error;
+use solana_launchpad::ErrorCode;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+
+const SUPPLY: u64 = 1_000_000_000_000;
+const PRICE: u64 = 1_000_000;
+const SOFT_CAP: u64 = 5_000_000;
+const HOUR: i64 = 3_600;
+
+#[tokio::test]
+async fn test_launch_rejects_invalid_soft_cap() {
+    let mut env = setup().await;
+    let creator = funded_keypair(&mut env.rpc).await;
+    let now = current_timestamp(&env.rpc);
+    let windowed = |price_per_token, limit_per_mint| {
+        LaunchArgs::new(SUPPLY, price_per_token, limit_per_mint)
+            .with_window(Some(now), Some(now + HOUR))
+            .with_soft_cap(SOFT_CAP)
+    };
+
+    let cases = [
+        LaunchArgs::new(SUPPLY, PRICE, 0).with_soft_cap(SOFT_CAP),
+        windowed(0, 1_000_000_000),
+        windowed(PRICE, 0).with_price_slope(1_000),
+        windowed(2 * PRICE, 0).with_auction(PRICE, true),
+    ];
+    for args in cases {
+        let sale = create_standard_sale_mint(&mut env.rpc, &creator, TOKEN_DECIMALS).await;
+        let result = launch_token(&mut env.rpc, &creator, &sale, args).await;
+        assert_rpc_error(result, 0, error_code(ErrorCode::InvalidSoftCap)).unwrap();
+    }
+}
+
+/// Launches a soft-capped sale ending in an hour and returns
+/// (creator, sale, creator_token_account, creator_usdc_account, end_ts)
+async fn launch_soft_cap_sale(env: &mut TestEnv) -> (Keypair, StandardSale, Pubkey, Pubkey, i64) {
+    let end_ts = current_timestamp(&env.rpc) + HOUR;
+    let (creator, sale, creator_usdc_account) = launch_sale(
+        env,
+        LaunchArgs::new(SUPPLY, PRICE, 0)
+            .with_window(None, Some(end_ts))
+            .with_soft_cap(SOFT_CAP),
+    )
+    .await;
+    let owner = env.owner.insecure_clone();
+    let creator_token_account =
+        create_token_account(&mut env.rpc, &owner, &sale.token_mint, &creator.pubkey()).await;
+    (
+        creator,
+        sale,
+        creator_token_account,
+        creator_usdc_account,
+        end_ts,
+    )
+}
+
+/// Creates a buyer and buys `usdc_amount` with their record
+async fn buyer_with_purchase(
+    env: &mut TestEnv,
+    sale: &StandardSale,
+    creator_usdc_account: Pubkey,
+    usdc_amount: u64,
+) -> (Keypair, BuyAccounts) {
+    let (buyer, accounts) = create_sale_buyer(env, sale, creator_usdc_account, 10_000_000).await;
+    buy_with_record(env, &buyer, sale, &accounts, usdc_amount)
+        .await
+        .unwrap();
+    (buyer, accounts)
+}
+
+#[tokio::test]
+async fn test_met_soft_cap_releases_escrow_on_finalize() {
+    let mut env = setup().await;
+    let (creator, sale, creator_token_account, creator_usdc_account, end_ts) =
+        launch_soft_cap_sale(&mut env).await;
+    let (first, first_accounts) =
+        buyer_with_purchase(&mut env, &sale, creator_usdc_account, 2_000_000).await;
+    buyer_with_purchase(&mut env, &sale, creator_usdc_account, 4_000_000).await;
+
+    // Payments are held in escrow until the sale closes
+    assert_eq!(token_balance(&mut env.rpc, &creator_usdc_account).await, 0);
+    assert_eq!(
+        token_balance(&mut env.rpc, &env.fee_recipient_usdc_account).await,
+        0
+    );
+    let token_sale = get_token_sale(&mut env.rpc, &sale.token_mint).await;
+    assert_eq!(token_sale.usdc_raised, 6_000_000);
+    assert_eq!(token_sale.usdc_reserve, 6_000_000);
+    let escrow = sale_escrow_pda(&sale.token_mint);
+    assert_eq!(token_balance(&mut env.rpc, &escrow).await, 6_000_000);
+
+    warp_to_timestamp(&mut env.rpc, end_ts);
+    let keeper = funded_keypair(&mut env.rpc).await;
+    let instruction =
+        finalize_sale_instruction(&env, &sale, creator_token_account, creator_usdc_account);
+    send(&mut env.rpc, instruction, &keeper, &[]).await.unwrap();
+
+    let fee = 6_000_000 * PLATFORM_FEE_BPS as u64 / 10_000;
+    assert_eq!(
+        token_balance(&mut env.rpc, &creator_usdc_account).await,
+        6_000_000 - fee
+    );
+    assert_eq!(
+        token_balance(&mut env.rpc, &env.fee_recipient_usdc_account).await,
+        fee
+    );
+    let token_sale = get_token_sale(&mut env.rpc, &sale.token_mint).await;
+    assert!(token_sale.proceeds_claimed);
+    assert_eq!(token_sale.usdc_reserve, 0);
+    assert_eq!(token_balance(&mut env.rpc, &escrow).await, 0);
+
+    let result = claim_refund(
+        &mut env,
+        RefundKind::SoftCap,
+        &first,
+        &sale,
+        &first_accounts,
+    )
+    .await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::SoftCapMet)).unwrap();
+    let instruction =
+        claim_reserve_instruction(&env, &creator.pubkey(), &sale, creator_usdc_account);
+    let result = send(&mut env.rpc, instruction, &creator, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::AlreadyClaimed)).unwrap();
+}
+
+#[tokio::test]
+async fn test_missed_soft_cap_refunds_buyers() {
+    let mut env = setup().await;
+    let (creator, sale, creator_token_account, creator_usdc_account, end_ts) =
+        launch_soft_cap_sale(&mut env).await;
+    let (buyer, accounts) =
+        buyer_with_purchase(&mut env, &sale, creator_usdc_account, 2_000_000).await;
+
+    let result = claim_refund(&mut env, RefundKind::SoftCap, &buyer, &sale, &accounts).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::SaleStillActive)).unwrap();
+
+    warp_to_timestamp(&mut env.rpc, end_ts);
+    let instruction =
+        finalize_sale_instruction(&env, &sale, creator_token_account, creator_usdc_account);
+    send(&mut env.rpc, instruction, &buyer, &[]).await.unwrap();
+    assert_eq!(token_balance(&mut env.rpc, &creator_usdc_account).await, 0);
+    assert_eq!(
+        token_balance(&mut env.rpc, &creator_token_account).await,
+        SUPPLY - 2_000_000_000
+    );
+
+    // The creator cannot take the escrow of a failed raise
+    let instruction =
+        claim_reserve_instruction(&env, &creator.pubkey(), &sale, creator_usdc_account);
+    let result = send(&mut env.rpc, instruction, &creator, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::SoftCapNotMet)).unwrap();
+
+    claim_refund(&mut env, RefundKind::SoftCap, &buyer, &sale, &accounts)
+        .await
+        .unwrap();
+    assert_eq!(
+        token_balance(&mut env.rpc, &accounts.buyer_usdc_account).await,
+        10_000_000
+    );
+    assert_eq!(
+        token_balance(&mut env.rpc, &accounts.buyer_token_account).await,
+        0
+    );
+    let (_, record) = get_buyer_record(&mut env.rpc, &sale.token_mint, &buyer.pubkey())
+        .await
+        .unwrap();
+    assert_eq!((record.tokens_bought, record.usdc_paid), (0, 0));
+    assert_eq!(
+        get_token_sale(&mut env.rpc, &sale.token_mint)
+            .await
+            .usdc_reserve,
+        0
+    );
+
+    let result = claim_refund(&mut env, RefundKind::SoftCap, &buyer, &sale, &accounts).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::NoRefundDue)).unwrap();
+}
+
+#[tokio::test]
+async fn test_escrow_is_held_per_sale() {
+    let mut env = setup().await;
+    let (_, first_sale, creator_token_account, first_usdc_account, end_ts) =
+        launch_soft_cap_sale(&mut env).await;
+    let (_, second_sale, _, second_usdc_account, _) = launch_soft_cap_sale(&mut env).await;
+    let (buyer, accounts) =
+        buyer_with_purchase(&mut env, &first_sale, first_usdc_account, 2_000_000).await;
+    buyer_with_purchase(&mut env, &second_sale, second_usdc_account, 3_000_000).await;
+
+    let first_escrow = sale_escrow_pda(&first_sale.token_mint);
+    let second_escrow = sale_escrow_pda(&second_sale.token_mint);
+    assert_eq!(token_balance(&mut env.rpc, &first_escrow).await, 2_000_000);
+    assert_eq!(token_balance(&mut env.rpc, &second_escrow).await, 3_000_000);
+
+    warp_to_timestamp(&mut env.rpc, end_ts);
+    let instruction =
+        finalize_sale_instruction(&env, &first_sale, creator_token_account, first_usdc_account);
+    send(&mut env.rpc, instruction, &buyer, &[]).await.unwrap();
+
+    // A refund cannot be drawn from another sale's escrow
+    let record = buyer_record_inputs(&mut env.rpc, &first_sale.token_mint, &buyer.pubkey()).await;
+    let mut instruction =
+        claim_refund_instruction(&env, &buyer.pubkey(), &first_sale, &accounts, record);
+    for meta in instruction
+        .accounts
+        .iter_mut()
+        .filter(|meta| meta.pubkey == first_escrow)
+    {
+        meta.pubkey = second_escrow;
+    }
+    let result = send(&mut env.rpc, instruction, &buyer, &[]).await;
+    assert_rpc_error(
+        result,
+        0,
+        anchor_lang::error::ErrorCode::ConstraintSeeds.into(),
+    )
+    .unwrap();
+
+    claim_refund(
+        &mut env,
+        RefundKind::SoftCap,
+        &buyer,
+        &first_sale,
+        &accounts,
+    )
+    .await
+    .unwrap();
+    assert_eq!(token_balance(&mut env.rpc, &first_escrow).await, 0);
+    assert_eq!(token_balance(&mut env.rpc, &second_escrow).await, 3_000_000);
+}
@@ -0,0 +1,176 @@
+#![cfg(feature = "test-sbf")]
+
+mod common;
+
+use common::*;
+use light_program_test::utils::assert::assert_rpc_error;
+use solana_launchpad::{ErrorCode, QuoteCurrency};
+use solana_sdk::{pubkey::Pubkey, signature::Signer};
+
+const SUPPLY: u64 = 1_000_000_000_000;
+/// 0.001 SOL per whole token
+const PRICE: u64 = 1_000_000;
+const WHOLE: u64 = 1_000_000_000;
+
+#[tokio::test]
+async fn test_sol_sale_pays_lamports() {
+    let mut env = setup().await;
+    let owner = env.owner.insecure_clone();
+    let (creator, sale, creator_usdc_account) = launch_sale(
+        &mut env,
+        LaunchArgs::new(SUPPLY, PRICE, 0).with_quote_currency(QuoteCurrency::Sol),
+    )
+    .await;
+    let buyer = funded_keypair(&mut env.rpc).await;
+    let buyer_token_account =
+        create_token_account(&mut env.rpc, &owner, &sale.token_mint, &buyer.pubkey()).await;
+    let creator_lamports = lamport_balance(&mut env.rpc, &creator.pubkey()).await;
+    let fee_recipient_lamports = lamport_balance(&mut env.rpc, &owner.pubkey()).await;
+
+    let instruction = buy_tokens_sol_instruction(
+        &env,
+        &buyer.pubkey(),
+        &sale,
+        buyer_token_account,
+        creator.pubkey(),
+        owner.pubkey(),
+        3_000_000,
+    );
+    send(&mut env.rpc, instruction, &buyer, &[]).await.unwrap();
+
+    let fee = 3_000_000 * PLATFORM_FEE_BPS as u64 / 10_000;
+    assert_eq!(
+        token_balance(&mut env.rpc, &buyer_token_account).await,
+        3 * WHOLE
+    );
+    assert_eq!(
+        lamport_balance(&mut env.rpc, &creator.pubkey()).await,
+        creator_lamports + 3_000_000 - fee
+    );
+    assert_eq!(
+        lamport_balance(&mut env.rpc, &owner.pubkey()).await,
+        fee_recipient_lamports + fee
+    );
+    let token_sale = get_token_sale(&mut env.rpc, &sale.token_mint).await;
+    assert_eq!(token_sale.quote_currency, QuoteCurrency::Sol);
+    assert_eq!(token_sale.quote_decimals, 9);
+    assert_eq!(token_sale.usdc_raised, 3_000_000);
+
+    // USDC accounts cannot pay for a SOL sale
+    let (usdc_buyer, accounts) =
+        create_sale_buyer(&mut env, &sale, creator_usdc_account, 10_000_000).await;
+    let instruction =
+        buy_tokens_instruction(&env, &usdc_buyer.pubkey(), &sale, &accounts, 1_000_000);
+    let result = send(&mut env.rpc, instruction, &usdc_buyer, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::InvalidMint)).unwrap();
+}
+
+#[tokio::test]
+async fn test_sol_payment_keeps_recipients_rent_exempt() {
+    let mut env = setup().await;
+    let owner = env.owner.insecure_clone();
+    let (creator, sale, _) = launch_sale(
+        &mut env,
+        LaunchArgs::new(SUPPLY, PRICE, 0).with_quote_currency(QuoteCurrency::Sol),
+    )
+    .await;
+    // A new wallet that holds no lamports yet
+    let treasury = Pubkey::new_unique();
+    update_fee_recipient(&mut env.rpc, &owner, treasury)
+        .await
+        .unwrap();
+    let buyer = funded_keypair(&mut env.rpc).await;
+    let buyer_token_account =
+        create_token_account(&mut env.rpc, &owner, &sale.token_mint, &buyer.pubkey()).await;
+
+    // A 150_000 lamport fee is below the rent-exempt minimum of an empty wallet
+    let instruction = buy_tokens_sol_instruction(
+        &env,
+        &buyer.pubkey(),
+        &sale,
+        buyer_token_account,
+        creator.pubkey(),
+        treasury,
+        3_000_000,
+    );
+    let result = send(&mut env.rpc, instruction, &buyer, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::RecipientNotRentExempt)).unwrap();
+
+    // A 1_000_000 lamport fee is above it
+    let instruction = buy_tokens_sol_instruction(
+        &env,
+        &buyer.pubkey(),
+        &sale,
+        buyer_token_account,
+        creator.pubkey(),
+        treasury,
+        20_000_000,
+    );
+    send(&mut env.rpc, instruction, &buyer, &[]).await.unwrap();
+    assert_eq!(lamport_balance(&mut env.rpc, &treasury).await, 1_000_000);
+}
+
+#[tokio::test]
+async fn test_sol_sale_rejects_reserve_features() {
+    let mut env = setup().await;
+    let creator = funded_keypair(&mut env.rpc).await;
+    let sale = create_standard_sale_mint(&mut env.rpc, &creator, TOKEN_DECIMALS).await;
+
+    let result = launch_token(
+        &mut env.rpc,
+        &creator,
+        &sale,
+        LaunchArgs::new(SUPPLY, PRICE, 0)
+            .with_price_slope(1_000)
+            .with_quote_currency(QuoteCurrency::Sol),
+    )
+    .await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::InvalidQuoteCurrency)).unwrap();
+}
+
+#[tokio::test]
+async fn test_compressed_sol_sale_pays_lamports() {
+    let mut env = setup().await;
+    let owner = env.owner.insecure_clone();
+    let creator = funded_keypair(&mut env.rpc).await;
+    let token_mint = create_compressed_sale_mint(&mut env.rpc, &creator).await;
+    launch_token_compressed(
+        &mut env.rpc,
+        &creator,
+        token_mint,
+        LaunchArgs::new(SUPPLY, PRICE, 0).with_quote_currency(QuoteCurrency::Sol),
+    )
+    .await
+    .unwrap();
+    let buyer = funded_keypair(&mut env.rpc).await;
+    let creator_lamports = lamport_balance(&mut env.rpc, &creator.pubkey()).await;
+    let fee_recipient_lamports = lamport_balance(&mut env.rpc, &owner.pubkey()).await;
+
+    let inputs = compressed_sale_inputs(&mut env.rpc, &token_mint, &token_mint).await;
+    let instruction = buy_tokens_compressed_sol_instruction(
+        &env,
+        &buyer.pubkey(),
+        creator.pubkey(),
+        token_mint,
+        inputs,
+        2_000_000,
+    );
+    send(&mut env.rpc, instruction, &buyer, &[]).await.unwrap();
+
+    let fee = 2_000_000 * PLATFORM_FEE_BPS as u64 / 10_000;
+    assert_eq!(
+        compressed_token_balance(&mut env.rpc, &buyer.pubkey(), &token_mint).await,
+        2 * WHOLE
+    );
+    assert_eq!(
+        lamport_balance(&mut env.rpc, &creator.pubkey()).await,
+        creator_lamports + 2_000_000 - fee
+    );
+    assert_eq!(
+        lamport_balance(&mut env.rpc, &owner.pubkey()).await,
+        fee_recipient_lamports + fee
+    );
+    let (_, sale) = get_compressed_sale(&mut env.rpc, &token_mint).await;
+    assert_eq!(sale.quote_currency, QuoteCurrency::Sol);
+    assert_eq!(sale.usdc_raised, 2_000_000);
+}
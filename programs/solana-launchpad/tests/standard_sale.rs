@@ -0,0 +1,418 @@
+#![cfg(feature = "test-sbf")]
+
+mod common;
+
+use common::*;
+use light_program_test::utils::assert::assert_rpc_error;
+use solana_launchpad::ErrorCode;
+use solana_sdk::signature::{Keypair, Signer};
+
+const SUPPLY: u64 = 1_000_000_000_000;
+const PRICE: u64 = 1_000_000;
+
+#[tokio::test]
+async fn test_standard_sale_lifecycle() {
+    let mut env = setup().await;
+    let (creator, sale, creator_usdc_account) =
+        launch_sale(&mut env, LaunchArgs::new(SUPPLY, PRICE, 0)).await;
+
+    let token_sale = get_token_sale(&mut env.rpc, &sale.token_mint).await;
+    assert_eq!(token_sale.creator, creator.pubkey());
+    assert_eq!(token_sale.token_mint, sale.token_mint);
+    assert_eq!(token_sale.price_per_token, PRICE);
+    assert_eq!(token_sale.supply_for_sale, SUPPLY);
+    assert_eq!(token_sale.tokens_sold, 0);
+    assert_eq!(token_sale.decimals, TOKEN_DECIMALS);
+    assert!(token_sale.active);
+    assert_eq!(
+        token_balance(&mut env.rpc, &sale.sale_token_account).await,
+        SUPPLY
+    );
+
+    // 1 USDC at 1 USDC per token buys one whole token
+    let (buyer, accounts) =
+        create_sale_buyer(&mut env, &sale, creator_usdc_account, 10_000_000).await;
+    let instruction = buy_tokens_instruction(&env, &buyer.pubkey(), &sale, &accounts, 1_000_000);
+    send(&mut env.rpc, instruction, &buyer, &[]).await.unwrap();
+
+    let tokens_bought = 1_000_000_000;
+    let fee = 1_000_000 * PLATFORM_FEE_BPS as u64 / 10_000;
+    assert_eq!(
+        token_balance(&mut env.rpc, &accounts.buyer_token_account).await,
+        tokens_bought
+    );
+    assert_eq!(
+        token_balance(&mut env.rpc, &accounts.buyer_usdc_account).await,
+        9_000_000
+    );
+    assert_eq!(
+        token_balance(&mut env.rpc, &env.fee_recipient_usdc_account).await,
+        fee
+    );
+    assert_eq!(
+        token_balance(&mut env.rpc, &creator_usdc_account).await,
+        1_000_000 - fee
+    );
+    assert_eq!(
+        token_balance(&mut env.rpc, &sale_escrow_pda(&sale.token_mint)).await,
+        0
+    );
+    assert_eq!(
+        get_token_sale(&mut env.rpc, &sale.token_mint)
+            .await
+            .tokens_sold,
+        tokens_bought
+    );
+
+    // Closing returns the unsold supply to the creator
+    let owner = env.owner.insecure_clone();
+    let creator_token_account =
+        create_token_account(&mut env.rpc, &owner, &sale.token_mint, &creator.pubkey()).await;
+    let instruction = close_sale_instruction(&creator.pubkey(), &sale, creator_token_account);
+    send(&mut env.rpc, instruction, &creator, &[])
+        .await
+        .unwrap();
+
+    assert!(!get_token_sale(&mut env.rpc, &sale.token_mint).await.active);
+    assert_eq!(
+        token_balance(&mut env.rpc, &creator_token_account).await,
+        SUPPLY - tokens_bought
+    );
+    assert_eq!(
+        token_balance(&mut env.rpc, &sale.sale_token_account).await,
+        0
+    );
+
+    // Buying from a closed sale fails
+    let instruction = buy_tokens_instruction(&env, &buyer.pubkey(), &sale, &accounts, 1_000_000);
+    let result = send(&mut env.rpc, instruction, &buyer, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::SaleNotActive)).unwrap();
+
+    // Closing twice fails
+    let instruction = close_sale_instruction(&creator.pubkey(), &sale, creator_token_account);
+    let result = send(&mut env.rpc, instruction, &creator, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::AlreadyClosed)).unwrap();
+}
+
+#[tokio::test]
+async fn test_free_mint() {
+    let mut env = setup().await;
+    let limit = 5_000_000_000;
+    let (_creator, sale, creator_usdc_account) =
+        launch_sale(&mut env, LaunchArgs::new(SUPPLY, 0, limit)).await;
+    let (buyer, accounts) =
+        create_sale_buyer(&mut env, &sale, creator_usdc_account, 1_000_000).await;
+
+    let instruction = buy_tokens_instruction(&env, &buyer.pubkey(), &sale, &accounts, 1);
+    let result = send(&mut env.rpc, instruction, &buyer, &[]).await;
+    assert_rpc_error(
+        result,
+        0,
+        error_code(ErrorCode::FreeMintRequiresZeroPayment),
+    )
+    .unwrap();
+
+    let instruction = buy_tokens_instruction(&env, &buyer.pubkey(), &sale, &accounts, 0);
+    send(&mut env.rpc, instruction, &buyer, &[]).await.unwrap();
+
+    assert_eq!(
+        token_balance(&mut env.rpc, &accounts.buyer_token_account).await,
+        limit
+    );
+    assert_eq!(
+        token_balance(&mut env.rpc, &accounts.buyer_usdc_account).await,
+        1_000_000
+    );
+}
+
+#[tokio::test]
+async fn test_sale_deactivates_when_sold_out() {
+    let mut env = setup().await;
+    let supply = 2_000_000_000;
+    let (_creator, sale, creator_usdc_account) =
+        launch_sale(&mut env, LaunchArgs::new(supply, PRICE, 0)).await;
+    let (buyer, accounts) =
+        create_sale_buyer(&mut env, &sale, creator_usdc_account, 10_000_000).await;
+
+    // 3 tokens requested but only 2 remain
+    let instruction = buy_tokens_instruction(&env, &buyer.pubkey(), &sale, &accounts, 3_000_000);
+    let result = send(&mut env.rpc, instruction, &buyer, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::InsufficientSupply)).unwrap();
+
+    let instruction = buy_tokens_instruction(&env, &buyer.pubkey(), &sale, &accounts, 2_000_000);
+    send(&mut env.rpc, instruction, &buyer, &[]).await.unwrap();
+
+    let token_sale = get_token_sale(&mut env.rpc, &sale.token_mint).await;
+    assert_eq!(token_sale.tokens_sold, supply);
+    assert!(!token_sale.active);
+}
+
+#[tokio::test]
+async fn test_launch_validation() {
+    let mut env = setup().await;
+    let creator = funded_keypair(&mut env.rpc).await;
+    let sale = create_standard_sale_mint(&mut env.rpc, &creator, TOKEN_DECIMALS).await;
+    let max_supply = 10u64.pow(TOKEN_DECIMALS as u32) * 1_000_000_000;
+
+    let cases = [
+        (
+            LaunchArgs {
+                name: String::new(),
+                ..LaunchArgs::new(SUPPLY, PRICE, 0)
+            },
+            ErrorCode::InvalidNameLength,
+        ),
+        (
+            LaunchArgs {
+                name: "N".repeat(33),
+                ..LaunchArgs::new(SUPPLY, PRICE, 0)
+            },
+            ErrorCode::InvalidNameLength,
+        ),
+        (
+            LaunchArgs {
+                symbol: String::new(),
+                ..LaunchArgs::new(SUPPLY, PRICE, 0)
+            },
+            ErrorCode::InvalidSymbolLength,
+        ),
+        (
+            LaunchArgs {
+                symbol: "S".repeat(11),
+                ..LaunchArgs::new(SUPPLY, PRICE, 0)
+            },
+            ErrorCode::InvalidSymbolLength,
+        ),
+        (
+            LaunchArgs {
+                metadata_id: "m".repeat(101),
+                ..LaunchArgs::new(SUPPLY, PRICE, 0)
+            },
+            ErrorCode::MetadataIdTooLong,
+        ),
+        (LaunchArgs::new(0, PRICE, 0), ErrorCode::InvalidSupply),
+        (
+            LaunchArgs::new(max_supply + 1, PRICE, 0),
+            ErrorCode::SupplyTooLarge,
+        ),
+        (
+            LaunchArgs::new(SUPPLY, 0, 0),
+            ErrorCode::FreeMintRequiresLimit,
+        ),
+        (LaunchArgs::new(SUPPLY, 999, 0), ErrorCode::PriceTooLow),
+        (
+            LaunchArgs::new(SUPPLY, PRICE, SUPPLY + 1),
+            ErrorCode::LimitExceedsSupply,
+        ),
+    ];
+    for (args, expected) in cases {
+        let result = launch_token(&mut env.rpc, &creator, &sale, args).await;
+        assert_rpc_error(result, 0, error_code(expected)).unwrap();
+    }
+
+    // The mint is still usable once the arguments are valid
+    launch_token(
+        &mut env.rpc,
+        &creator,
+        &sale,
+        LaunchArgs::new(max_supply, PRICE, 0),
+    )
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn test_buy_validation() {
+    let mut env = setup().await;
+    let limit = 2_000_000_000;
+    let (_creator, sale, creator_usdc_account) =
+        launch_sale(&mut env, LaunchArgs::new(SUPPLY, PRICE, limit)).await;
+    let (buyer, accounts) =
+        create_sale_buyer(&mut env, &sale, creator_usdc_account, 10_000_000).await;
+
+    let instruction = buy_tokens_instruction(&env, &buyer.pubkey(), &sale, &accounts, 0);
+    let result = send(&mut env.rpc, instruction, &buyer, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::AmountMustBePositive)).unwrap();
+
+    let instruction = buy_tokens_instruction(&env, &buyer.pubkey(), &sale, &accounts, 3_000_000);
+    let result = send(&mut env.rpc, instruction, &buyer, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::ExceedsMintLimit)).unwrap();
+
+    assert_eq!(
+        token_balance(&mut env.rpc, &accounts.buyer_usdc_account).await,
+        10_000_000
+    );
+}
+
+#[tokio::test]
+async fn test_buy_rejects_amount_worth_zero_tokens() {
+    let mut env = setup().await;
+    let creator = funded_keypair(&mut env.rpc).await;
+    let sale = create_standard_sale_mint(&mut env.rpc, &creator, 0).await;
+    launch_token(
+        &mut env.rpc,
+        &creator,
+        &sale,
+        LaunchArgs::new(1_000, PRICE, 0),
+    )
+    .await
+    .unwrap();
+    let owner = env.owner.insecure_clone();
+    let creator_usdc_account =
+        create_token_account(&mut env.rpc, &owner, &env.usdc_mint, &creator.pubkey()).await;
+    let (buyer, accounts) =
+        create_sale_buyer(&mut env, &sale, creator_usdc_account, 10_000_000).await;
+
+    // Less than the price of one indivisible token
+    let instruction = buy_tokens_instruction(&env, &buyer.pubkey(), &sale, &accounts, PRICE - 1);
+    let result = send(&mut env.rpc, instruction, &buyer, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::PurchaseAmountTooSmall)).unwrap();
+}
+
+#[tokio::test]
+async fn test_buy_charges_only_whole_tokens() {
+    let mut env = setup().await;
+    let creator = funded_keypair(&mut env.rpc).await;
+    let sale = create_standard_sale_mint(&mut env.rpc, &creator, 0).await;
+    launch_token(
+        &mut env.rpc,
+        &creator,
+        &sale,
+        LaunchArgs::new(1_000, PRICE, 0),
+    )
+    .await
+    .unwrap();
+    let owner = env.owner.insecure_clone();
+    let creator_usdc_account =
+        create_token_account(&mut env.rpc, &owner, &env.usdc_mint, &creator.pubkey()).await;
+    let (buyer, accounts) =
+        create_sale_buyer(&mut env, &sale, creator_usdc_account, 10_000_000).await;
+
+    // 2.5 USDC buys two indivisible tokens; the remaining 0.5 USDC stays with the buyer
+    let instruction = buy_tokens_instruction(&env, &buyer.pubkey(), &sale, &accounts, 2_500_000);
+    send(&mut env.rpc, instruction, &buyer, &[]).await.unwrap();
+
+    let fee = 2 * PRICE * PLATFORM_FEE_BPS as u64 / 10_000;
+    assert_eq!(
+        token_balance(&mut env.rpc, &accounts.buyer_token_account).await,
+        2
+    );
+    assert_eq!(
+        token_balance(&mut env.rpc, &accounts.buyer_usdc_account).await,
+        10_000_000 - 2 * PRICE
+    );
+    assert_eq!(
+        token_balance(&mut env.rpc, &creator_usdc_account).await,
+        2 * PRICE - fee
+    );
+    assert_eq!(
+        get_token_sale(&mut env.rpc, &sale.token_mint)
+            .await
+            .usdc_raised,
+        2 * PRICE
+    );
+}
+
+#[tokio::test]
+async fn test_buy_rejects_wrong_accounts() {
+    let mut env = setup().await;
+    let (_creator, sale, creator_usdc_account) =
+        launch_sale(&mut env, LaunchArgs::new(SUPPLY, PRICE, 0)).await;
+    let (buyer, accounts) =
+        create_sale_buyer(&mut env, &sale, creator_usdc_account, 10_000_000).await;
+
+    // Creator share routed back to the buyer
+    let redirected = BuyAccounts {
+        creator_usdc_account: accounts.buyer_usdc_account,
+        ..accounts
+    };
+    let instruction = buy_tokens_instruction(&env, &buyer.pubkey(), &sale, &redirected, 1_000_000);
+    let result = send(&mut env.rpc, instruction, &buyer, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::InvalidTokenAccountOwner)).unwrap();
+
+    // Paying with a USDC look-alike
+    let fake_usdc = create_mint(&mut env.rpc, &buyer, &buyer.pubkey(), USDC_DECIMALS).await;
+    let fake_usdc_account =
+        create_token_account(&mut env.rpc, &buyer, &fake_usdc, &buyer.pubkey()).await;
+    mint_tokens(
+        &mut env.rpc,
+        &buyer,
+        &fake_usdc,
+        &fake_usdc_account,
+        10_000_000,
+    )
+    .await;
+    let fake = BuyAccounts {
+        buyer_usdc_account: fake_usdc_account,
+        ..accounts
+    };
+    let instruction = buy_tokens_instruction(&env, &buyer.pubkey(), &sale, &fake, 1_000_000);
+    let result = send(&mut env.rpc, instruction, &buyer, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::InvalidMint)).unwrap();
+
+    assert_eq!(
+        get_token_sale(&mut env.rpc, &sale.token_mint)
+            .await
+            .tokens_sold,
+        0
+    );
+}
+
+#[tokio::test]
+async fn test_fees_go_to_fee_recipient() {
+    let mut env = setup().await;
+    let owner = env.owner.insecure_clone();
+    let treasury = Keypair::new();
+    let treasury_usdc_account =
+        create_token_account(&mut env.rpc, &owner, &env.usdc_mint, &treasury.pubkey()).await;
+    update_fee_recipient(&mut env.rpc, &owner, treasury.pubkey())
+        .await
+        .unwrap();
+
+    let (_creator, sale, creator_usdc_account) =
+        launch_sale(&mut env, LaunchArgs::new(SUPPLY, PRICE, 0)).await;
+    let (buyer, accounts) =
+        create_sale_buyer(&mut env, &sale, creator_usdc_account, 10_000_000).await;
+
+    // The owner's own USDC account no longer receives fees
+    let instruction = buy_tokens_instruction(&env, &buyer.pubkey(), &sale, &accounts, 1_000_000);
+    let result = send(&mut env.rpc, instruction, &buyer, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::InvalidTokenAccountOwner)).unwrap();
+
+    let owner_usdc_account = env.fee_recipient_usdc_account;
+    env.fee_recipient_usdc_account = treasury_usdc_account;
+    let instruction = buy_tokens_instruction(&env, &buyer.pubkey(), &sale, &accounts, 1_000_000);
+    send(&mut env.rpc, instruction, &buyer, &[]).await.unwrap();
+
+    let fee = 1_000_000 * PLATFORM_FEE_BPS as u64 / 10_000;
+    assert_eq!(
+        token_balance(&mut env.rpc, &treasury_usdc_account).await,
+        fee
+    );
+    assert_eq!(token_balance(&mut env.rpc, &owner_usdc_account).await, 0);
+}
+
+#[tokio::test]
+async fn test_close_rejects_non_creator() {
+    let mut env = setup().await;
+    let (_creator, sale, _) = launch_sale(&mut env, LaunchArgs::new(SUPPLY, PRICE, 0)).await;
+    let attacker = funded_keypair(&mut env.rpc).await;
+    let owner = env.owner.insecure_clone();
+    let attacker_token_account =
+        create_token_account(&mut env.rpc, &owner, &sale.token_mint, &attacker.pubkey()).await;
+
+    let instruction = close_sale_instruction(&attacker.pubkey(), &sale, attacker_token_account);
+    let result = send(&mut env.rpc, instruction, &attacker, &[]).await;
+    assert_rpc_error(
+        result,
+        0,
+        anchor_lang::error::ErrorCode::ConstraintHasOne.into(),
+    )
+    .unwrap();
+
+    assert!(get_token_sale(&mut env.rpc, &sale.token_mint).await.active);
+    assert_eq!(
+        token_balance(&mut env.rpc, &attacker_token_account).await,
+        0
+    );
+}
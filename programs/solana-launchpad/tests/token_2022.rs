@@ -0,0 +1,176 @@
+#![cfg(feature = "test-sbf")]
+
+mod common;
+
+use common::*;
+use light_program_test::utils::assert::assert_rpc_error;
+use solana_launchpad::ErrorCode;
+use solana_sdk::{pubkey::Pubkey, signature::Signer};
+
+const SUPPLY: u64 = 1_000_000_000_000;
+const PRICE: u64 = 1_000_000;
+const WHOLE: u64 = 1_000_000_000;
+const DAY: i64 = 86_400;
+
+#[tokio::test]
+async fn test_transfer_fee_comes_out_of_unsold_supply() {
+    let mut env = setup().await;
+    let owner = env.owner.insecure_clone();
+    let creator = funded_keypair(&mut env.rpc).await;
+    let sale = create_token_2022_sale_mint(
+        &mut env.rpc,
+        &creator,
+        TOKEN_DECIMALS,
+        SaleMintExtension::TransferFee {
+            basis_points: 100,
+            maximum_fee: u64::MAX,
+        },
+    )
+    .await;
+    launch_token(
+        &mut env.rpc,
+        &creator,
+        &sale,
+        LaunchArgs::new(SUPPLY, PRICE, 0),
+    )
+    .await
+    .unwrap();
+    let creator_usdc_account =
+        create_token_account(&mut env.rpc, &owner, &env.usdc_mint, &creator.pubkey()).await;
+    let (buyer, accounts) =
+        create_sale_buyer(&mut env, &sale, creator_usdc_account, 10_000_000).await;
+
+    let instruction = buy_tokens_instruction(&env, &buyer.pubkey(), &sale, &accounts, 3_000_000);
+    send(&mut env.rpc, instruction, &buyer, &[]).await.unwrap();
+
+    // The buyer receives every token paid for
+    assert_eq!(
+        token_balance(&mut env.rpc, &accounts.buyer_token_account).await,
+        3 * WHOLE
+    );
+    let token_sale = get_token_sale(&mut env.rpc, &sale.token_mint).await;
+    assert_eq!(token_sale.tokens_sold, 3 * WHOLE);
+    assert_eq!(token_sale.usdc_raised, 3_000_000);
+    // and the 1% fee withheld on top is taken from what is left to sell:
+    // 3_030_303_031 is sent so that 3 tokens arrive after the fee
+    assert_eq!(SUPPLY - token_sale.supply_for_sale, 30_303_031);
+    assert_eq!(
+        withheld_transfer_fee(&mut env.rpc, &accounts.buyer_token_account).await,
+        30_303_031
+    );
+    assert_eq!(
+        token_balance(&mut env.rpc, &sale.sale_token_account).await,
+        token_sale.supply_for_sale - token_sale.tokens_sold
+    );
+}
+
+#[tokio::test]
+async fn test_vested_claims_bear_the_transfer_fee() {
+    let mut env = setup().await;
+    let owner = env.owner.insecure_clone();
+    let creator = funded_keypair(&mut env.rpc).await;
+    let start_ts = current_timestamp(&env.rpc);
+    let sale = create_token_2022_sale_mint(
+        &mut env.rpc,
+        &creator,
+        TOKEN_DECIMALS,
+        SaleMintExtension::TransferFee {
+            basis_points: 100,
+            maximum_fee: u64::MAX,
+        },
+    )
+    .await;
+    launch_token(
+        &mut env.rpc,
+        &creator,
+        &sale,
+        LaunchArgs::new(SUPPLY, PRICE, 0).with_vesting(start_ts, start_ts, start_ts + DAY),
+    )
+    .await
+    .unwrap();
+    let creator_usdc_account =
+        create_token_account(&mut env.rpc, &owner, &env.usdc_mint, &creator.pubkey()).await;
+    let (buyer, accounts) =
+        create_sale_buyer(&mut env, &sale, creator_usdc_account, 10_000_000).await;
+
+    let record = buyer_record_inputs(&mut env.rpc, &sale.token_mint, &buyer.pubkey()).await;
+    let instruction =
+        buy_vesting_tokens_instruction(&env, &buyer.pubkey(), &sale, &accounts, 3_000_000, record);
+    send(&mut env.rpc, instruction, &buyer, &[]).await.unwrap();
+
+    // The purchase reaches the vault in full, its fee taken from the unsold supply
+    let vesting_vault = vesting_vault_pda(&sale.token_mint);
+    assert_eq!(token_balance(&mut env.rpc, &vesting_vault).await, 3 * WHOLE);
+    let token_sale = get_token_sale(&mut env.rpc, &sale.token_mint).await;
+    assert_eq!(SUPPLY - token_sale.supply_for_sale, 30_303_031);
+
+    // but the 1% fee on the release is withheld from what the buyer receives
+    warp_to_timestamp(&mut env.rpc, start_ts + DAY);
+    let record = buyer_record_inputs(&mut env.rpc, &sale.token_mint, &buyer.pubkey()).await;
+    let instruction =
+        claim_vested_instruction(&buyer.pubkey(), &sale, accounts.buyer_token_account, record);
+    send(&mut env.rpc, instruction, &buyer, &[]).await.unwrap();
+    assert_eq!(
+        token_balance(&mut env.rpc, &accounts.buyer_token_account).await,
+        3 * WHOLE - 30_000_000
+    );
+    assert_eq!(
+        withheld_transfer_fee(&mut env.rpc, &accounts.buyer_token_account).await,
+        30_000_000
+    );
+    assert_eq!(token_balance(&mut env.rpc, &vesting_vault).await, 0);
+}
+
+#[tokio::test]
+async fn test_launch_rejects_non_transferable_mint() {
+    let mut env = setup().await;
+    let creator = funded_keypair(&mut env.rpc).await;
+    let sale = create_token_2022_sale_mint(
+        &mut env.rpc,
+        &creator,
+        TOKEN_DECIMALS,
+        SaleMintExtension::NonTransferable,
+    )
+    .await;
+
+    let result = launch_token(
+        &mut env.rpc,
+        &creator,
+        &sale,
+        LaunchArgs::new(SUPPLY, PRICE, 0),
+    )
+    .await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::UnsupportedMintExtension)).unwrap();
+}
+
+/// Creates a Token-2022 sale mint with `extension` and expects the launch to be rejected
+async fn assert_launch_rejected(extension: SaleMintExtension) {
+    let mut env = setup().await;
+    let creator = funded_keypair(&mut env.rpc).await;
+    let sale = create_token_2022_sale_mint(&mut env.rpc, &creator, TOKEN_DECIMALS, extension).await;
+
+    let result = launch_token(
+        &mut env.rpc,
+        &creator,
+        &sale,
+        LaunchArgs::new(SUPPLY, PRICE, 0),
+    )
+    .await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::UnsupportedMintExtension)).unwrap();
+}
+
+#[tokio::test]
+async fn test_launch_rejects_foreign_permanent_delegate() {
+    // A delegate other than the sale could move tokens out of the sale account
+    assert_launch_rejected(SaleMintExtension::PermanentDelegate(Pubkey::new_unique())).await;
+}
+
+#[tokio::test]
+async fn test_launch_rejects_transfer_hook() {
+    assert_launch_rejected(SaleMintExtension::TransferHook(Pubkey::new_unique())).await;
+}
+
+#[tokio::test]
+async fn test_launch_rejects_default_frozen_mint() {
+    assert_launch_rejected(SaleMintExtension::DefaultFrozen).await;
+}
@@ -0,0 +1,231 @@
+#![cfg(feature = "test-sbf")]
+
+mod common;
+
+use common::*;
+use light_program_test::{utils::assert::assert_rpc_error, RpcError};
+use solana_launchpad::ErrorCode;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+};
+
+const SUPPLY: u64 = 1_000_000_000_000;
+const PRICE: u64 = 1_000_000;
+const WHOLE: u64 = 1_000_000_000;
+const DAY: i64 = 86_400;
+
+async fn claim_vested(
+    env: &mut TestEnv,
+    buyer: &Keypair,
+    sale: &StandardSale,
+    buyer_token_account: Pubkey,
+) -> Result<Signature, RpcError> {
+    let record = buyer_record_inputs(&mut env.rpc, &sale.token_mint, &buyer.pubkey()).await;
+    let instruction = claim_vested_instruction(&buyer.pubkey(), sale, buyer_token_account, record);
+    send(&mut env.rpc, instruction, buyer, &[]).await
+}
+
+#[tokio::test]
+async fn test_launch_rejects_invalid_vesting() {
+    let mut env = setup().await;
+    let creator = funded_keypair(&mut env.rpc).await;
+    let now = current_timestamp(&env.rpc);
+
+    let cases = [
+        LaunchArgs::new(SUPPLY, PRICE, 0).with_vesting(now, now - 1, now + DAY),
+        LaunchArgs::new(SUPPLY, PRICE, 0).with_vesting(now, now + 2 * DAY, now + DAY),
+        LaunchArgs::new(SUPPLY, PRICE, 0).with_vesting(now, now, now),
+        LaunchArgs::new(SUPPLY, PRICE, 0)
+            .with_window(None, Some(now + DAY))
+            .with_soft_cap(PRICE)
+            .with_vesting(now, now, now + DAY),
+    ];
+    for args in cases {
+        let sale = create_standard_sale_mint(&mut env.rpc, &creator, TOKEN_DECIMALS).await;
+        let result = launch_token(&mut env.rpc, &creator, &sale, args).await;
+        assert_rpc_error(result, 0, error_code(ErrorCode::InvalidVesting)).unwrap();
+    }
+}
+
+#[tokio::test]
+async fn test_purchases_vest_linearly_after_cliff() {
+    let mut env = setup().await;
+    let owner = env.owner.insecure_clone();
+    let creator = funded_keypair(&mut env.rpc).await;
+    let start_ts = current_timestamp(&env.rpc);
+    let sale = create_standard_sale_mint(&mut env.rpc, &creator, TOKEN_DECIMALS).await;
+    launch_token(
+        &mut env.rpc,
+        &creator,
+        &sale,
+        LaunchArgs::new(SUPPLY, PRICE, 0).with_vesting(
+            start_ts,
+            start_ts + DAY,
+            start_ts + 4 * DAY,
+        ),
+    )
+    .await
+    .unwrap();
+    let creator_usdc_account =
+        create_token_account(&mut env.rpc, &owner, &env.usdc_mint, &creator.pubkey()).await;
+    let (buyer, accounts) =
+        create_sale_buyer(&mut env, &sale, creator_usdc_account, 10_000_000).await;
+
+    // Vesting sales keep a record of every buyer
+    let instruction = buy_tokens_instruction(&env, &buyer.pubkey(), &sale, &accounts, 4_000_000);
+    let result = send(&mut env.rpc, instruction, &buyer, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::BuyerRecordRequired)).unwrap();
+
+    let record = buyer_record_inputs(&mut env.rpc, &sale.token_mint, &buyer.pubkey()).await;
+    let instruction =
+        buy_vesting_tokens_instruction(&env, &buyer.pubkey(), &sale, &accounts, 4_000_000, record);
+    send(&mut env.rpc, instruction, &buyer, &[]).await.unwrap();
+    assert_eq!(
+        token_balance(&mut env.rpc, &accounts.buyer_token_account).await,
+        0
+    );
+    let vesting_vault = vesting_vault_pda(&sale.token_mint);
+    assert_eq!(token_balance(&mut env.rpc, &vesting_vault).await, 4 * WHOLE);
+
+    // Nothing unlocks before the cliff
+    let result = claim_vested(&mut env, &buyer, &sale, accounts.buyer_token_account).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::NothingVested)).unwrap();
+
+    // Half way through, half has vested
+    warp_to_timestamp(&mut env.rpc, start_ts + 2 * DAY);
+    claim_vested(&mut env, &buyer, &sale, accounts.buyer_token_account)
+        .await
+        .unwrap();
+    assert_eq!(
+        token_balance(&mut env.rpc, &accounts.buyer_token_account).await,
+        2 * WHOLE
+    );
+    let result = claim_vested(&mut env, &buyer, &sale, accounts.buyer_token_account).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::NothingVested)).unwrap();
+
+    warp_to_timestamp(&mut env.rpc, start_ts + 5 * DAY);
+    claim_vested(&mut env, &buyer, &sale, accounts.buyer_token_account)
+        .await
+        .unwrap();
+    assert_eq!(
+        token_balance(&mut env.rpc, &accounts.buyer_token_account).await,
+        4 * WHOLE
+    );
+    assert_eq!(token_balance(&mut env.rpc, &vesting_vault).await, 0);
+    let (_, record) = get_buyer_record(&mut env.rpc, &sale.token_mint, &buyer.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(record.tokens_claimed, 4 * WHOLE);
+}
+
+#[tokio::test]
+async fn test_claim_requires_vesting_sale() {
+    let mut env = setup().await;
+    let (_, sale, creator_usdc_account) = launch_sale(
+        &mut env,
+        LaunchArgs::new(SUPPLY, PRICE, 0).with_max_per_wallet(10 * WHOLE),
+    )
+    .await;
+    let (buyer, accounts) =
+        create_sale_buyer(&mut env, &sale, creator_usdc_account, 10_000_000).await;
+    let record = buyer_record_inputs(&mut env.rpc, &sale.token_mint, &buyer.pubkey()).await;
+    let instruction = buy_tokens_instruction_with(
+        &env,
+        &buyer.pubkey(),
+        &sale,
+        &accounts,
+        1_000_000,
+        0,
+        None,
+        Some(record),
+    );
+    send(&mut env.rpc, instruction, &buyer, &[]).await.unwrap();
+
+    // The sale has no vesting vault to release from
+    let result = claim_vested(&mut env, &buyer, &sale, accounts.buyer_token_account).await;
+    assert_rpc_error(
+        result,
+        0,
+        anchor_lang::error::ErrorCode::AccountNotInitialized.into(),
+    )
+    .unwrap();
+}
+
+#[tokio::test]
+async fn test_compressed_purchases_vest() {
+    let mut env = setup().await;
+    let owner = env.owner.insecure_clone();
+    let creator = funded_keypair(&mut env.rpc).await;
+    let start_ts = current_timestamp(&env.rpc);
+    let token_mint = create_compressed_sale_mint(&mut env.rpc, &creator).await;
+    launch_token_compressed(
+        &mut env.rpc,
+        &creator,
+        token_mint,
+        LaunchArgs::new(SUPPLY, PRICE, 0).with_vesting(start_ts, start_ts, start_ts + 4 * DAY),
+    )
+    .await
+    .unwrap();
+    let creator_usdc_account =
+        create_token_account(&mut env.rpc, &owner, &env.usdc_mint, &creator.pubkey()).await;
+    let (buyer, buyer_usdc_account) = create_buyer(&mut env, 10_000_000).await;
+
+    let inputs = compressed_sale_inputs_for_buyer(
+        &mut env.rpc,
+        &token_mint,
+        &token_mint,
+        Some(&buyer.pubkey()),
+    )
+    .await;
+    let instruction = buy_tokens_compressed_instruction(
+        &env,
+        &buyer.pubkey(),
+        buyer_usdc_account,
+        creator_usdc_account,
+        token_mint,
+        inputs,
+        4_000_000,
+    );
+    send(&mut env.rpc, instruction, &buyer, &[]).await.unwrap();
+    assert_eq!(
+        compressed_token_balance(&mut env.rpc, &buyer.pubkey(), &token_mint).await,
+        0
+    );
+    assert_eq!(
+        compressed_token_balance(
+            &mut env.rpc,
+            &vesting_authority_pda(&token_mint),
+            &token_mint
+        )
+        .await,
+        4 * WHOLE
+    );
+
+    warp_to_timestamp(&mut env.rpc, start_ts + DAY);
+    let inputs = vesting_claim_inputs(&mut env.rpc, &token_mint, &buyer.pubkey()).await;
+    let instruction = claim_vested_compressed_instruction(&buyer.pubkey(), token_mint, inputs);
+    send(&mut env.rpc, instruction, &buyer, &[]).await.unwrap();
+    assert_eq!(
+        compressed_token_balance(&mut env.rpc, &buyer.pubkey(), &token_mint).await,
+        WHOLE
+    );
+
+    warp_to_timestamp(&mut env.rpc, start_ts + 4 * DAY);
+    let inputs = vesting_claim_inputs(&mut env.rpc, &token_mint, &buyer.pubkey()).await;
+    let instruction = claim_vested_compressed_instruction(&buyer.pubkey(), token_mint, inputs);
+    send(&mut env.rpc, instruction, &buyer, &[]).await.unwrap();
+    assert_eq!(
+        compressed_token_balance(&mut env.rpc, &buyer.pubkey(), &token_mint).await,
+        4 * WHOLE
+    );
+    let (_, record) = get_buyer_record(&mut env.rpc, &token_mint, &buyer.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(record.tokens_claimed, 4 * WHOLE);
+
+    let inputs = vesting_claim_inputs(&mut env.rpc, &token_mint, &buyer.pubkey()).await;
+    let instruction = claim_vested_compressed_instruction(&buyer.pubkey(), token_mint, inputs);
+    let result = send(&mut env.rpc, instruction, &buyer, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::NothingVested)).unwrap();
+}
@@ -0,0 +1,243 @@
+#![cfg(feature = "test-sbf")]
+
+mod common;
+
+use common::*;
+use light_program_test::utils::assert::assert_rpc_error;
+use solana_launchpad::ErrorCode;
+use solana_sdk::signature::Signer;
+
+const SUPPLY: u64 = 1_000_000_000_000;
+const PRICE: u64 = 1_000_000;
+
+#[tokio::test]
+async fn test_launch_rejects_wallet_limit_below_mint_limit() {
+    let mut env = setup().await;
+    let creator = funded_keypair(&mut env.rpc).await;
+    let args = LaunchArgs::new(SUPPLY, PRICE, 2_000_000_000).with_max_per_wallet(1_000_000_000);
+
+    let sale = create_standard_sale_mint(&mut env.rpc, &creator, TOKEN_DECIMALS).await;
+    let result = launch_token(&mut env.rpc, &creator, &sale, args.clone()).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::InvalidWalletLimit)).unwrap();
+
+    let token_mint = create_compressed_sale_mint(&mut env.rpc, &creator).await;
+    let result = launch_token_compressed(&mut env.rpc, &creator, token_mint, args).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::InvalidWalletLimit)).unwrap();
+}
+
+#[tokio::test]
+async fn test_standard_wallet_cap_is_cumulative() {
+    let mut env = setup().await;
+    let (sale, buyer, accounts) = launch_sale_with_buyer(
+        &mut env,
+        LaunchArgs::new(SUPPLY, PRICE, 0).with_max_per_wallet(3_000_000_000),
+        10_000_000,
+    )
+    .await;
+    assert_eq!(
+        get_token_sale(&mut env.rpc, &sale.token_mint)
+            .await
+            .max_per_wallet,
+        3_000_000_000
+    );
+
+    // Capped sales need the buyer record
+    let instruction = buy_tokens_instruction(&env, &buyer.pubkey(), &sale, &accounts, 1_000_000);
+    let result = send(&mut env.rpc, instruction, &buyer, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::BuyerRecordRequired)).unwrap();
+
+    // The first buy creates the record, later buys update it
+    buy_with_record(&mut env, &buyer, &sale, &accounts, 2_000_000)
+        .await
+        .unwrap();
+    let (_, record) = get_buyer_record(&mut env.rpc, &sale.token_mint, &buyer.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(record.token_mint, sale.token_mint);
+    assert_eq!(record.buyer, buyer.pubkey());
+    assert_eq!(record.tokens_bought, 2_000_000_000);
+
+    let result = buy_with_record(&mut env, &buyer, &sale, &accounts, 2_000_000).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::ExceedsWalletLimit)).unwrap();
+
+    buy_with_record(&mut env, &buyer, &sale, &accounts, 1_000_000)
+        .await
+        .unwrap();
+    let result = buy_with_record(&mut env, &buyer, &sale, &accounts, 1_000_000).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::ExceedsWalletLimit)).unwrap();
+
+    assert_eq!(
+        token_balance(&mut env.rpc, &accounts.buyer_token_account).await,
+        3_000_000_000
+    );
+    let (_, record) = get_buyer_record(&mut env.rpc, &sale.token_mint, &buyer.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(record.tokens_bought, 3_000_000_000);
+}
+
+#[tokio::test]
+async fn test_free_mint_cannot_be_claimed_in_a_loop() {
+    let mut env = setup().await;
+    let limit = 5_000_000_000;
+    let (sale, buyer, accounts) = launch_sale_with_buyer(
+        &mut env,
+        LaunchArgs::new(SUPPLY, 0, limit).with_max_per_wallet(limit),
+        10_000_000,
+    )
+    .await;
+
+    buy_with_record(&mut env, &buyer, &sale, &accounts, 0)
+        .await
+        .unwrap();
+    let result = buy_with_record(&mut env, &buyer, &sale, &accounts, 0).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::ExceedsWalletLimit)).unwrap();
+
+    assert_eq!(
+        token_balance(&mut env.rpc, &accounts.buyer_token_account).await,
+        limit
+    );
+    assert_eq!(
+        get_token_sale(&mut env.rpc, &sale.token_mint)
+            .await
+            .tokens_sold,
+        limit
+    );
+}
+
+#[tokio::test]
+async fn test_buyer_record_of_another_wallet_is_rejected() {
+    let mut env = setup().await;
+    let owner = env.owner.insecure_clone();
+    let (sale, buyer, accounts) = launch_sale_with_buyer(
+        &mut env,
+        LaunchArgs::new(SUPPLY, PRICE, 0).with_max_per_wallet(2_000_000_000),
+        10_000_000,
+    )
+    .await;
+    buy_with_record(&mut env, &buyer, &sale, &accounts, 2_000_000)
+        .await
+        .unwrap();
+
+    // A fresh wallet reusing the exhausted buyer's record is caught by its address
+    let (other, other_usdc_account) = create_buyer(&mut env, 10_000_000).await;
+    let other_accounts = BuyAccounts {
+        buyer_usdc_account: other_usdc_account,
+        buyer_token_account: create_token_account(
+            &mut env.rpc,
+            &owner,
+            &sale.token_mint,
+            &other.pubkey(),
+        )
+        .await,
+        creator_usdc_account: accounts.creator_usdc_account,
+    };
+    let record = buyer_record_inputs(&mut env.rpc, &sale.token_mint, &buyer.pubkey()).await;
+    let instruction = buy_tokens_instruction_with(
+        &env,
+        &other.pubkey(),
+        &sale,
+        &other_accounts,
+        1_000_000,
+        0,
+        None,
+        Some(record),
+    );
+    let result = send(&mut env.rpc, instruction, &other, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::InvalidBuyerRecord)).unwrap();
+
+    // Other wallets get their own allowance
+    buy_with_record(&mut env, &other, &sale, &other_accounts, 2_000_000)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_uncapped_sale_skips_buyer_record() {
+    let mut env = setup().await;
+    let (sale, buyer, accounts) =
+        launch_sale_with_buyer(&mut env, LaunchArgs::new(SUPPLY, PRICE, 0), 10_000_000).await;
+
+    let instruction = buy_tokens_instruction(&env, &buyer.pubkey(), &sale, &accounts, 1_000_000);
+    send(&mut env.rpc, instruction, &buyer, &[]).await.unwrap();
+    assert!(
+        get_buyer_record(&mut env.rpc, &sale.token_mint, &buyer.pubkey())
+            .await
+            .is_none()
+    );
+}
+
+#[tokio::test]
+async fn test_compressed_wallet_cap() {
+    let mut env = setup().await;
+    let owner = env.owner.insecure_clone();
+    let creator = funded_keypair(&mut env.rpc).await;
+    let token_mint = create_compressed_sale_mint(&mut env.rpc, &creator).await;
+    launch_token_compressed(
+        &mut env.rpc,
+        &creator,
+        token_mint,
+        LaunchArgs::new(SUPPLY, PRICE, 0).with_max_per_wallet(3_000_000_000),
+    )
+    .await
+    .unwrap();
+    let creator_usdc_account =
+        create_token_account(&mut env.rpc, &owner, &env.usdc_mint, &creator.pubkey()).await;
+    let (buyer, buyer_usdc_account) = create_buyer(&mut env, 10_000_000).await;
+    let (_, sale) = get_compressed_sale(&mut env.rpc, &token_mint).await;
+    assert_eq!(sale.max_per_wallet, 3_000_000_000);
+
+    let inputs = compressed_sale_inputs(&mut env.rpc, &token_mint, &token_mint).await;
+    let instruction = buy_tokens_compressed_instruction(
+        &env,
+        &buyer.pubkey(),
+        buyer_usdc_account,
+        creator_usdc_account,
+        token_mint,
+        inputs,
+        1_000_000,
+    );
+    let result = send(&mut env.rpc, instruction, &buyer, &[]).await;
+    assert_rpc_error(result, 0, error_code(ErrorCode::BuyerRecordRequired)).unwrap();
+
+    let cases = [
+        (2_000_000, None),
+        (2_000_000, Some(ErrorCode::ExceedsWalletLimit)),
+        (1_000_000, None),
+        (1_000_000, Some(ErrorCode::ExceedsWalletLimit)),
+    ];
+    for (usdc_amount, expected) in cases {
+        let inputs = compressed_sale_inputs_for_buyer(
+            &mut env.rpc,
+            &token_mint,
+            &token_mint,
+            Some(&buyer.pubkey()),
+        )
+        .await;
+        let instruction = buy_tokens_compressed_instruction(
+            &env,
+            &buyer.pubkey(),
+            buyer_usdc_account,
+            creator_usdc_account,
+            token_mint,
+            inputs,
+            usdc_amount,
+        );
+        let result = send(&mut env.rpc, instruction, &buyer, &[]).await;
+        match expected {
+            Some(error) => assert_rpc_error(result, 0, error_code(error)).unwrap(),
+            None => {
+                result.unwrap();
+            }
+        }
+    }
+
+    assert_eq!(
+        compressed_token_balance(&mut env.rpc, &buyer.pubkey(), &token_mint).await,
+        3_000_000_000
+    );
+    let (_, record) = get_buyer_record(&mut env.rpc, &token_mint, &buyer.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(record.tokens_bought, 3_000_000_000);
+}
@@ -6,6 +6,7 @@ import {
   getOrCreateAssociatedTokenAccount,
   mintTo as splMintTo,
   getAccount,
+  TOKEN_PROGRAM_ID,
 } from "@solana/spl-token";
 import { assert } from "chai";
 import { Rpc, createRpc } from "@lightprotocol/stateless.js";
@@ -17,6 +18,25 @@ import {
 } from "@lightprotocol/compressed-token";
 import bs58 from "bs58";
 
+// Empty validity proof for buys that touch no compressed accounts
+const emptyProof = { 0: null };
+
+// launch_token settings of a fixed-price sale in the registered USDC mint,
+// with no window, allowlist, caps or vesting
+const defaultSaleParams = {
+  startTs: null,
+  endTs: null,
+  merkleRoot: null,
+  maxPerWallet: new BN(0),
+  priceSlope: new BN(0),
+  auction: null,
+  softCap: new BN(0),
+  hardCapUsdc: new BN(0),
+  quoteCurrency: { token: {} },
+  vesting: null,
+  proceedsVesting: null,
+};
+
 // Helper function to log transaction gas costs
 async function logGasCost(
   connection: anchor.web3.Connection,
@@ -74,7 +94,6 @@ describe("gasless-launchpad with ZK Compression", () => {
   let creatorUsdcAccount: anchor.web3.PublicKey;
   let buyerUsdcAccount: anchor.web3.PublicKey;
   let platformOwnerUsdcAccount: anchor.web3.PublicKey;
-  let programUsdcAccount: anchor.web3.PublicKey;
 
   // Program authority PDA
   let programAuthority: anchor.web3.PublicKey;
@@ -82,6 +101,15 @@ describe("gasless-launchpad with ZK Compression", () => {
 
   // App state PDA
   let appState: anchor.web3.PublicKey;
+  let quoteMintEntry: anchor.web3.PublicKey;
+
+  /** Per-sale escrow of the quote mint, created by launchToken */
+  function saleEscrowPda(tokenMint: anchor.web3.PublicKey): anchor.web3.PublicKey {
+    return anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("escrow"), tokenMint.toBuffer()],
+      program.programId
+    )[0];
+  }
 
   /**
    * Setup a token for standard (non-compressed) testing
@@ -220,13 +248,14 @@ describe("gasless-launchpad with ZK Compression", () => {
       program.programId
     );
 
-    // Initialize app state (if not already initialized)
+    // Initialize app state and register USDC as a quote mint (if not already initialized)
     let usingExistingMint = false;
     try {
       const initTx = await program.methods
-        .initialize(usdcMint, 500) // 5% platform fee
+        .initialize(500, new BN(1000)) // 5% platform fee
         .accounts({
           owner: platformOwner.publicKey,
+          usdcMint,
         })
         .signers([platformOwner])
         .rpc();
@@ -241,6 +270,11 @@ describe("gasless-launchpad with ZK Compression", () => {
       console.log("Using existing USDC mint from app_state:", usdcMint.toString());
     }
 
+    [quoteMintEntry] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("quote_mint"), usdcMint.toBuffer()],
+      program.programId
+    );
+
     // Create token accounts
     const creatorAccount = await getOrCreateAssociatedTokenAccount(
       provider.connection,
@@ -266,15 +300,6 @@ describe("gasless-launchpad with ZK Compression", () => {
     );
     platformOwnerUsdcAccount = platformAccount.address;
 
-    const programAccount = await getOrCreateAssociatedTokenAccount(
-      provider.connection,
-      platformOwner,
-      usdcMint,
-      programAuthority,
-      true
-    );
-    programUsdcAccount = programAccount.address;
-
     // Mint USDC to buyer (only if we created the mint)
     if (!usingExistingMint) {
       await splMintTo(
@@ -303,11 +328,16 @@ describe("gasless-launchpad with ZK Compression", () => {
       const { tokenMint, tokenSale, saleTokenAccount } = await setupStandardTestToken(creator);
 
       const tx = await program.methods
-        .launchToken(name, symbol, supply, pricePerToken, limitPerMint, metadataId)
+        .launchToken(name, symbol, supply, pricePerToken, limitPerMint, metadataId, defaultSaleParams)
         .accounts({
           creator: creator.publicKey,
           tokenMint: tokenMint,
           saleTokenAccount,
+          quoteMintEntry,
+          quoteMint: usdcMint,
+          saleEscrow: saleEscrowPda(tokenMint),
+          vestingVault: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
         })
         .signers([creator])
         .rpc();
@@ -341,11 +371,16 @@ describe("gasless-launchpad with ZK Compression", () => {
       const { tokenMint, tokenSale, saleTokenAccount } = await setupStandardTestToken(creator);
 
       await program.methods
-        .launchToken(name, symbol, supply, pricePerToken, limitPerMint, metadataId)
+        .launchToken(name, symbol, supply, pricePerToken, limitPerMint, metadataId, defaultSaleParams)
         .accounts({
           creator: creator.publicKey,
           tokenMint: tokenMint,
           saleTokenAccount,
+          quoteMintEntry,
+          quoteMint: usdcMint,
+          saleEscrow: saleEscrowPda(tokenMint),
+          vestingVault: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
         })
         .signers([creator])
         .rpc();
@@ -367,11 +402,16 @@ describe("gasless-launchpad with ZK Compression", () => {
 
       try {
         await program.methods
-          .launchToken(name, symbol, supply, pricePerToken, limitPerMint, metadataId)
+          .launchToken(name, symbol, supply, pricePerToken, limitPerMint, metadataId, defaultSaleParams)
           .accounts({
             creator: creator.publicKey,
             tokenMint: tokenMint,
             saleTokenAccount,
+            quoteMintEntry,
+            quoteMint: usdcMint,
+            saleEscrow: saleEscrowPda(tokenMint),
+            vestingVault: null,
+            tokenProgram: TOKEN_PROGRAM_ID,
           })
           .signers([creator])
           .rpc();
@@ -393,11 +433,16 @@ describe("gasless-launchpad with ZK Compression", () => {
 
       try {
         await program.methods
-          .launchToken(name, symbol, supply, pricePerToken, limitPerMint, metadataId)
+          .launchToken(name, symbol, supply, pricePerToken, limitPerMint, metadataId, defaultSaleParams)
           .accounts({
             creator: creator.publicKey,
             tokenMint: tokenMint,
             saleTokenAccount,
+            quoteMintEntry,
+            quoteMint: usdcMint,
+            saleEscrow: saleEscrowPda(tokenMint),
+            vestingVault: null,
+            tokenProgram: TOKEN_PROGRAM_ID,
           })
           .signers([creator])
           .rpc();
@@ -427,12 +472,18 @@ describe("gasless-launchpad with ZK Compression", () => {
           new BN(1000000000000), // 1,000 tokens (9 decimals) - max allowed
           new BN(1000000), // 1 USDC per token
           new BN(100000000000), // 100 tokens limit
-          "buy123"
+          "buy123",
+          defaultSaleParams
         )
         .accounts({
           creator: creator.publicKey,
           tokenMint: testTokenMint,
           saleTokenAccount,
+          quoteMintEntry,
+          quoteMint: usdcMint,
+          saleEscrow: saleEscrowPda(testTokenMint),
+          vestingVault: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
         })
         .signers([creator])
         .rpc();
@@ -457,7 +508,7 @@ describe("gasless-launchpad with ZK Compression", () => {
       const initialPlatformUsdc = await getAccount(provider.connection, platformOwnerUsdcAccount);
 
       const tx = await program.methods
-        .buyTokens(usdcAmount)
+        .buyTokens(usdcAmount, new BN(0), null, emptyProof as any, null, null)
         .accounts({
           buyer: buyer.publicKey,
           tokenSale: testTokenSale,
@@ -465,9 +516,14 @@ describe("gasless-launchpad with ZK Compression", () => {
           saleTokenAccount,
           buyerTokenAccount,
           buyerUsdcAccount,
-          programUsdcAccount,
-          ownerUsdcAccount: platformOwnerUsdcAccount,
+          saleEscrow: saleEscrowPda(testTokenMint),
+          feeRecipientUsdcAccount: platformOwnerUsdcAccount,
           creatorUsdcAccount,
+          quoteMintEntry,
+          vestingVault: null,
+          referrer: null,
+          referrerUsdcAccount: null,
+          saleTokenProgram: TOKEN_PROGRAM_ID,
         })
         .signers([buyer])
         .rpc();
@@ -520,12 +576,18 @@ describe("gasless-launchpad with ZK Compression", () => {
           new BN(1000000),
           new BN(0), // Free
           new BN(100), // Limit per mint
-          "free"
+          "free",
+          defaultSaleParams
         )
         .accounts({
           creator: creator.publicKey,
           tokenMint: freeTokenMint,
           saleTokenAccount: freeSaleTokenAccount,
+          quoteMintEntry,
+          quoteMint: usdcMint,
+          saleEscrow: saleEscrowPda(freeTokenMint),
+          vestingVault: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
         })
         .signers([creator])
         .rpc();
@@ -540,7 +602,7 @@ describe("gasless-launchpad with ZK Compression", () => {
       const buyerFreeTokenAccount = buyerFreeTokenAccountInfo.address;
 
       await program.methods
-        .buyTokens(new BN(0))
+        .buyTokens(new BN(0), new BN(0), null, emptyProof as any, null, null)
         .accounts({
           buyer: buyer.publicKey,
           tokenSale: freeTokenSale,
@@ -548,9 +610,14 @@ describe("gasless-launchpad with ZK Compression", () => {
           saleTokenAccount: freeSaleTokenAccount,
           buyerTokenAccount: buyerFreeTokenAccount,
           buyerUsdcAccount,
-          programUsdcAccount,
-          ownerUsdcAccount: platformOwnerUsdcAccount,
+          saleEscrow: saleEscrowPda(freeTokenMint),
+          feeRecipientUsdcAccount: platformOwnerUsdcAccount,
           creatorUsdcAccount,
+          quoteMintEntry,
+          vestingVault: null,
+          referrer: null,
+          referrerUsdcAccount: null,
+          saleTokenProgram: TOKEN_PROGRAM_ID,
         })
         .signers([buyer])
         .rpc();
@@ -573,7 +640,7 @@ describe("gasless-launchpad with ZK Compression", () => {
 
       try {
         await program.methods
-          .buyTokens(usdcAmount)
+          .buyTokens(usdcAmount, new BN(0), null, emptyProof as any, null, null)
           .accounts({
             buyer: buyer.publicKey,
             tokenSale: testTokenSale,
@@ -581,9 +648,14 @@ describe("gasless-launchpad with ZK Compression", () => {
             saleTokenAccount,
             buyerTokenAccount,
             buyerUsdcAccount,
-            programUsdcAccount,
-            ownerUsdcAccount: platformOwnerUsdcAccount,
+            saleEscrow: saleEscrowPda(testTokenMint),
+            feeRecipientUsdcAccount: platformOwnerUsdcAccount,
             creatorUsdcAccount,
+            quoteMintEntry,
+            vestingVault: null,
+            referrer: null,
+            referrerUsdcAccount: null,
+            saleTokenProgram: TOKEN_PROGRAM_ID,
           })
           .signers([buyer])
           .rpc();
@@ -608,12 +680,18 @@ describe("gasless-launchpad with ZK Compression", () => {
           new BN(100000000000), // 100 tokens (9 decimals)
           new BN(1000000),
           new BN(100000000000), // Can buy all at once
-          "small"
+          "small",
+          defaultSaleParams
         )
         .accounts({
           creator: creator.publicKey,
           tokenMint: smallTokenMint,
           saleTokenAccount: smallSaleTokenAccount,
+          quoteMintEntry,
+          quoteMint: usdcMint,
+          saleEscrow: saleEscrowPda(smallTokenMint),
+          vestingVault: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
         })
         .signers([creator])
         .rpc();
@@ -629,7 +707,7 @@ describe("gasless-launchpad with ZK Compression", () => {
 
       // Buy all tokens
       await program.methods
-        .buyTokens(new BN(100000000)) // 100 USDC for 100 tokens
+        .buyTokens(new BN(100000000), new BN(0), null, emptyProof as any, null, null) // 100 USDC for 100 tokens
         .accounts({
           buyer: buyer.publicKey,
           tokenSale: smallTokenSale,
@@ -637,9 +715,14 @@ describe("gasless-launchpad with ZK Compression", () => {
           saleTokenAccount: smallSaleTokenAccount,
           buyerTokenAccount: buyerSmallTokenAccount,
           buyerUsdcAccount,
-          programUsdcAccount,
-          ownerUsdcAccount: platformOwnerUsdcAccount,
+          saleEscrow: saleEscrowPda(smallTokenMint),
+          feeRecipientUsdcAccount: platformOwnerUsdcAccount,
           creatorUsdcAccount,
+          quoteMintEntry,
+          vestingVault: null,
+          referrer: null,
+          referrerUsdcAccount: null,
+          saleTokenProgram: TOKEN_PROGRAM_ID,
         })
         .signers([buyer])
         .rpc();
@@ -669,12 +752,18 @@ describe("gasless-launchpad with ZK Compression", () => {
           new BN(1000),
           new BN(1000000),
           new BN(100),
-          "close"
+          "close",
+          defaultSaleParams
         )
         .accounts({
           creator: creator.publicKey,
           tokenMint: closeTokenMint,
           saleTokenAccount: closeSaleTokenAccount,
+          quoteMintEntry,
+          quoteMint: usdcMint,
+          saleEscrow: saleEscrowPda(closeTokenMint),
+          vestingVault: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
         })
         .signers([creator])
         .rpc();
@@ -699,6 +788,7 @@ describe("gasless-launchpad with ZK Compression", () => {
           tokenMint: closeTokenMint,
           saleTokenAccount: closeSaleTokenAccount,
           creatorTokenAccount,
+          tokenProgram: TOKEN_PROGRAM_ID,
         })
         .signers([creator])
         .rpc();
@@ -725,12 +815,18 @@ describe("gasless-launchpad with ZK Compression", () => {
           new BN(1000),
           new BN(1000000),
           new BN(100),
-          "another"
+          "another",
+          defaultSaleParams
         )
         .accounts({
           creator: creator.publicKey,
           tokenMint: anotherTokenMint,
           saleTokenAccount: anotherSaleTokenAccount,
+          quoteMintEntry,
+          quoteMint: usdcMint,
+          saleEscrow: saleEscrowPda(anotherTokenMint),
+          vestingVault: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
         })
         .signers([creator])
         .rpc();
@@ -752,6 +848,7 @@ describe("gasless-launchpad with ZK Compression", () => {
             tokenMint: anotherTokenMint,
             saleTokenAccount: anotherSaleTokenAccount,
             creatorTokenAccount: buyerTokenAccount,
+            tokenProgram: TOKEN_PROGRAM_ID,
           })
           .signers([buyer]) // Wrong signer - not the creator
           .rpc();
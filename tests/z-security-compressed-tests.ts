@@ -0,0 +1,389 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program, BN } from "@coral-xyz/anchor";
+import { GaslessLaunchpad } from "../target/types/gasless_launchpad";
+import {
+  createMint,
+  getOrCreateAssociatedTokenAccount,
+} from "@solana/spl-token";
+import { assert } from "chai";
+import bs58 from "bs58";
+
+const COMPRESSED_TOKEN_PROGRAM_ID = new anchor.web3.PublicKey(
+  "cTokenmWW8bLPjZEBAUgYy3zKxQZW6VKi7bqNFEVv3m"
+);
+const COMPRESSED_TOKEN_CPI_AUTHORITY = new anchor.web3.PublicKey(
+  "GXtd2izAiMJPwMEjfgTRH3d7k9mjn4Jq3JrWFv9gySYy"
+);
+
+// Account constraints are checked before any proof is verified, so these
+// tests pass placeholder proofs and compressed account metadata.
+const emptyProof = { 0: null };
+const emptyAccountMeta = {
+  treeInfo: {
+    rootIndex: 0,
+    proveByIndex: false,
+    merkleTreePubkeyIndex: 0,
+    queuePubkeyIndex: 0,
+    leafIndex: 0,
+  },
+  address: new Array(32).fill(0),
+  outputStateTreeIndex: 0,
+};
+
+describe("Security Tests - Compressed Sales", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace
+    .GaslessLaunchpad as Program<GaslessLaunchpad>;
+
+  let usdcMint: anchor.web3.PublicKey;
+  let fakeUsdcMint: anchor.web3.PublicKey;
+  let creator: anchor.web3.Keypair;
+  let buyer: anchor.web3.Keypair;
+  let attacker: anchor.web3.Keypair;
+
+  let creatorUsdcAccount: anchor.web3.PublicKey;
+  let buyerUsdcAccount: anchor.web3.PublicKey;
+  let attackerUsdcAccount: anchor.web3.PublicKey;
+  let attackerFakeUsdcAccount: anchor.web3.PublicKey;
+  let feeRecipientUsdcAccount: anchor.web3.PublicKey;
+  let programUsdcAccount: anchor.web3.PublicKey;
+
+  let programAuthority: anchor.web3.PublicKey;
+  let appState: anchor.web3.PublicKey;
+  let quoteMintEntry: anchor.web3.PublicKey;
+
+  let tokenMint: anchor.web3.PublicKey;
+  let saleAuthority: anchor.web3.PublicKey;
+  let saleAuthorityBump: number;
+  let vestingAuthority: anchor.web3.PublicKey;
+
+  function currentSale(overrides: Record<string, any> = {}) {
+    return {
+      creator: creator.publicKey,
+      tokenMint,
+      pricePerToken: new BN(1000000),
+      supplyForSale: new BN(1000000000),
+      tokensSold: new BN(0),
+      active: true,
+      limitPerMint: new BN(0),
+      decimals: 9,
+      saleAuthority,
+      saleAuthorityBump,
+      startTs: null,
+      endTs: null,
+      merkleRoot: null,
+      maxPerWallet: new BN(0),
+      priceSlope: new BN(0),
+      auction: null,
+      hardCapUsdc: new BN(0),
+      usdcRaised: new BN(0),
+      quoteCurrency: { token: {} },
+      quoteMint: usdcMint,
+      vesting: null,
+      quoteDecimals: 6,
+      platformFeeBps: 500,
+      ...overrides,
+    };
+  }
+
+  async function buyCompressed(
+    signer: anchor.web3.Keypair,
+    accountOverrides: Record<string, anchor.web3.PublicKey> = {},
+    saleOverrides: Record<string, any> = {}
+  ) {
+    return program.methods
+      .buyTokensCompressed(
+        emptyProof as any,
+        currentSale(saleOverrides) as any,
+        emptyAccountMeta as any,
+        new BN(1000000),
+        new BN(0),
+        null,
+        null,
+        null,
+        emptyProof as any,
+        [],
+        0
+      )
+      .accounts({
+        buyer: signer.publicKey,
+        buyerUsdcAccount,
+        creatorUsdcAccount,
+        feeRecipientUsdcAccount,
+        programUsdcAccount,
+        tokenMint,
+        appState,
+        quoteMintEntry,
+        programAuthority,
+        saleAuthority,
+        vestingAuthority,
+        compressedTokenProgram: COMPRESSED_TOKEN_PROGRAM_ID,
+        compressedTokenCpiAuthority: COMPRESSED_TOKEN_CPI_AUTHORITY,
+        ...accountOverrides,
+      } as any)
+      .signers([signer])
+      .rpc();
+  }
+
+  function assertError(err: any, names: string[]) {
+    const errStr = err.toString();
+    assert.ok(
+      names.some((name) => errStr.includes(name)),
+      `Expected one of ${names.join(", ")}, got: ${errStr.substring(0, 200)}`
+    );
+  }
+
+  before(async () => {
+    attacker = anchor.web3.Keypair.fromSecretKey(
+      bs58.decode(
+        "3VGCZbTmMQsRN9rkgG2D8PJroSeVSccEJ5pttChTUSpveCGoD1MY4sTCYjcH1EtKJm4PmR4KMbSNT8Hhc4rx3PRJ"
+      )
+    );
+    creator = anchor.web3.Keypair.fromSecretKey(
+      bs58.decode(
+        "2zUfsrV2vDiejgagoQUhs6qT5AzJg1iVGcE4U8Q5XJB1e5Pi3TJ5xDaDDWaqZ8uNqCPTUwB2Xwjnh5irtCV3CYmH"
+      )
+    );
+    buyer = anchor.web3.Keypair.fromSecretKey(
+      bs58.decode(
+        "3zyLcEF78fZdusNRVVcwX5yrpeRnBN7v2hyAPxXBDQCkXaVJQahy8WfT1GgvR72bCKebJVxNioPCtt55hUiDMJTY"
+      )
+    );
+
+    [programAuthority] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("authority")],
+      program.programId
+    );
+    [appState] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("app_state")],
+      program.programId
+    );
+
+    // App state is initialized by the main test suite
+    const appStateAccount = await program.account.appState.fetch(appState);
+    usdcMint = appStateAccount.usdcMint;
+    [quoteMintEntry] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("quote_mint"), usdcMint.toBuffer()],
+      program.programId
+    );
+
+    fakeUsdcMint = await createMint(
+      provider.connection,
+      attacker,
+      attacker.publicKey,
+      null,
+      6
+    );
+
+    creatorUsdcAccount = (
+      await getOrCreateAssociatedTokenAccount(
+        provider.connection,
+        creator,
+        usdcMint,
+        creator.publicKey
+      )
+    ).address;
+    buyerUsdcAccount = (
+      await getOrCreateAssociatedTokenAccount(
+        provider.connection,
+        buyer,
+        usdcMint,
+        buyer.publicKey
+      )
+    ).address;
+    attackerUsdcAccount = (
+      await getOrCreateAssociatedTokenAccount(
+        provider.connection,
+        attacker,
+        usdcMint,
+        attacker.publicKey
+      )
+    ).address;
+    attackerFakeUsdcAccount = (
+      await getOrCreateAssociatedTokenAccount(
+        provider.connection,
+        attacker,
+        fakeUsdcMint,
+        attacker.publicKey
+      )
+    ).address;
+    feeRecipientUsdcAccount = (
+      await getOrCreateAssociatedTokenAccount(
+        provider.connection,
+        attacker,
+        usdcMint,
+        appStateAccount.feeRecipient,
+        true
+      )
+    ).address;
+    programUsdcAccount = (
+      await getOrCreateAssociatedTokenAccount(
+        provider.connection,
+        attacker,
+        usdcMint,
+        programAuthority,
+        true
+      )
+    ).address;
+
+    // Sale mint with the sale_authority PDA as mint authority
+    const tokenMintKeypair = anchor.web3.Keypair.generate();
+    [saleAuthority, saleAuthorityBump] =
+      anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("sale_authority"), tokenMintKeypair.publicKey.toBuffer()],
+        program.programId
+      );
+    [vestingAuthority] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("vesting_authority"), tokenMintKeypair.publicKey.toBuffer()],
+      program.programId
+    );
+    tokenMint = await createMint(
+      provider.connection,
+      creator,
+      saleAuthority,
+      null,
+      9,
+      tokenMintKeypair
+    );
+  });
+
+  describe("Buyer USDC Account", () => {
+    it("Rejects a buyer USDC account with a fake mint", async () => {
+      try {
+        await buyCompressed(attacker, {
+          buyerUsdcAccount: attackerFakeUsdcAccount,
+        });
+        assert.fail("Should have failed - fake USDC was accepted!");
+      } catch (err) {
+        assertError(err, ["InvalidMint", "6016"]);
+      }
+    });
+
+    it("Rejects a buyer USDC account owned by someone else", async () => {
+      try {
+        await buyCompressed(attacker, { buyerUsdcAccount });
+        assert.fail("Should have failed - wrong account owner!");
+      } catch (err) {
+        assertError(err, ["InvalidTokenAccountOwner", "6017"]);
+      }
+    });
+  });
+
+  describe("Creator USDC Account", () => {
+    it("Rejects routing the creator share to the buyer", async () => {
+      try {
+        await buyCompressed(attacker, {
+          buyerUsdcAccount: attackerUsdcAccount,
+          creatorUsdcAccount: attackerUsdcAccount,
+        });
+        assert.fail("Should have failed - creator share redirected!");
+      } catch (err) {
+        assertError(err, ["InvalidTokenAccountOwner", "6017"]);
+      }
+    });
+
+    it("Rejects a creator USDC account with the wrong mint", async () => {
+      try {
+        await buyCompressed(
+          attacker,
+          {
+            buyerUsdcAccount: attackerUsdcAccount,
+            creatorUsdcAccount: attackerFakeUsdcAccount,
+          },
+          { creator: attacker.publicKey }
+        );
+        assert.fail("Should have failed - wrong creator USDC mint!");
+      } catch (err) {
+        assertError(err, ["InvalidMint", "6016"]);
+      }
+    });
+  });
+
+  describe("Platform Fee Account", () => {
+    it("Rejects routing the platform fee to the buyer", async () => {
+      try {
+        await buyCompressed(attacker, {
+          buyerUsdcAccount: attackerUsdcAccount,
+          feeRecipientUsdcAccount: attackerUsdcAccount,
+        });
+        assert.fail("Should have failed - platform fee redirected!");
+      } catch (err) {
+        assertError(err, ["InvalidTokenAccountOwner", "6017"]);
+      }
+    });
+
+    it("Rejects a fee recipient USDC account with the wrong mint", async () => {
+      try {
+        await buyCompressed(attacker, {
+          buyerUsdcAccount: attackerUsdcAccount,
+          feeRecipientUsdcAccount: attackerFakeUsdcAccount,
+        });
+        assert.fail("Should have failed - wrong fee recipient USDC mint!");
+      } catch (err) {
+        assertError(err, [
+          "InvalidTokenAccountOwner",
+          "InvalidMint",
+          "6016",
+          "6017",
+        ]);
+      }
+    });
+  });
+
+  describe("Program USDC Account", () => {
+    it("Rejects a program USDC account not owned by the authority PDA", async () => {
+      try {
+        await buyCompressed(attacker, {
+          buyerUsdcAccount: attackerUsdcAccount,
+          programUsdcAccount: attackerUsdcAccount,
+        });
+        assert.fail("Should have failed - escrow redirected!");
+      } catch (err) {
+        assertError(err, ["InvalidTokenAccountOwner", "6017"]);
+      }
+    });
+  });
+
+  describe("Sale Binding", () => {
+    it("Rejects a sale for a different token mint", async () => {
+      try {
+        await buyCompressed(
+          attacker,
+          { buyerUsdcAccount: attackerUsdcAccount },
+          { tokenMint: fakeUsdcMint }
+        );
+        assert.fail("Should have failed - sale mint mismatch!");
+      } catch (err) {
+        assertError(err, ["InvalidMint", "6016"]);
+      }
+    });
+
+    it("Rejects closing a compressed sale by a non-creator", async () => {
+      try {
+        await program.methods
+          .closeSaleCompressed(
+            emptyProof as any,
+            currentSale() as any,
+            emptyAccountMeta as any,
+            emptyProof as any,
+            [],
+            0
+          )
+          .accounts({
+            creator: attacker.publicKey,
+            tokenMint,
+            saleAuthority,
+            compressedTokenProgram: COMPRESSED_TOKEN_PROGRAM_ID,
+            compressedTokenCpiAuthority: COMPRESSED_TOKEN_CPI_AUTHORITY,
+          } as any)
+          .signers([attacker])
+          .rpc();
+        assert.fail("Should have failed - non-creator closed the sale!");
+      } catch (err) {
+        assertError(err, ["Unauthorized", "6021"]);
+      }
+    });
+  });
+});
@@ -6,11 +6,32 @@ import {
   getOrCreateAssociatedTokenAccount,
   mintTo,
   getAccount,
+  TOKEN_PROGRAM_ID,
 } from "@solana/spl-token";
 import { assert } from "chai";
 import { Rpc, createRpc } from "@lightprotocol/stateless.js";
 import { createTokenPool } from "@lightprotocol/compressed-token";
 import bs58 from "bs58";
+
+// Empty validity proof for buys that touch no compressed accounts
+const emptyProof = { 0: null };
+
+// launch_token settings of a fixed-price sale in the registered USDC mint,
+// with no window, allowlist, caps or vesting
+const defaultSaleParams = {
+  startTs: null,
+  endTs: null,
+  merkleRoot: null,
+  maxPerWallet: new BN(0),
+  priceSlope: new BN(0),
+  auction: null,
+  softCap: new BN(0),
+  hardCapUsdc: new BN(0),
+  quoteCurrency: { token: {} },
+  vesting: null,
+  proceedsVesting: null,
+};
+
 describe("Security Tests", () => {
   const provider = anchor.AnchorProvider.env();
   anchor.setProvider(provider);
@@ -33,10 +54,18 @@ describe("Security Tests", () => {
   let attackerUsdcAccount: anchor.web3.PublicKey;
   let attackerFakeUsdcAccount: anchor.web3.PublicKey;
   let platformOwnerUsdcAccount: anchor.web3.PublicKey;
-  let programUsdcAccount: anchor.web3.PublicKey;
 
   let programAuthority: anchor.web3.PublicKey;
   let appState: anchor.web3.PublicKey;
+  let quoteMintEntry: anchor.web3.PublicKey;
+
+  // Per-sale escrow of the quote mint, created by launchToken
+  function saleEscrowPda(tokenMint: anchor.web3.PublicKey): anchor.web3.PublicKey {
+    return anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("escrow"), tokenMint.toBuffer()],
+      program.programId
+    )[0];
+  }
 
   // Helper function to setup a token for testing with ZK compression support
   async function setupTestToken(
@@ -170,14 +199,15 @@ describe("Security Tests", () => {
       program.programId
     );
 
-    // Initialize app state (if not already initialized)
+    // Initialize app state and register USDC as a quote mint (if not already initialized)
     let usingExistingMint = false;
     try {
       await program.methods
-        .initialize(usdcMint, 500)
+        .initialize(500, new BN(1000))
         .accounts({
           owner: platformOwner.publicKey,
           appState,
+          usdcMint,
         })
         .signers([platformOwner])
         .rpc();
@@ -192,6 +222,11 @@ describe("Security Tests", () => {
       );
     }
 
+    [quoteMintEntry] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("quote_mint"), usdcMint.toBuffer()],
+      program.programId
+    );
+
     // Create token accounts
     const creatorAcc = await getOrCreateAssociatedTokenAccount(
       provider.connection,
@@ -225,15 +260,6 @@ describe("Security Tests", () => {
     );
     platformOwnerUsdcAccount = platformAcc.address;
 
-    const programAcc = await getOrCreateAssociatedTokenAccount(
-      provider.connection,
-      platformOwner,
-      usdcMint,
-      programAuthority,
-      true
-    );
-    programUsdcAccount = programAcc.address;
-
     // Mint USDC to buyer and attacker (only if we created the mint)
     if (!usingExistingMint) {
       await mintTo(
@@ -270,10 +296,11 @@ describe("Security Tests", () => {
     it("Cannot reinitialize app state", async () => {
       try {
         await program.methods
-          .initialize(usdcMint, 500)
+          .initialize(500, new BN(1000))
           .accounts({
             owner: platformOwner.publicKey,
             appState,
+            usdcMint,
           })
           .signers([platformOwner])
           .rpc();
@@ -287,10 +314,11 @@ describe("Security Tests", () => {
     it("Rejects invalid fee (>1000 bps)", async () => {
       try {
         await program.methods
-          .initialize(usdcMint, 1001) // Invalid: >10%
+          .initialize(1001, new BN(1000)) // Invalid: >10%
           .accounts({
             owner: platformOwner.publicKey,
             appState,
+            usdcMint,
           })
           .signers([platformOwner])
           .rpc();
@@ -375,13 +403,19 @@ describe("Security Tests", () => {
           new BN(1000000000),
           new BN(1000000),
           new BN(100000000),
-          "meta"
+          "meta",
+          defaultSaleParams
         )
         .accounts({
           creator: creator.publicKey,
           tokenMint: tokenMint,
           tokenSale,
           saleTokenAccount,
+          quoteMintEntry,
+          quoteMint: usdcMint,
+          saleEscrow: saleEscrowPda(tokenMint),
+          vestingVault: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
         })
         .signers([creator])
         .rpc();
@@ -398,7 +432,7 @@ describe("Security Tests", () => {
       // Try to buy with fake USDC
       try {
         await program.methods
-          .buyTokens(new BN(10000000))
+          .buyTokens(new BN(10000000), new BN(0), null, emptyProof as any, null, null)
           .accounts({
             buyer: attacker.publicKey,
             tokenSale,
@@ -407,10 +441,15 @@ describe("Security Tests", () => {
             buyerTokenAccount: attackerTokenAccount,
             buyerUsdcAccount: attackerFakeUsdcAccount, // FAKE USDC!
             programAuthority,
-            programUsdcAccount,
-            ownerUsdcAccount: platformOwnerUsdcAccount,
+            saleEscrow: saleEscrowPda(tokenMint),
+            feeRecipientUsdcAccount: platformOwnerUsdcAccount,
             creatorUsdcAccount,
             appState,
+            quoteMintEntry,
+            vestingVault: null,
+            referrer: null,
+            referrerUsdcAccount: null,
+            saleTokenProgram: TOKEN_PROGRAM_ID,
           })
           .signers([attacker])
           .rpc();
@@ -447,13 +486,19 @@ describe("Security Tests", () => {
           new BN(1000000000),
           new BN(1000000),
           new BN(100000000),
-          "meta2"
+          "meta2",
+          defaultSaleParams
         )
         .accounts({
           creator: creator.publicKey,
           tokenMint: tokenMint,
           tokenSale,
           saleTokenAccount,
+          quoteMintEntry,
+          quoteMint: usdcMint,
+          saleEscrow: saleEscrowPda(tokenMint),
+          vestingVault: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
         })
         .signers([creator])
         .rpc();
@@ -470,7 +515,7 @@ describe("Security Tests", () => {
       // Try to use buyer's USDC account while attacker signs
       try {
         await program.methods
-          .buyTokens(new BN(1000000))
+          .buyTokens(new BN(1000000), new BN(0), null, emptyProof as any, null, null)
           .accounts({
             buyer: attacker.publicKey, // Attacker signing
             tokenSale,
@@ -479,10 +524,15 @@ describe("Security Tests", () => {
             buyerTokenAccount: attackerTokenAccount,
             buyerUsdcAccount: buyerUsdcAccount, // But using buyer's USDC!
             programAuthority,
-            programUsdcAccount,
-            ownerUsdcAccount: platformOwnerUsdcAccount,
+            saleEscrow: saleEscrowPda(tokenMint),
+            feeRecipientUsdcAccount: platformOwnerUsdcAccount,
             creatorUsdcAccount,
             appState,
+            quoteMintEntry,
+            vestingVault: null,
+            referrer: null,
+            referrerUsdcAccount: null,
+            saleTokenProgram: TOKEN_PROGRAM_ID,
           })
           .signers([attacker])
           .rpc();
@@ -518,13 +568,19 @@ describe("Security Tests", () => {
             new BN("999999999999999"), // Way too large for 2 decimals
             new BN(1000000),
             new BN(1000),
-            "meta"
+            "meta",
+            defaultSaleParams
           )
           .accounts({
             creator: creator.publicKey,
             tokenMint: tokenMint,
             tokenSale,
             saleTokenAccount,
+            quoteMintEntry,
+            quoteMint: usdcMint,
+            saleEscrow: saleEscrowPda(tokenMint),
+            vestingVault: null,
+            tokenProgram: TOKEN_PROGRAM_ID,
           })
           .signers([creator])
           .rpc();
@@ -553,13 +609,19 @@ describe("Security Tests", () => {
           new BN(1000000000),
           new BN(1000000000000), // Very expensive: 1,000,000 USDC per token
           new BN(0),
-          "meta"
+          "meta",
+          defaultSaleParams
         )
         .accounts({
           creator: creator.publicKey,
           tokenMint: tokenMint,
           tokenSale,
           saleTokenAccount,
+          quoteMintEntry,
+          quoteMint: usdcMint,
+          saleEscrow: saleEscrowPda(tokenMint),
+          vestingVault: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
         })
         .signers([creator])
         .rpc();
@@ -576,7 +638,7 @@ describe("Security Tests", () => {
 
         // Try to buy with tiny amount (1 micro USDC)
         await program.methods
-          .buyTokens(new BN(1))
+          .buyTokens(new BN(1), new BN(0), null, emptyProof as any, null, null)
           .accounts({
             buyer: buyer.publicKey,
             tokenSale,
@@ -585,10 +647,15 @@ describe("Security Tests", () => {
             buyerTokenAccount,
             buyerUsdcAccount,
             programAuthority,
-            programUsdcAccount,
-            ownerUsdcAccount: platformOwnerUsdcAccount,
+            saleEscrow: saleEscrowPda(tokenMint),
+            feeRecipientUsdcAccount: platformOwnerUsdcAccount,
             creatorUsdcAccount,
             appState,
+            quoteMintEntry,
+            vestingVault: null,
+            referrer: null,
+            referrerUsdcAccount: null,
+            saleTokenProgram: TOKEN_PROGRAM_ID,
           })
           .signers([buyer])
           .rpc();
@@ -624,13 +691,19 @@ describe("Security Tests", () => {
           new BN(1000000000),
           new BN(1000000),
           new BN(100000000),
-          "meta"
+          "meta",
+          defaultSaleParams
         )
         .accounts({
           creator: creator.publicKey,
           tokenMint: tokenMint,
           tokenSale,
           saleTokenAccount,
+          quoteMintEntry,
+          quoteMint: usdcMint,
+          saleEscrow: saleEscrowPda(tokenMint),
+          vestingVault: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
         })
         .signers([creator])
         .rpc();
@@ -653,6 +726,7 @@ describe("Security Tests", () => {
           tokenMint: tokenMint,
           saleTokenAccount,
           creatorTokenAccount,
+          tokenProgram: TOKEN_PROGRAM_ID,
         })
         .signers([creator])
         .rpc();
@@ -669,7 +743,7 @@ describe("Security Tests", () => {
       // Try to buy after close
       try {
         await program.methods
-          .buyTokens(new BN(1000000))
+          .buyTokens(new BN(1000000), new BN(0), null, emptyProof as any, null, null)
           .accounts({
             buyer: buyer.publicKey,
             tokenSale,
@@ -678,10 +752,15 @@ describe("Security Tests", () => {
             buyerTokenAccount,
             buyerUsdcAccount,
             programAuthority,
-            programUsdcAccount,
-            ownerUsdcAccount: platformOwnerUsdcAccount,
+            saleEscrow: saleEscrowPda(tokenMint),
+            feeRecipientUsdcAccount: platformOwnerUsdcAccount,
             creatorUsdcAccount,
             appState,
+            quoteMintEntry,
+            vestingVault: null,
+            referrer: null,
+            referrerUsdcAccount: null,
+            saleTokenProgram: TOKEN_PROGRAM_ID,
           })
           .signers([buyer])
           .rpc();
@@ -713,13 +792,19 @@ describe("Security Tests", () => {
           new BN(1000000000),
           new BN(1000000),
           new BN(100000000),
-          "meta"
+          "meta",
+          defaultSaleParams
         )
         .accounts({
           creator: creator.publicKey,
           tokenMint: tokenMint,
           tokenSale,
           saleTokenAccount,
+          quoteMintEntry,
+          quoteMint: usdcMint,
+          saleEscrow: saleEscrowPda(tokenMint),
+          vestingVault: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
         })
         .signers([creator])
         .rpc();
@@ -742,6 +827,7 @@ describe("Security Tests", () => {
           tokenMint: tokenMint,
           saleTokenAccount,
           creatorTokenAccount,
+          tokenProgram: TOKEN_PROGRAM_ID,
         })
         .signers([creator])
         .rpc();
@@ -756,6 +842,7 @@ describe("Security Tests", () => {
             tokenMint: tokenMint,
             saleTokenAccount,
             creatorTokenAccount,
+            tokenProgram: TOKEN_PROGRAM_ID,
           })
           .signers([creator])
           .rpc();
@@ -782,13 +869,19 @@ describe("Security Tests", () => {
             new BN(1000000000),
             new BN(1000000),
             new BN(100000000),
-            longMetadata
+            longMetadata,
+            defaultSaleParams
           )
           .accounts({
             creator: creator.publicKey,
             tokenMint: tokenMint,
             tokenSale,
             saleTokenAccount,
+            quoteMintEntry,
+            quoteMint: usdcMint,
+            saleEscrow: saleEscrowPda(tokenMint),
+            vestingVault: null,
+            tokenProgram: TOKEN_PROGRAM_ID,
           })
           .signers([creator])
           .rpc();